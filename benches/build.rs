@@ -0,0 +1,54 @@
+//! Times `Grammar::build` on the built-in grammars.
+//!
+//! Run with `cargo bench`, optionally followed by the names of the grammars
+//! to time (see `pgen::this::by_name`).
+
+use std::env;
+use std::time::{Duration, Instant};
+
+const GRAMMARS: &[&str] = &["pgen", "sql", "c"];
+
+/// Keep sampling a grammar until both of these are reached.
+const MIN_RUNS: usize = 10;
+const MIN_TIME: Duration = Duration::from_secs(1);
+
+fn main() {
+    // cargo passes `--bench` along; everything else picks grammars
+    let names = env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .collect::<Vec<_>>();
+    let names = if names.is_empty() {
+        GRAMMARS.iter().map(|name| name.to_string()).collect()
+    } else {
+        names
+    };
+
+    for name in names {
+        if pgen::this::by_name(&name).is_none() {
+            eprintln!("Unknown grammar: {}", name);
+            continue;
+        }
+
+        let mut times = Vec::new();
+        let started = Instant::now();
+        while times.len() < MIN_RUNS || started.elapsed() < MIN_TIME {
+            let grammar = pgen::this::by_name(&name).unwrap();
+            let start = Instant::now();
+            let parser = grammar.build().unwrap();
+            times.push(start.elapsed());
+            drop(parser);
+        }
+
+        times.sort();
+        let total = times.iter().sum::<Duration>();
+        println!(
+            "build {:<6} runs: {:>5}  min: {:>12?}  median: {:>12?}  mean: {:>12?}",
+            name,
+            times.len(),
+            times[0],
+            times[times.len() / 2],
+            total / times.len() as u32,
+        );
+    }
+}
@@ -48,13 +48,15 @@ impl<K: Debug, V: Debug, H> Iterator for IntoIter<K, V, H> {
 
     fn next(&mut self) -> Option<Self::Item> {
         if let Some(node) = self.0.head {
-            let node_ptr = node.as_ptr();
-            self.0.head = unsafe { (*node_ptr).next };
-            let mut key = unsafe { *Box::from_raw(&mut (*node_ptr).key) };
-            let mut value = unsafe { *Box::from_raw(&mut (*node_ptr).value) };
-            debug_assert!(key.is_some(), "Key is none.");
-            debug_assert!(value.is_some(), "Value is none.");
-            Some((key.take().unwrap(), value.take().unwrap()))
+            let mut node = unsafe { Box::from_raw(node.as_ptr()) };
+            self.0.head = node.next;
+            match self.0.head {
+                Some(mut head) => unsafe { head.as_mut() }.prev = None,
+                None => self.0.tail = None,
+            }
+            debug_assert!(node.key.is_some(), "Key is none.");
+            debug_assert!(node.value.is_some(), "Value is none.");
+            Some((node.key.take().unwrap(), node.value.take().unwrap()))
         } else {
             None
         }
@@ -67,7 +69,7 @@ impl<K: Eq + Hash + Debug, V: Debug, H> Default for OrdHashMap<K, V, H> {
             head: None,
             tail: None,
             map: HashMap::new(),
-            _hasher: PhantomData::default(),
+            _hasher: PhantomData,
         }
     }
 }
@@ -110,12 +112,15 @@ impl<K: Eq + Hash + Debug, V: Debug + Eq, H: Hasher + Default> Index<&K> for Ord
 
 impl<K: Debug, V: Debug, H> Drop for OrdHashMap<K, V, H> {
     fn drop(&mut self) {
-        for (_, node_ptr) in self.map.iter_mut() {
-            let node = unsafe { node_ptr.as_ref() };
-            eprintln!("Freeing {:?} {:?}", node_ptr, node);
-            let boxed = unsafe { Box::from_raw(node_ptr) };
+        // walk the linked list rather than the map, since consuming iterators
+        // free nodes from the front without touching the map
+        let mut curr = self.head.take();
+        while let Some(node) = curr {
+            let node = unsafe { Box::from_raw(node.as_ptr()) };
+            curr = node.next;
         }
-        panic!("Dropped.");
+        self.tail = None;
+        self.map.clear();
     }
 }
 
@@ -144,9 +149,14 @@ impl<K: Eq + Hash + Debug, V: Debug + Eq, H: Hasher + Default> OrdHashMap<K, V,
         if let Some(node) = self.map.remove(&hash) {
             let mut node = unsafe { Box::from_raw(node.as_ptr()) };
 
-            // set the previous's next to be this next
-            if let Some(mut prev) = node.prev {
-                unsafe { prev.as_mut() }.next = node.next;
+            // unlink the node from its neighbors
+            match node.prev {
+                Some(mut prev) => unsafe { prev.as_mut() }.next = node.next,
+                None => self.head = node.next,
+            }
+            match node.next {
+                Some(mut next) => unsafe { next.as_mut() }.prev = node.prev,
+                None => self.tail = node.prev,
             }
             debug_assert!(node.value.is_some(), "Value is none.");
             node.value.take()
@@ -159,13 +169,9 @@ impl<K: Eq + Hash + Debug, V: Debug + Eq, H: Hasher + Default> OrdHashMap<K, V,
     ///
     /// The inserted item will always be the last regardless of whether or not the key existed previously.
     pub fn insert(&mut self, key: K, value: V) -> Option<V> {
-        // debug_assert!(self.check_validity("insert::begin"), "must be valid");
-        eprintln!("Inserting {:?}: {:?}", key, value);
-
         let mut h = H::default();
         key.hash(&mut h);
         let hash = h.finish();
-        println!("Hash of {:?} is {}", key, hash);
 
         let old_value = self.remove(&key);
 
@@ -180,24 +186,21 @@ impl<K: Eq + Hash + Debug, V: Debug + Eq, H: Hasher + Default> OrdHashMap<K, V,
         let mut node_ptr = unsafe { NonNull::new_unchecked(raw_boxed_node) };
 
         if let Some(ref mut tail) = self.tail {
-            (*unsafe { node_ptr.as_mut() }).prev = Some(*tail);
-            (*unsafe { tail.as_mut() }).next = Some(node_ptr);
+            unsafe { node_ptr.as_mut() }.prev = Some(*tail);
+            unsafe { tail.as_mut() }.next = Some(node_ptr);
         }
 
-        if let None = self.head {
+        if self.head.is_none() {
             self.head = Some(node_ptr);
         }
         self.tail = Some(node_ptr);
         self.map.insert(hash, node_ptr);
 
-        println!("Hashmap: {:?}", self.map);
-
         debug_assert!(self.check_validity("insert::end"), "must be valid");
 
         old_value
     }
 
-    #[cfg(debug_assertions)]
     fn check_validity(&self, _: impl AsRef<str>) -> bool {
         let mut curr = self.head;
         let mut new_map = self.map.clone();
@@ -244,7 +247,7 @@ impl<K: Eq + Hash + Debug, V: Debug + Eq, H: Hasher + Default> OrdHashMap<K, V,
         }
 
         debug_assert!(
-            new_map.len() == 0,
+            new_map.is_empty(),
             "New hash map has too many elements: {:?}",
             new_map
         );
@@ -263,12 +266,7 @@ impl<K: Eq + Hash + Debug, V: Debug + Eq> OrdHashMap<K, V, DefaultHasher> {
 impl<K: Eq + Hash + Debug, V: Debug, H> OrdHashMap<K, V, H> {
     /// Creates an iterator for the list
     pub fn iter<'a>(&'a self) -> Iter<'a, K, V> {
-        Iter(self.head, PhantomData::default())
-    }
-
-    /// Creates a consuming iterator for the list
-    pub fn into_iter(self) -> IntoIter<K, V, H> {
-        IntoIter(self)
+        Iter(self.head, PhantomData)
     }
 
     /// Get the length of the map
@@ -279,6 +277,21 @@ impl<K: Eq + Hash + Debug, V: Debug, H> OrdHashMap<K, V, H> {
         );
         self.map.len()
     }
+
+    /// Returns true if the map contains no elements
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<K: Eq + Hash + Debug, V: Debug, H> IntoIterator for OrdHashMap<K, V, H> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V, H>;
+
+    /// Creates a consuming iterator for the list
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
 }
 
 #[cfg(test)]
@@ -341,7 +354,7 @@ mod tests {
         assert_eq!(m2.len(), 2);
     }
 
-    thread_local! { static DROP_VECTOR: RefCell<Vec<i32>> = RefCell::new(Vec::new()) }
+    thread_local! { static DROP_VECTOR: RefCell<Vec<i32>> = const { RefCell::new(Vec::new()) } }
 
     #[derive(Hash, PartialEq, Eq)]
     struct Droppable {
@@ -0,0 +1,470 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use indexmap::IndexSet;
+use symbol::Symbol as Id;
+
+use crate::grammar::{GrammarError, Symbol};
+use crate::Grammar;
+
+/// Nullable, FIRST, FOLLOW, productive and reachable sets of a grammar.
+///
+/// FIRST sets follow the dragon book convention of containing
+/// `Symbol::Epsilon` when the symbol (or string of symbols) can derive the
+/// empty string.
+#[derive(Debug)]
+pub struct GrammarAnalysis {
+    /// Productions in production-number order, with `ɛ` removed
+    pub(crate) productions: Vec<(Id, Vec<Symbol>)>,
+    pub(crate) nullable: IndexSet<Id>,
+    pub(crate) first_sets: BTreeMap<Symbol, BTreeSet<Symbol>>,
+    pub(crate) follow_sets: BTreeMap<Symbol, BTreeSet<Symbol>>,
+    pub(crate) productive: IndexSet<Id>,
+    pub(crate) reachable: IndexSet<Id>,
+}
+
+impl Grammar {
+    /// Computes the nullable, FIRST, FOLLOW, productive and reachable sets.
+    pub fn analyze(&self) -> Result<GrammarAnalysis, GrammarError> {
+        let grammar_symbols = self.grammar_symbols()?;
+        self.check_symbols(&grammar_symbols)?;
+        let mut productions = Vec::new();
+        for (nonterminal, alternatives) in self.productions.iter() {
+            for production in alternatives {
+                productions.push((*nonterminal, production.symbols(&grammar_symbols)?));
+            }
+        }
+
+        let mut analysis = GrammarAnalysis {
+            productions,
+            nullable: IndexSet::new(),
+            first_sets: BTreeMap::new(),
+            follow_sets: BTreeMap::new(),
+            productive: IndexSet::new(),
+            reachable: IndexSet::new(),
+        };
+        analysis.compute_nullable();
+        analysis.compute_first_sets(self);
+        analysis.compute_follow_sets(self);
+        analysis.compute_productive();
+        analysis.compute_reachable(self);
+
+        // keep these in grammar order rather than discovery order
+        analysis.nullable = in_grammar_order(self, &analysis.nullable);
+        analysis.productive = in_grammar_order(self, &analysis.productive);
+        analysis.reachable = in_grammar_order(self, &analysis.reachable);
+        Ok(analysis)
+    }
+}
+
+impl GrammarAnalysis {
+    /// Whether the nonterminal can derive the empty string.
+    pub fn is_nullable(&self, nonterminal: Id) -> bool {
+        self.nullable.contains(&nonterminal)
+    }
+
+    /// The nonterminals that can derive the empty string.
+    pub fn nullable(&self) -> impl Iterator<Item = Id> + '_ {
+        self.nullable.iter().cloned()
+    }
+
+    /// The FIRST set of a single terminal or nonterminal.
+    pub fn first_set(&self, symbol: &Symbol) -> Option<&BTreeSet<Symbol>> {
+        self.first_sets.get(symbol)
+    }
+
+    /// The FIRST set of a string of symbols.
+    ///
+    /// Contains `Symbol::Epsilon` if every symbol in the string is nullable,
+    /// including when the string is empty.
+    pub fn first(&self, symbols: &[Symbol]) -> BTreeSet<Symbol> {
+        let mut result = BTreeSet::new();
+        for symbol in symbols {
+            match symbol {
+                Symbol::Epsilon => continue,
+                Symbol::EOF => {
+                    result.insert(Symbol::EOF);
+                    return result;
+                }
+                _ => (),
+            }
+            let first_set = match self.first_sets.get(symbol) {
+                Some(first_set) => first_set,
+                None => return result,
+            };
+            result.extend(first_set.iter().filter(|s| **s != Symbol::Epsilon).cloned());
+            if !first_set.contains(&Symbol::Epsilon) {
+                return result;
+            }
+        }
+        result.insert(Symbol::Epsilon);
+        result
+    }
+
+    /// The FOLLOW set of a nonterminal.
+    pub fn follow_set(&self, nonterminal: Id) -> Option<&BTreeSet<Symbol>> {
+        self.follow_sets.get(&Symbol::NT(nonterminal))
+    }
+
+    /// The PREDICT set of a production: the lookaheads an LL(1) parser picks
+    /// it on. That's FIRST of its right hand side, plus FOLLOW of its left
+    /// hand side if the right hand side is nullable.
+    pub fn predict_set(&self, production: usize) -> BTreeSet<Symbol> {
+        let (lhs, symbols) = &self.productions[production];
+        let mut predict_set = self.first(symbols);
+        if predict_set.remove(&Symbol::Epsilon) {
+            predict_set.extend(self.follow_sets[&Symbol::NT(*lhs)].iter().cloned());
+        }
+        predict_set
+    }
+
+    /// Whether the nonterminal can derive a string of terminals.
+    pub fn is_productive(&self, nonterminal: Id) -> bool {
+        self.productive.contains(&nonterminal)
+    }
+
+    /// The nonterminals that can derive a string of terminals.
+    pub fn productive(&self) -> impl Iterator<Item = Id> + '_ {
+        self.productive.iter().cloned()
+    }
+
+    /// Whether the nonterminal appears in some derivation from a start symbol.
+    pub fn is_reachable(&self, nonterminal: Id) -> bool {
+        self.reachable.contains(&nonterminal)
+    }
+
+    /// The nonterminals that appear in some derivation from a start symbol.
+    pub fn reachable(&self) -> impl Iterator<Item = Id> + '_ {
+        self.reachable.iter().cloned()
+    }
+
+    fn compute_nullable(&mut self) {
+        loop {
+            let mut changes = false;
+            for (nonterminal, symbols) in self.productions.iter() {
+                if self.nullable.contains(nonterminal) {
+                    continue;
+                }
+                let nullable = symbols.iter().all(|symbol| match symbol {
+                    Symbol::NT(name) => self.nullable.contains(name),
+                    _ => false,
+                });
+                if nullable {
+                    self.nullable.insert(*nonterminal);
+                    changes = true;
+                }
+            }
+
+            if !changes {
+                break;
+            }
+        }
+    }
+
+    fn compute_first_sets(&mut self, grammar: &Grammar) {
+        let terminals = grammar.terminals.keys().map(|terminal| Symbol::T(*terminal));
+        for terminal in terminals.chain(Some(Symbol::error())) {
+            self.first_sets
+                .insert(terminal.clone(), vec![terminal].into_iter().collect());
+        }
+        for nonterminal in grammar.productions.keys() {
+            let mut first_set = BTreeSet::new();
+            if self.nullable.contains(nonterminal) {
+                first_set.insert(Symbol::Epsilon);
+            }
+            self.first_sets.insert(Symbol::NT(*nonterminal), first_set);
+        }
+
+        loop {
+            let mut changes = false;
+            for (nonterminal, symbols) in self.productions.iter() {
+                // FIRST(X1 .. Xn) minus epsilon: keep going while the Xi are nullable
+                let mut new_symbols = BTreeSet::new();
+                for symbol in symbols {
+                    let first_set = &self.first_sets[symbol];
+                    new_symbols
+                        .extend(first_set.iter().filter(|s| **s != Symbol::Epsilon).cloned());
+                    if !first_set.contains(&Symbol::Epsilon) {
+                        break;
+                    }
+                }
+
+                let first_set = self.first_sets.get_mut(&Symbol::NT(*nonterminal)).unwrap();
+                if !first_set.is_superset(&new_symbols) {
+                    first_set.extend(new_symbols);
+                    changes = true;
+                }
+            }
+
+            if !changes {
+                break;
+            }
+        }
+    }
+
+    fn compute_follow_sets(&mut self, grammar: &Grammar) {
+        for nonterminal in grammar.productions.keys() {
+            self.follow_sets
+                .insert(Symbol::NT(*nonterminal), BTreeSet::new());
+        }
+        // put $ in FOLLOW(S) for every start symbol
+        for start_symbol in grammar.start_symbols.iter() {
+            self.follow_sets
+                .entry(Symbol::NT(*start_symbol))
+                .or_default()
+                .insert(Symbol::EOF);
+        }
+
+        loop {
+            let mut changes = false;
+            for (nonterminal, symbols) in self.productions.iter() {
+                for (i, symbol) in symbols.iter().enumerate() {
+                    if let Symbol::NT(_) = symbol {
+                        // if A -> aBb, then everything in FIRST(b) - e is in FOLLOW(B),
+                        // and if b is nullable, everything in FOLLOW(A) is too
+                        let mut new_symbols = self.first(&symbols[i + 1..]);
+                        if new_symbols.remove(&Symbol::Epsilon) {
+                            new_symbols.extend(self.follow_sets[&Symbol::NT(*nonterminal)].clone());
+                        }
+
+                        let follow_set = self.follow_sets.get_mut(symbol).unwrap();
+                        if !follow_set.is_superset(&new_symbols) {
+                            follow_set.extend(new_symbols);
+                            changes = true;
+                        }
+                    }
+                }
+            }
+
+            if !changes {
+                break;
+            }
+        }
+    }
+
+    fn compute_productive(&mut self) {
+        loop {
+            let mut changes = false;
+            for (nonterminal, symbols) in self.productions.iter() {
+                if self.productive.contains(nonterminal) {
+                    continue;
+                }
+                let productive = symbols.iter().all(|symbol| match symbol {
+                    Symbol::NT(name) => self.productive.contains(name),
+                    _ => true,
+                });
+                if productive {
+                    self.productive.insert(*nonterminal);
+                    changes = true;
+                }
+            }
+
+            if !changes {
+                break;
+            }
+        }
+    }
+
+    fn compute_reachable(&mut self, grammar: &Grammar) {
+        let mut stack = grammar
+            .start_symbols
+            .iter()
+            .filter(|symbol| grammar.productions.contains_key(*symbol))
+            .cloned()
+            .collect::<Vec<_>>();
+        while let Some(nonterminal) = stack.pop() {
+            if !self.reachable.insert(nonterminal) {
+                continue;
+            }
+            for (lhs, symbols) in self.productions.iter() {
+                if *lhs != nonterminal {
+                    continue;
+                }
+                for symbol in symbols {
+                    if let Symbol::NT(name) = symbol {
+                        if !self.reachable.contains(name) {
+                            stack.push(*name);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn in_grammar_order(grammar: &Grammar, set: &IndexSet<Id>) -> IndexSet<Id> {
+    grammar
+        .productions
+        .keys()
+        .filter(|nonterminal| set.contains(*nonterminal))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use symbol::Symbol as Id;
+
+    use crate::grammar::Symbol;
+    use crate::Grammar;
+
+    fn remove_terminals<V: Clone>(map: &BTreeMap<Symbol, V>) -> BTreeMap<Symbol, V> {
+        let mut map = map.clone();
+        let mut to_remove = BTreeSet::new();
+        for key in map.keys() {
+            if let Symbol::T(_) = key {
+                to_remove.insert(key.clone());
+            }
+        }
+        for key in to_remove {
+            map.remove(&key);
+        }
+        map
+    }
+
+    fn make_arith_1() -> Grammar {
+        make_grammar! {
+            start_symbols: [E],
+            terminals: {
+                Add: r"\+",
+                Mul: r"\*",
+                N0: r"0",
+                N1: r"1",
+            },
+            productions: {
+                E: [ [E, Mul, B], [E, Add, B], [B] ],
+                B: [ [N0], [N1] ],
+            }
+        }
+    }
+
+    #[test]
+    fn test_arith_1() {
+        use crate::grammar::Symbol::*;
+        let grammar = make_arith_1();
+        let analysis = grammar.analyze().unwrap();
+
+        // First sets
+        let actual_first_sets = remove_terminals(&analysis.first_sets);
+        let expected_first_sets = btreemap! {
+            NT(Id::from("E")) => btreeset!{ T(Id::from("N0")), T(Id::from("N1")), },
+            NT(Id::from("B")) => btreeset!{ T(Id::from("N0")), T(Id::from("N1")), },
+        };
+        assert!(
+            actual_first_sets.iter().eq(expected_first_sets.iter()),
+            "Expected: {:?}, Got: {:?}",
+            expected_first_sets,
+            actual_first_sets
+        );
+
+        // Follow sets
+        let actual_follow_sets = remove_terminals(&analysis.follow_sets);
+        let expected_follow_sets = btreemap! {
+            NT(Id::from("E")) => btreeset!{ T(Id::from("Add")), T(Id::from("Mul")), EOF, },
+            NT(Id::from("B")) => btreeset!{ T(Id::from("Add")), T(Id::from("Mul")), EOF, },
+        };
+        assert!(
+            actual_follow_sets.iter().eq(expected_follow_sets.iter()),
+            "Expected: {:?}, Got: {:?}",
+            expected_follow_sets,
+            actual_follow_sets
+        );
+    }
+
+    #[test]
+    fn test_epsilon_chains() {
+        use crate::grammar::Symbol::*;
+        let grammar = make_grammar! {
+            start_symbols: [E],
+            terminals: {
+                N: r"\d+",
+                Add: r"\+",
+                Mul: r"\*",
+                LP: r"\(",
+                RP: r"\)",
+            },
+            productions: {
+                E: [ [T, E_] ],
+                E_: [ [Add, T, E_], [ɛ] ],
+                T: [ [F, T_] ],
+                T_: [ [Mul, F, T_], [ɛ] ],
+                F: [ [LP, E, RP], [N] ],
+                // X -> Y Z only reaches N after seeing that both Y and Z are nullable
+                X: [ [Y, Z, N] ],
+                Y: [ [ɛ] ],
+                Z: [ [Y], [Mul] ],
+            }
+        };
+        let analysis = grammar.analyze().unwrap();
+        let id = Id::from;
+
+        assert_eq!(
+            analysis.nullable().collect::<Vec<_>>(),
+            vec![id("E_"), id("T_"), id("Y"), id("Z")]
+        );
+        assert_eq!(
+            analysis.first_set(&NT(id("E"))).unwrap(),
+            &btreeset! { T(id("N")), T(id("LP")) }
+        );
+        assert_eq!(
+            analysis.first_set(&NT(id("E_"))).unwrap(),
+            &btreeset! { T(id("Add")), Epsilon }
+        );
+        assert_eq!(
+            analysis.first_set(&NT(id("X"))).unwrap(),
+            &btreeset! { T(id("Mul")), T(id("N")) }
+        );
+        assert_eq!(
+            analysis.first(&[NT(id("Y")), NT(id("Z"))]),
+            btreeset! { T(id("Mul")), Epsilon }
+        );
+        assert_eq!(analysis.first(&[]), btreeset! { Epsilon });
+
+        assert_eq!(
+            analysis.follow_set(id("E_")).unwrap(),
+            &btreeset! { T(id("RP")), EOF }
+        );
+        assert_eq!(
+            analysis.follow_set(id("T")).unwrap(),
+            &btreeset! { T(id("Add")), T(id("RP")), EOF }
+        );
+        assert_eq!(
+            analysis.follow_set(id("F")).unwrap(),
+            &btreeset! { T(id("Add")), T(id("Mul")), T(id("RP")), EOF }
+        );
+        assert_eq!(
+            analysis.follow_set(id("Y")).unwrap(),
+            &btreeset! { T(id("Mul")), T(id("N")) }
+        );
+    }
+
+    #[test]
+    fn test_productive_and_reachable() {
+        let grammar = make_grammar! {
+            start_symbols: [S],
+            terminals: {
+                A: r"a",
+            },
+            productions: {
+                S: [ [A], [L] ],
+                // L never bottoms out
+                L: [ [A, L] ],
+                U: [ [A] ],
+            }
+        };
+        let analysis = grammar.analyze().unwrap();
+        let id = Id::from;
+
+        assert_eq!(
+            analysis.productive().collect::<Vec<_>>(),
+            vec![id("S"), id("U")]
+        );
+        assert!(!analysis.is_productive(id("L")));
+        assert_eq!(
+            analysis.reachable().collect::<Vec<_>>(),
+            vec![id("S"), id("L")]
+        );
+        assert!(!analysis.is_reachable(id("U")));
+    }
+}
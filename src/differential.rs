@@ -0,0 +1,123 @@
+//! Differential tests: every parser built from a grammar has to accept and
+//! reject the same inputs as the Earley parser, which shares none of their
+//! tables, for random sentences of the grammar and mutations of them.
+
+use proptest::prelude::*;
+use proptest::test_runner::{Config, TestCaseError, TestRunner};
+
+use crate::runtime;
+use crate::tables::TableLayout;
+use crate::testing::inputs;
+use crate::{BuildOptions, DefaultReductions, EarleyParser, Grammar, LLParser, Parser};
+
+/// Every way of parsing a grammar: LR parsers with each kind of default
+/// reductions, run by the interpreter, by the runtime on both table layouts
+/// (which is what generated table parsers do), and as GLR, an LL parser if
+/// the grammar is LL(1), and the Earley parser.
+struct Parsers {
+    start: String,
+    lr: Vec<Parser>,
+    ll: Option<LLParser>,
+    earley: EarleyParser,
+}
+
+impl Parsers {
+    fn new(grammar: &Grammar, start: &str) -> Self {
+        let lr = [
+            DefaultReductions::None,
+            DefaultReductions::Consistent,
+            DefaultReductions::All,
+        ]
+        .iter()
+        .map(|mode| {
+            let options = BuildOptions {
+                default_reductions: *mode,
+                ..BuildOptions::default()
+            };
+            grammar.clone().build_with(&options).unwrap()
+        })
+        .collect();
+        Parsers {
+            start: start.to_owned(),
+            lr,
+            ll: grammar.clone().build_ll().ok(),
+            earley: grammar.clone().build_earley().unwrap(),
+        }
+    }
+
+    /// Checks that every parser accepts the input if the Earley parser does,
+    /// and that the LR parsers agree on the tree or the error.
+    fn check(&self, input: &str) -> Result<bool, TestCaseError> {
+        let start = self.start.as_str();
+        let accepted = self.earley.recognize(start, input).is_ok();
+        for parser in self.lr.iter() {
+            let interpreted = parser.interpret(start, input);
+            prop_assert_eq!(interpreted.is_ok(), accepted, "interpreter on {:?}", input);
+            prop_assert_eq!(
+                parser.parse_glr(start, input).is_ok(),
+                accepted,
+                "GLR on {:?}",
+                input
+            );
+
+            let lexer = parser.lexer();
+            let productions = parser.production_shapes();
+            let start_state = parser.start_state(start).unwrap();
+            for layout in &[TableLayout::Dense, TableLayout::Comb] {
+                let tables = parser.pack_tables(*layout);
+                let parsed = runtime::parse(&tables, &productions, &lexer, start_state, input);
+                prop_assert_eq!(&parsed, &interpreted, "{:?} tables on {:?}", layout, input);
+            }
+        }
+        if let Some(ll) = &self.ll {
+            let parsed = ll.parse(start, input);
+            prop_assert_eq!(parsed.is_ok(), accepted, "LL on {:?}", input);
+        }
+        Ok(accepted)
+    }
+}
+
+/// Runs the parsers of a grammar on random inputs, checking that valid ones
+/// are accepted and that they all agree on mutated ones.
+fn check_grammar(grammar: Grammar, start: &str) {
+    let parsers = Parsers::new(&grammar, start);
+    run(&inputs(&grammar, start, 0), |input| {
+        prop_assert!(parsers.check(&input)?, "rejected {:?}", input);
+        Ok(())
+    });
+    run(&inputs(&grammar, start, 3), |input| {
+        parsers.check(&input).map(|_| ())
+    });
+}
+
+/// Runs a test on 64 cases, panicking with the smallest failing one.
+fn run<S: Strategy>(strategy: &S, test: impl Fn(S::Value) -> Result<(), TestCaseError>) {
+    let config = Config {
+        cases: 64,
+        failure_persistence: None,
+        ..Config::default()
+    };
+    if let Err(error) = TestRunner::new(config).run(strategy, test) {
+        panic!("{}", error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_grammar;
+
+    #[test]
+    fn test_pgen_grammar() {
+        check_grammar(crate::this::pgen_grammar(), "E");
+    }
+
+    #[test]
+    fn test_ll_grammar() {
+        check_grammar(crate::this::ll_grammar(), "E");
+    }
+
+    #[test]
+    fn test_stmts_grammar() {
+        check_grammar(crate::this::stmts_grammar(), "Stmts");
+    }
+}
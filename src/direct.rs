@@ -0,0 +1,307 @@
+use std::io::{self, Write};
+
+use crate::grammar::{symbol_list, Symbol};
+use crate::parser::Action;
+use crate::Parser;
+
+impl Parser {
+    /// Writes a function for every state of the direct-coded backend, and a
+    /// `STATES` constant to dispatch on the state on top of the stack.
+    ///
+    /// Every state function makes one move on a `pgen::runtime::Stack`,
+    /// matching on the lookahead's terminal number, and returns whether the
+    /// input was accepted. Gotos get a function per nonterminal, and error
+    /// recovery gets a `shift_error` and an `accepts` function.
+    ///
+    /// For what can come next, every state also gets an `expected_N`
+    /// function, dispatched through `EXPECTED`, that makes the reductions a
+    /// terminal calls for on a stack of states and says whether the state
+    /// they end in takes it. A `has_goto` function tells which nonterminals
+    /// that state can go on to.
+    pub(crate) fn write_states(&self, mut w: impl Write) -> io::Result<()> {
+        let terminals = self.terminal_columns();
+        for (i, (action, _)) in self.table_iter() {
+            // group the terminals that get the same action into one arm
+            let mut arms: Vec<(Action, Vec<usize>)> = Vec::new();
+            for (column, terminal) in terminals.iter().enumerate() {
+                if let Some(action) = action.get(terminal) {
+                    match arms.iter_mut().find(|(other, _)| other == action) {
+                        Some((_, columns)) => columns.push(column),
+                        None => arms.push((action.clone(), vec![column])),
+                    }
+                }
+            }
+
+            writeln!(w, "// State {}", i)?;
+            for item in self.states[i].iter() {
+                writeln!(w, "//   {}", item)?;
+            }
+            writeln!(
+                w,
+                "fn state_{}(stack: &mut Stack<'_>) -> Result<bool, ParseError> {{",
+                i
+            )?;
+            let mut expected = arms
+                .iter()
+                .flat_map(|(_, columns)| columns.iter().cloned())
+                .collect::<Vec<_>>();
+            expected.sort_unstable();
+            let default = self.default_reductions[i];
+            if let (true, Some(n)) = (arms.is_empty(), default) {
+                // a consistent state reduces without looking at the lookahead
+                self.write_move(&mut w, &Action::Reduce(n))?;
+            } else {
+                writeln!(w, "    match stack.lookahead()? {{")?;
+                for (action, columns) in arms.iter() {
+                    let names = columns
+                        .iter()
+                        .map(|column| terminals[*column].name().to_string())
+                        .collect::<Vec<_>>();
+                    let columns = columns.iter().map(usize::to_string).collect::<Vec<_>>();
+                    writeln!(w, "        // {}", names.join(" | "))?;
+                    writeln!(w, "        {} => {{", columns.join(" | "))?;
+                    self.write_move(&mut w, action)?;
+                    writeln!(w, "        }}")?;
+                }
+                writeln!(w, "        _ => {{")?;
+                match default {
+                    Some(n) => self.write_move(&mut w, &Action::Reduce(n))?,
+                    None => {
+                        let expected = expected.iter().map(usize::to_string).collect::<Vec<_>>();
+                        writeln!(
+                            w,
+                            "            return Err(stack.error(&[{}]));",
+                            expected.join(", ")
+                        )?
+                    }
+                }
+                writeln!(w, "        }}")?;
+                writeln!(w, "    }}")?;
+            }
+            writeln!(w, "    Ok(false)")?;
+            writeln!(w, "}}")?;
+
+            writeln!(
+                w,
+                "fn expected_{}(stack: &mut Vec<usize>, terminal: usize) -> bool {{",
+                i
+            )?;
+            if let (true, Some(n)) = (arms.is_empty(), default) {
+                self.write_expected_reduction(&mut w, n)?;
+            } else {
+                // every shift takes the terminal, whatever state it goes to
+                let mut takes = arms
+                    .iter()
+                    .filter(|(action, _)| !matches!(action, Action::Reduce(_)))
+                    .flat_map(|(_, columns)| columns.iter().cloned())
+                    .collect::<Vec<_>>();
+                takes.sort_unstable();
+                writeln!(w, "    match terminal {{")?;
+                if !takes.is_empty() {
+                    let takes = takes.iter().map(usize::to_string).collect::<Vec<_>>();
+                    writeln!(w, "        {} => true,", takes.join(" | "))?;
+                }
+                for (action, columns) in arms.iter() {
+                    if let Action::Reduce(n) = action {
+                        let columns = columns.iter().map(usize::to_string).collect::<Vec<_>>();
+                        writeln!(w, "        {} => {{", columns.join(" | "))?;
+                        self.write_expected_reduction(&mut w, *n)?;
+                        writeln!(w, "        }}")?;
+                    }
+                }
+                match default {
+                    Some(n) => {
+                        writeln!(w, "        _ => {{")?;
+                        self.write_expected_reduction(&mut w, n)?;
+                        writeln!(w, "        }}")?;
+                    }
+                    None => writeln!(w, "        _ => false,")?,
+                }
+                writeln!(w, "    }}")?;
+            }
+            writeln!(w, "}}")?;
+        }
+
+        for nonterminal in self.nonterminals.iter() {
+            let symbol = Symbol::NT(*nonterminal);
+            writeln!(w, "fn goto_{}(state: usize) -> usize {{", nonterminal)?;
+            writeln!(w, "    match state {{")?;
+            for (i, (_, goto)) in self.table_iter() {
+                if let Some(next) = goto.get(&symbol) {
+                    writeln!(w, "        {} => {},", i, next)?;
+                }
+            }
+            writeln!(w, "        _ => unreachable!(),")?;
+            writeln!(w, "    }}")?;
+            writeln!(w, "}}")?;
+        }
+
+        writeln!(
+            w,
+            "fn has_goto(state: usize, nonterminal: usize) -> bool {{"
+        )?;
+        writeln!(w, "    match state {{")?;
+        for (i, (_, goto)) in self.table_iter() {
+            let nonterminals = (0..self.nonterminals.len())
+                .filter(|n| goto.contains_key(&Symbol::NT(self.nonterminals[*n])))
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>();
+            if !nonterminals.is_empty() {
+                writeln!(
+                    w,
+                    "        {} => matches!(nonterminal, {}),",
+                    i,
+                    nonterminals.join(" | ")
+                )?;
+            }
+        }
+        writeln!(w, "        _ => false,")?;
+        writeln!(w, "    }}")?;
+        writeln!(w, "}}")?;
+
+        // what error recovery needs to know about the states
+        writeln!(w, "fn shift_error(state: usize) -> Option<usize> {{")?;
+        writeln!(w, "    match state {{")?;
+        for (i, (action, _)) in self.table_iter() {
+            if let Some(Action::Shift(next)) = action.get(&Symbol::error()) {
+                writeln!(w, "        {} => Some({}),", i, next)?;
+            }
+        }
+        writeln!(w, "        _ => None,")?;
+        writeln!(w, "    }}")?;
+        writeln!(w, "}}")?;
+        writeln!(w, "fn accepts(state: usize, terminal: usize) -> bool {{")?;
+        writeln!(w, "    match state {{")?;
+        for (i, (action, _)) in self.table_iter() {
+            if self.default_reductions[i].is_some() {
+                writeln!(w, "        {} => true,", i)?;
+                continue;
+            }
+            let columns = (0..terminals.len())
+                .filter(|column| action.contains_key(&terminals[*column]))
+                .map(|column| column.to_string())
+                .collect::<Vec<_>>();
+            if !columns.is_empty() {
+                writeln!(w, "        {} => matches!(terminal, {}),", i, columns.join(" | "))?;
+            }
+        }
+        writeln!(w, "        _ => false,")?;
+        writeln!(w, "    }}")?;
+        writeln!(w, "}}")?;
+
+        writeln!(
+            w,
+            "const STATES: &[fn(&mut Stack<'_>) -> Result<bool, ParseError>] = &["
+        )?;
+        for i in 0..self.table.0.len() {
+            writeln!(w, "    state_{},", i)?;
+        }
+        writeln!(w, "];")?;
+        writeln!(
+            w,
+            "const EXPECTED: &[fn(&mut Vec<usize>, usize) -> bool] = &["
+        )?;
+        for i in 0..self.table.0.len() {
+            writeln!(w, "    expected_{},", i)?;
+        }
+        writeln!(w, "];")?;
+        Ok(())
+    }
+
+    fn write_move(&self, mut w: impl Write, action: &Action) -> io::Result<()> {
+        match action {
+            Action::Shift(next) => writeln!(w, "            stack.shift({})?;", next),
+            Action::Reduce(n) => {
+                let (lhs, symbols) = &self.productions[*n];
+                writeln!(
+                    w,
+                    "            // reduce {} -> {}",
+                    lhs,
+                    symbol_list(symbols)
+                )?;
+                writeln!(
+                    w,
+                    "            let uncovered = stack.reduce({}, {});",
+                    n,
+                    symbols.len()
+                )?;
+                writeln!(w, "            stack.goto(goto_{}(uncovered));", lhs)
+            }
+            Action::Accept => writeln!(w, "            return Ok(true);"),
+        }
+    }
+
+    /// Writes a reduction on the stack of states `expected_N` is given,
+    /// going on to ask the state it ends in.
+    fn write_expected_reduction(&self, mut w: impl Write, n: usize) -> io::Result<()> {
+        let (lhs, symbols) = &self.productions[n];
+        writeln!(
+            w,
+            "            // reduce {} -> {}",
+            lhs,
+            symbol_list(symbols)
+        )?;
+        writeln!(
+            w,
+            "            stack.truncate(stack.len() - {});",
+            symbols.len()
+        )?;
+        writeln!(w, "            let uncovered = *stack.last().unwrap();")?;
+        writeln!(w, "            stack.push(goto_{}(uncovered));", lhs)?;
+        writeln!(
+            w,
+            "            EXPECTED[*stack.last().unwrap()](stack, terminal)"
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Backend, BuildOptions, CodegenOptions, DefaultReductions, Grammar};
+
+    #[test]
+    fn test_direct_codegen() {
+        let grammar = make_grammar! {
+            start_symbols: [E],
+            terminals: {
+                Add: r"\+",
+                N: r"1",
+            },
+            productions: {
+                E: [ [E, Add, T], [T] ],
+                T: [ [N] ],
+            }
+        };
+        let options = BuildOptions {
+            default_reductions: DefaultReductions::Consistent,
+            ..BuildOptions::default()
+        };
+        let parser = grammar.build_with(&options).unwrap();
+        let mut buf = Vec::new();
+        let options = CodegenOptions {
+            backend: Backend::Direct,
+            ..CodegenOptions::default()
+        };
+        parser.codegen_with(&mut buf, &options).unwrap();
+        let code = String::from_utf8(buf).unwrap();
+
+        for i in 0..parser.states.len() {
+            assert!(code.contains(&format!("    state_{},\n", i)));
+            assert!(code.contains(&format!("    expected_{},\n", i)));
+        }
+        assert!(!code.contains("TABLES"));
+
+        // T -> N . is consistent, so it doesn't match on the lookahead
+        let state = parser.states.iter().position(|items| {
+            items
+                .iter()
+                .any(|item| item.to_string() == "T -> N \u{2022}")
+        });
+        assert!(code.contains(&format!(
+            "fn state_{}(stack: &mut Stack<'_>) -> Result<bool, ParseError> {{\n            // reduce T -> N\n",
+            state.unwrap()
+        )));
+        assert!(code.contains("        // $\n        2 => {\n            return Ok(true);\n"));
+        assert!(code.contains("fn goto_T(state: usize) -> usize {"));
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::BTreeSet;
+use std::io::{self, Write};
+
+use crate::parser::Action;
+use crate::Parser;
+
+impl Parser {
+    /// Writes the LR automaton as a Graphviz DOT graph.
+    ///
+    /// Every state becomes a node listing its items, and every shift or goto
+    /// becomes an edge labeled with the symbol it's taken on. States that can
+    /// reduce are shaded, accepting states get a double border, and states
+    /// with conflicts are colored (orange for shift/reduce, red for
+    /// reduce/reduce).
+    pub fn write_dot(&self, mut w: impl Write) -> io::Result<()> {
+        writeln!(w, "digraph automaton {{")?;
+        writeln!(w, "    rankdir=LR;")?;
+        writeln!(w, "    node [shape=box, fontname=monospace];")?;
+        writeln!(w, "    edge [fontname=monospace];")?;
+
+        for (i, (action, goto)) in self.table_iter() {
+            let mut label = format!("State {}\\l", i);
+            for item in self.states[i].iter() {
+                label += &escape(&item.to_string());
+                label += "\\l";
+            }
+
+            let mut attrs = vec![format!("label=\"{}\"", label)];
+            let conflicts = self
+                .conflicts
+                .iter()
+                .filter(|conflict| conflict.state == i)
+                .collect::<Vec<_>>();
+            if !conflicts.is_empty() {
+                let color = if conflicts.iter().any(|conflict| conflict.is_shift_reduce()) {
+                    "orange"
+                } else {
+                    "red"
+                };
+                attrs.push(format!("style=filled, fillcolor={}", color));
+            } else if self.default_reductions[i].is_some()
+                || action.values().any(|a| matches!(a, Action::Reduce(_)))
+            {
+                attrs.push("style=filled, fillcolor=lightblue".to_owned());
+            }
+            if action.values().any(|a| *a == Action::Accept) {
+                attrs.push("peripheries=2".to_owned());
+            }
+            writeln!(w, "    state{} [{}];", i, attrs.join(", "))?;
+
+            // sort the edges so the output doesn't depend on hash order
+            let edges = action
+                .iter()
+                .filter_map(|(symbol, action)| match action {
+                    Action::Shift(n) => Some((symbol, *n)),
+                    _ => None,
+                })
+                .chain(goto.iter().map(|(symbol, n)| (symbol, *n)))
+                .collect::<BTreeSet<_>>();
+            for (symbol, n) in edges {
+                writeln!(
+                    w,
+                    "    state{} -> state{} [label=\"{}\"];",
+                    i,
+                    n,
+                    escape(symbol.name().as_str())
+                )?;
+            }
+        }
+
+        writeln!(w, "}}")?;
+        Ok(())
+    }
+}
+
+/// Escapes a string for use inside a double-quoted DOT label.
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Grammar;
+
+    fn dot(grammar: Grammar) -> String {
+        let parser = grammar.build().unwrap();
+        let mut buf = Vec::new();
+        parser.write_dot(&mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_arith_dot() {
+        let output = dot(make_grammar! {
+            start_symbols: [E],
+            terminals: {
+                Add: r"\+",
+                N: r"1",
+            },
+            productions: {
+                E: [ [E, Add, B], [B] ],
+                B: [ [N] ],
+            }
+        });
+
+        assert!(output.starts_with("digraph automaton {"));
+        assert!(output.contains("E' -> \u{2022} E\\l"));
+        assert!(output.contains("E -> E Add \u{2022} B\\l"));
+        assert!(output.contains("[label=\"Add\"]"));
+        assert!(output.contains("[label=\"B\"]"));
+        assert!(output.contains("peripheries=2"));
+        assert!(output.contains("fillcolor=lightblue"));
+        assert!(!output.contains("fillcolor=orange"));
+    }
+
+    #[test]
+    fn test_conflict_dot() {
+        // E -> E + E is ambiguous, so there's a shift/reduce conflict on Add
+        let output = dot(make_grammar! {
+            start_symbols: [E],
+            terminals: {
+                Add: r"\+",
+                N: r"1",
+            },
+            productions: {
+                E: [ [E, Add, E], [N] ],
+            }
+        });
+
+        assert!(output.contains("fillcolor=orange"));
+    }
+}
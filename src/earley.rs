@@ -0,0 +1,498 @@
+//! Earley parsing, straight off a `Grammar` with no tables to build, for any
+//! context-free grammar at all.
+//!
+//! Recognizing fills in a chart with an item set per token boundary, and the
+//! parse forest is read back out of the chart afterwards.
+
+use std::collections::{HashMap, HashSet};
+
+use indexmap::IndexMap;
+use symbol::Symbol as Id;
+
+use crate::analysis::GrammarAnalysis;
+use crate::glr::{Family, Forest, ForestNode, NodeId};
+use crate::grammar::{GrammarError, Symbol};
+use crate::runtime::{Lexer, ParseError};
+use crate::Grammar;
+
+/// A parser for any grammar, ambiguous or not, without the lints or conflict
+/// checks of `Grammar::build`.
+#[derive(Debug)]
+pub struct EarleyParser {
+    pub(crate) start_symbols: Vec<Id>,
+    pub(crate) terminals: IndexMap<Id, String>,
+    pub(crate) productions: Vec<(Id, Vec<Symbol>)>,
+    pub(crate) analysis: GrammarAnalysis,
+}
+
+/// A production with a dot in it, and the token boundary it was predicted at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Item {
+    production: usize,
+    dot: usize,
+    origin: usize,
+}
+
+/// A token of the input: its terminal number and the bytes it covers.
+#[derive(Clone, Copy, Debug)]
+struct Token {
+    terminal: usize,
+    start: usize,
+    end: usize,
+}
+
+/// The item sets of a recognized input, used to build its forest.
+struct Chart<'a> {
+    parser: &'a EarleyParser,
+    input: &'a str,
+    tokens: Vec<Token>,
+    sets: Vec<HashSet<Item>>,
+    nodes: Vec<ForestNode>,
+    symbol_nodes: HashMap<(Id, usize, usize), NodeId>,
+    leaves: HashMap<usize, NodeId>,
+}
+
+impl Grammar {
+    /// Builds an Earley parser, only checking that the symbols make sense.
+    pub fn build_earley(self) -> Result<EarleyParser, GrammarError> {
+        let grammar_symbols = self.grammar_symbols()?;
+        self.check_start_symbols(&grammar_symbols)?;
+        self.check_patterns()?;
+        let analysis = self.analyze()?;
+        Ok(EarleyParser {
+            start_symbols: self.start_symbols,
+            terminals: self.terminals,
+            productions: analysis.productions.clone(),
+            analysis,
+        })
+    }
+}
+
+impl EarleyParser {
+    /// The nullable, FIRST and FOLLOW sets of the grammar.
+    pub fn analysis(&self) -> &GrammarAnalysis {
+        &self.analysis
+    }
+
+    /// Whether the input is a sentence of the start symbol.
+    pub fn recognize(
+        &self,
+        start_symbol: impl AsRef<str>,
+        input: impl AsRef<str>,
+    ) -> Result<(), ParseError> {
+        let start_symbol = Id::from(start_symbol.as_ref());
+        self.chart(start_symbol, input.as_ref()).map(|_| ())
+    }
+
+    /// Parses the input, returning the forest of every parse. The trees are
+    /// numbered like the ones from the LR parser built from the same grammar.
+    pub fn parse(
+        &self,
+        start_symbol: impl AsRef<str>,
+        input: impl AsRef<str>,
+    ) -> Result<Forest, ParseError> {
+        let start_symbol = Id::from(start_symbol.as_ref());
+        let mut chart = self.chart(start_symbol, input.as_ref())?;
+        let end = chart.tokens.len();
+        let root = chart.symbol_node(start_symbol, 0, end);
+        Ok(Forest {
+            nodes: chart.nodes,
+            root,
+        })
+    }
+
+    /// Lexes as much of the input as possible, along with the error where
+    /// no terminal matches, if there is one.
+    fn lex(&self, input: &str) -> (Vec<Token>, Option<ParseError>) {
+        let lexer = Lexer::for_terminals(&self.terminals);
+        let mut tokens = Vec::new();
+        let mut position = 0;
+        loop {
+            let (terminal, end) = match lexer.next(input, position) {
+                Some(token) => token,
+                None => return (tokens, Some(ParseError::unlexable(input, position))),
+            };
+            if terminal == lexer.eof() {
+                return (tokens, None);
+            }
+            tokens.push(Token {
+                terminal,
+                start: position,
+                end,
+            });
+            position = end;
+        }
+    }
+
+    /// The terminal number of a symbol, if it's a terminal.
+    fn terminal(&self, symbol: &Symbol) -> Option<usize> {
+        match symbol {
+            Symbol::T(name) => self.terminals.get_full(name).map(|(i, _, _)| i),
+            _ => None,
+        }
+    }
+
+    /// Runs the recognizer, failing at the first token no item can scan.
+    fn chart<'a>(&'a self, start_symbol: Id, input: &'a str) -> Result<Chart<'a>, ParseError> {
+        assert!(
+            self.start_symbols.contains(&start_symbol),
+            "not a start symbol"
+        );
+        let (tokens, unlexable) = self.lex(input);
+        let mut sets: Vec<HashSet<Item>> = vec![HashSet::new(); tokens.len() + 1];
+        let mut queue = self.predict(start_symbol, 0);
+        for i in 0..=tokens.len() {
+            let mut scanned = Vec::new();
+            while let Some(item) = queue.pop() {
+                if !sets[i].insert(item) {
+                    continue;
+                }
+                let symbols = &self.productions[item.production].1;
+                let advanced = Item {
+                    dot: item.dot + 1,
+                    ..item
+                };
+                match symbols.get(item.dot) {
+                    // complete
+                    None => {
+                        let lhs = self.productions[item.production].0;
+                        for waiting in sets[item.origin].iter() {
+                            let symbols = &self.productions[waiting.production].1;
+                            if symbols.get(waiting.dot) == Some(&Symbol::NT(lhs)) {
+                                queue.push(Item {
+                                    dot: waiting.dot + 1,
+                                    ..*waiting
+                                });
+                            }
+                        }
+                    }
+                    // predict, and skip over nullable nonterminals right away
+                    // since their completions may already have happened
+                    Some(Symbol::NT(nonterminal)) => {
+                        queue.extend(self.predict(*nonterminal, i));
+                        if self.analysis.is_nullable(*nonterminal) {
+                            queue.push(advanced);
+                        }
+                    }
+                    // scan
+                    Some(symbol) => {
+                        let token = tokens.get(i).map(|token| token.terminal);
+                        if token.is_some() && token == self.terminal(symbol) {
+                            scanned.push(advanced);
+                        }
+                    }
+                }
+            }
+            if i < tokens.len() && scanned.is_empty() {
+                let token = tokens[i];
+                let expected = self.expected(&sets[i], start_symbol);
+                let span = token.start..token.end;
+                return Err(ParseError::new(input, span, Some(token.terminal), expected));
+            }
+            queue = scanned;
+        }
+
+        // the input only got as far as the first token that doesn't lex
+        if let Some(error) = unlexable {
+            return Err(error);
+        }
+        let expected = self.expected(&sets[tokens.len()], start_symbol);
+        let eof = self.terminals.len();
+        if !expected.contains(&eof) {
+            let span = input.len()..input.len();
+            return Err(ParseError::new(input, span, Some(eof), expected));
+        }
+        Ok(Chart {
+            parser: self,
+            input,
+            tokens,
+            sets,
+            nodes: Vec::new(),
+            symbol_nodes: HashMap::new(),
+            leaves: HashMap::new(),
+        })
+    }
+
+    /// The terminals some item in the set can scan next, and the end of input
+    /// if the start symbol has been completed from the start.
+    fn expected(&self, set: &HashSet<Item>, start_symbol: Id) -> Vec<usize> {
+        let mut expected = set
+            .iter()
+            .filter_map(|item| {
+                let (lhs, symbols) = &self.productions[item.production];
+                match symbols.get(item.dot) {
+                    Some(symbol) => self.terminal(symbol),
+                    None if *lhs == start_symbol && item.origin == 0 => Some(self.terminals.len()),
+                    None => None,
+                }
+            })
+            .collect::<Vec<_>>();
+        expected.sort_unstable();
+        expected.dedup();
+        expected
+    }
+
+    fn predict(&self, nonterminal: Id, origin: usize) -> Vec<Item> {
+        self.productions
+            .iter()
+            .enumerate()
+            .filter(|(_, (lhs, _))| *lhs == nonterminal)
+            .map(|(production, _)| Item {
+                production,
+                dot: 0,
+                origin,
+            })
+            .collect()
+    }
+}
+
+impl<'a> Chart<'a> {
+    /// The byte offset of a token boundary.
+    fn offset(&self, i: usize) -> usize {
+        match self.tokens.get(i) {
+            Some(token) => token.start,
+            None => self.input.len(),
+        }
+    }
+
+    /// The forest node for a nonterminal deriving tokens `start..end`.
+    ///
+    /// The node is made before its families are, so cycles in the grammar
+    /// come back around to it instead of going on forever.
+    fn symbol_node(&mut self, nonterminal: Id, start: usize, end: usize) -> NodeId {
+        if let Some(id) = self.symbol_nodes.get(&(nonterminal, start, end)) {
+            return *id;
+        }
+        let id = self.nodes.len();
+        self.nodes.push(ForestNode::Symbol {
+            nonterminal,
+            start: self.offset(start),
+            end: self.offset(end),
+            families: Vec::new(),
+        });
+        self.symbol_nodes.insert((nonterminal, start, end), id);
+
+        let parser = self.parser;
+        let mut families = Vec::new();
+        for (production, (lhs, symbols)) in parser.productions.iter().enumerate() {
+            let complete = Item {
+                production,
+                dot: symbols.len(),
+                origin: start,
+            };
+            if *lhs == nonterminal && self.sets[end].contains(&complete) {
+                for children in self.children(complete, end) {
+                    families.push(Family {
+                        production,
+                        children,
+                    });
+                }
+            }
+        }
+        if let ForestNode::Symbol { families: f, .. } = &mut self.nodes[id] {
+            *f = families;
+        }
+        id
+    }
+
+    /// Every way of splitting up the symbols before the dot of an item in set
+    /// `end`, as the forest nodes for them.
+    fn children(&mut self, item: Item, end: usize) -> Vec<Vec<NodeId>> {
+        if item.dot == 0 {
+            return match item.origin == end {
+                true => vec![Vec::new()],
+                false => Vec::new(),
+            };
+        }
+        let before = Item {
+            dot: item.dot - 1,
+            ..item
+        };
+        let symbol = &self.parser.productions[item.production].1[before.dot];
+        let mut splits = Vec::new();
+        match symbol {
+            Symbol::NT(nonterminal) => {
+                for middle in item.origin..=end {
+                    let derives = self.sets[end].iter().any(|other| {
+                        let (lhs, symbols) = &self.parser.productions[other.production];
+                        lhs == nonterminal && other.origin == middle && other.dot == symbols.len()
+                    });
+                    if derives && self.sets[middle].contains(&before) {
+                        splits.push((middle, *nonterminal));
+                    }
+                }
+                let mut all = Vec::new();
+                for (middle, nonterminal) in splits {
+                    let node = self.symbol_node(nonterminal, middle, end);
+                    for mut prefix in self.children(before, middle) {
+                        prefix.push(node);
+                        all.push(prefix);
+                    }
+                }
+                all
+            }
+            _ => {
+                if end == 0 || !self.sets[end - 1].contains(&before) {
+                    return Vec::new();
+                }
+                let leaf = self.leaf(end - 1);
+                let mut all = self.children(before, end - 1);
+                for prefix in all.iter_mut() {
+                    prefix.push(leaf);
+                }
+                all
+            }
+        }
+    }
+
+    fn leaf(&mut self, i: usize) -> NodeId {
+        if let Some(id) = self.leaves.get(&i) {
+            return *id;
+        }
+        let token = self.tokens[i];
+        let id = self.nodes.len();
+        self.nodes.push(ForestNode::Leaf {
+            terminal: token.terminal,
+            text: self.input[token.start..token.end].to_owned(),
+            start: token.start,
+        });
+        self.leaves.insert(i, id);
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use symbol::Symbol as Id;
+
+    use crate::this::ll_grammar;
+    use crate::{Grammar, GrammarError};
+
+    fn make_arith() -> Grammar {
+        make_grammar! {
+            start_symbols: [E],
+            terminals: {
+                Add: r"\+",
+                Mul: r"\*",
+                LP: r"\(",
+                RP: r"\)",
+                N: r"1",
+            },
+            productions: {
+                E: [ [E, Add, T], [T] ],
+                T: [ [T, Mul, F], [F] ],
+                F: [ [LP, E, RP], [N] ],
+            }
+        }
+    }
+
+    #[test]
+    fn test_agrees_with_lr() {
+        for grammar in [make_arith(), ll_grammar()] {
+            let start = grammar.start_symbols[0].as_str();
+            let earley = grammar.clone().build_earley().unwrap();
+            let lr = grammar.build().unwrap();
+            for input in ["1", "(1+1)*1", "1*1+1*(1*1)", "1+", "(1", "1)+1", ""] {
+                let expected = lr.parse_glr(start, input);
+                let forest = earley.parse(start, input);
+                match (expected, forest) {
+                    (Ok(expected), Ok(forest)) => {
+                        assert_eq!(forest.trees(), expected.trees());
+                        assert_eq!(forest.count_trees(), 1);
+                    }
+                    (Err(expected), Err(err)) => assert_eq!(err, expected),
+                    (expected, forest) => panic!("{:?} vs {:?} on {:?}", expected, forest, input),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_ambiguous() {
+        let grammar = make_grammar! {
+            start_symbols: [E],
+            terminals: {
+                Add: r"\+",
+                N: r"1",
+            },
+            productions: {
+                E: [ [E, Add, E], [N] ],
+            }
+        };
+        let parser = grammar.build_earley().unwrap();
+        let catalan = [1, 1, 2, 5, 14, 42];
+        for (n, count) in catalan.iter().enumerate() {
+            let input = vec!["1"; n + 1].join("+");
+            let forest = parser.parse("E", &input).unwrap();
+            assert_eq!(forest.count_trees(), *count);
+            assert_eq!(forest.trees().len(), *count);
+        }
+        assert_eq!(parser.recognize("E", "1++1").unwrap_err().position(), 2);
+        assert_eq!(parser.recognize("E", "1+1+").unwrap_err().position(), 4);
+    }
+
+    #[test]
+    fn test_epsilon_and_cycles() {
+        // not SLR, and would have infinitely many trees around the cycle
+        let grammar = make_grammar! {
+            start_symbols: [S],
+            terminals: {
+                B: r"b",
+            },
+            productions: {
+                S: [ [A, S, A], [B], [S], [ɛ] ],
+                A: [ [ɛ], [B] ],
+            }
+        };
+        let parser = grammar.build_earley().unwrap();
+        assert!(parser.recognize("S", "").is_ok());
+        assert!(parser.recognize("S", "bbbbb").is_ok());
+        let forest = parser.parse("S", "b").unwrap();
+        assert!(forest.is_ambiguous());
+        // S -> b, or S -> A S A with one of the A's deriving the b; the S in
+        // the middle deriving it goes around the cycle
+        assert_eq!(forest.trees().len(), 3);
+        assert_eq!(forest.count_trees(), 3);
+    }
+
+    #[test]
+    fn test_shared_cycles() {
+        // the forest code is shared with GLR, so check a cycle that's reached
+        // both from inside and from outside it here too
+        let grammar = make_grammar! {
+            start_symbols: [R],
+            terminals: {
+                B: r"b",
+            },
+            productions: {
+                R: [ [S], [U] ],
+                S: [ [T], [B] ],
+                T: [ [S], [B] ],
+                U: [ [T] ],
+            }
+        };
+        let parser = grammar.build_earley().unwrap();
+        let forest = parser.parse("R", "b").unwrap();
+        assert_eq!(forest.count_trees(), 4);
+        assert_eq!(forest.trees().len(), 4);
+    }
+
+    #[test]
+    fn test_undefined_symbol() {
+        let grammar = make_grammar! {
+            start_symbols: [S],
+            terminals: {
+                A: r"a",
+            },
+            productions: {
+                S: [ [A, Missing] ],
+            }
+        };
+        match grammar.build_earley() {
+            Err(GrammarError::UndefinedNonterminal(names)) => {
+                assert_eq!(names, vec![Id::from("Missing")])
+            }
+            other => panic!("expected an undefined symbol, got {:?}", other),
+        }
+    }
+}
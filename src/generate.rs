@@ -0,0 +1,573 @@
+//! Random sentences of a grammar, for fuzzing the parsers built from it and
+//! whatever sits behind them.
+//!
+//! Derivations are made top down, preferring productions that haven't been
+//! used yet, and taking the shortest way out once they get too deep or too
+//! long. Every terminal's text is sampled from its regex, and the sentence is
+//! only kept if it lexes back into the same terminals.
+
+use std::collections::HashMap;
+
+use regex_syntax::hir::{self, Hir, HirKind, RepetitionKind, RepetitionRange};
+use symbol::Symbol as Id;
+
+use crate::grammar::{GrammarError, Symbol};
+use crate::lints::Lint;
+use crate::runtime::Lexer;
+use crate::Grammar;
+
+/// How many times a derivation gets its terminals sampled before it's given
+/// up on for not lexing back
+const SAMPLES: usize = 10;
+
+/// How many derivations a sentence gets before the generator gives up
+const DERIVATIONS: usize = 100;
+
+/// How many more times than it has to an unbounded repetition in a regex
+/// can repeat
+const EXTRA_REPEATS: u32 = 3;
+
+/// Options for `Grammar::generator`.
+#[derive(Clone, Debug)]
+pub struct GenerateOptions {
+    /// The same seed makes the same sentences
+    pub seed: u64,
+
+    /// How deep a derivation can get before it only takes the productions
+    /// that end it the soonest
+    pub max_depth: usize,
+
+    /// How many tokens a sentence can get before it does the same
+    pub max_tokens: usize,
+}
+
+impl Default for GenerateOptions {
+    fn default() -> Self {
+        GenerateOptions {
+            seed: 0,
+            max_depth: 16,
+            max_tokens: 64,
+        }
+    }
+}
+
+/// Makes random sentences of a start symbol, as an endless iterator that
+/// only ends if it can't find a sentence that lexes back.
+#[derive(Debug)]
+pub struct Generator {
+    start_symbol: Id,
+    productions: Vec<(Id, Vec<Symbol>)>,
+    by_lhs: HashMap<Id, Vec<usize>>,
+
+    /// How deep the shallowest derivation from each production is, or None
+    /// if it can't derive a sentence
+    heights: Vec<Option<usize>>,
+    terminals: Vec<(Id, Hir)>,
+    lexer: Lexer,
+    options: GenerateOptions,
+    rng: Rng,
+
+    /// How many times each production has been used, in sentences kept
+    uses: Vec<usize>,
+
+    /// Whether each production is unused, or leads to a nonterminal with an
+    /// unused production
+    leads_to_unused: Vec<bool>,
+}
+
+impl Grammar {
+    /// Starts generating random sentences of the start symbol, which doesn't
+    /// have to be one of the grammar's.
+    pub fn generator(
+        &self,
+        start_symbol: impl AsRef<str>,
+        options: &GenerateOptions,
+    ) -> Result<Generator, GrammarError> {
+        let start_symbol = Id::from(start_symbol.as_ref());
+        match self.grammar_symbols()?.get(&start_symbol) {
+            Some(Symbol::NT(_)) => (),
+            Some(_) => return Err(GrammarError::StartingTerminal(start_symbol)),
+            None => return Err(GrammarError::UndefinedNonterminal(vec![start_symbol])),
+        }
+        let analysis = self.analyze()?;
+        self.check_patterns()?;
+
+        let mut terminals = Vec::new();
+        for (terminal, regex) in self.terminals.iter() {
+            let hir = regex_syntax::Parser::new()
+                .parse(regex)
+                .map_err(|_| GrammarError::InvalidPattern(*terminal))?;
+            terminals.push((*terminal, hir));
+        }
+        let lexer = Lexer::for_terminals(&self.terminals);
+
+        let productions = analysis.productions;
+        let mut by_lhs = HashMap::new();
+        for (i, (lhs, _)) in productions.iter().enumerate() {
+            by_lhs.entry(*lhs).or_insert_with(Vec::new).push(i);
+        }
+        let n_productions = productions.len();
+        let mut generator = Generator {
+            start_symbol,
+            productions,
+            by_lhs,
+            heights: vec![None; n_productions],
+            terminals,
+            lexer,
+            options: options.clone(),
+            rng: Rng(options.seed),
+            uses: vec![0; n_productions],
+            leads_to_unused: vec![true; n_productions],
+        };
+        generator.compute_heights();
+        if generator.height(start_symbol).is_none() {
+            return Err(GrammarError::Lints(vec![Lint::Unproductive(start_symbol)]));
+        }
+        Ok(generator)
+    }
+}
+
+impl Generator {
+    /// The productions that no sentence so far has used, numbered like the
+    /// trees of parsers built from the grammar. Ones the start symbol can't
+    /// reach, or that can't derive a sentence, are never used.
+    pub fn uncovered(&self) -> Vec<usize> {
+        (0..self.uses.len())
+            .filter(|production| self.uses[*production] == 0)
+            .collect()
+    }
+
+    /// How deep the shallowest derivation from the nonterminal is.
+    fn height(&self, nonterminal: Id) -> Option<usize> {
+        self.by_lhs
+            .get(&nonterminal)?
+            .iter()
+            .filter_map(|production| self.heights[*production])
+            .min()
+    }
+
+    /// Finds the height of every production, going until nothing changes.
+    /// Productions with `error` in them never derive a sentence.
+    fn compute_heights(&mut self) {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for production in 0..self.productions.len() {
+                let mut height = Some(1);
+                for symbol in self.productions[production].1.iter() {
+                    height = match symbol {
+                        Symbol::NT(nonterminal) => self
+                            .height(*nonterminal)
+                            .and_then(|child| height.map(|height| height.max(child + 1))),
+                        Symbol::T(terminal) if self.terminal(*terminal).is_some() => height,
+                        _ => None,
+                    };
+                }
+                if height.is_some() && height != self.heights[production] {
+                    self.heights[production] = height;
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    /// Marks the productions that are unused, or lead to one, going until
+    /// nothing changes.
+    fn compute_leads_to_unused(&mut self) {
+        let mut leads_to_unused = self.uses.iter().map(|uses| *uses == 0).collect::<Vec<_>>();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (production, (_, symbols)) in self.productions.iter().enumerate() {
+                if leads_to_unused[production] {
+                    continue;
+                }
+                let leads = symbols.iter().any(|symbol| match symbol {
+                    Symbol::NT(nonterminal) => self
+                        .by_lhs
+                        .get(nonterminal)
+                        .into_iter()
+                        .flatten()
+                        .any(|production| leads_to_unused[*production]),
+                    _ => false,
+                });
+                if leads {
+                    leads_to_unused[production] = true;
+                    changed = true;
+                }
+            }
+        }
+        self.leads_to_unused = leads_to_unused;
+    }
+
+    /// The terminal's number, or None for `error`.
+    fn terminal(&self, terminal: Id) -> Option<usize> {
+        self.terminals.iter().position(|(id, _)| *id == terminal)
+    }
+
+    /// Derives the terminals of a sentence from the nonterminal.
+    fn derive(&mut self, nonterminal: Id, depth: usize, terminals: &mut Vec<usize>) {
+        let production = self.choose(nonterminal, depth, terminals.len());
+        self.uses[production] += 1;
+        let symbols = self.productions[production].1.clone();
+        for symbol in symbols {
+            match symbol {
+                Symbol::T(terminal) => terminals.push(self.terminal(terminal).unwrap()),
+                Symbol::NT(nonterminal) => self.derive(nonterminal, depth + 1, terminals),
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    /// Picks a production of the nonterminal that fits in the depth left, or
+    /// one of the shallowest once it's out of room. Unused productions come
+    /// first, then ones that lead to one, then any.
+    fn choose(&mut self, nonterminal: Id, depth: usize, n_tokens: usize) -> usize {
+        let productive = self.by_lhs[&nonterminal]
+            .iter()
+            .cloned()
+            .filter(|production| self.heights[*production].is_some())
+            .collect::<Vec<_>>();
+        let room = match depth < self.options.max_depth && n_tokens < self.options.max_tokens {
+            true => self.options.max_depth - depth,
+            false => 0,
+        };
+        let mut candidates = productive
+            .iter()
+            .cloned()
+            .filter(|production| self.heights[*production].unwrap() <= room)
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            let shallowest = self.height(nonterminal);
+            candidates = productive;
+            candidates.retain(|production| self.heights[*production] == shallowest);
+        }
+
+        let unused = candidates
+            .iter()
+            .cloned()
+            .filter(|production| self.uses[*production] == 0)
+            .collect::<Vec<_>>();
+        let leading = candidates
+            .iter()
+            .cloned()
+            .filter(|production| self.leads_to_unused[*production])
+            .collect::<Vec<_>>();
+        let candidates = [unused, leading, candidates]
+            .iter()
+            .find(|candidates| !candidates.is_empty())
+            .unwrap()
+            .clone();
+        candidates[self.rng.below(candidates.len())]
+    }
+
+    /// Samples text for every terminal, each one lexing as its terminal by
+    /// itself, or None if some terminal can't be sampled like that.
+    fn sample(&mut self, terminals: &[usize]) -> Option<String> {
+        let mut text = String::new();
+        for terminal in terminals.iter() {
+            let hir = self.terminals[*terminal].1.clone();
+            let sample = (0..SAMPLES).find_map(|_| {
+                let mut sample = String::new();
+                if !sample_hir(&hir, &mut self.rng, &mut sample) {
+                    return None;
+                }
+                match self.lexer.next(&sample, 0) {
+                    Some((lexed, end)) if lexed == *terminal && end == sample.len() => Some(sample),
+                    _ => None,
+                }
+            })?;
+            text += &sample;
+        }
+        Some(text)
+    }
+
+    /// Whether the text lexes into exactly the terminals.
+    fn lexes_back(&self, text: &str, terminals: &[usize]) -> bool {
+        let mut position = 0;
+        for terminal in terminals.iter() {
+            match self.lexer.next(text, position) {
+                Some((lexed, end)) if lexed == *terminal => position = end,
+                _ => return false,
+            }
+        }
+        position == text.len()
+    }
+}
+
+impl Iterator for Generator {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        self.compute_leads_to_unused();
+        for _ in 0..DERIVATIONS {
+            let uses = self.uses.clone();
+            let mut terminals = Vec::new();
+            self.derive(self.start_symbol, 0, &mut terminals);
+            for _ in 0..SAMPLES {
+                match self.sample(&terminals) {
+                    Some(text) if self.lexes_back(&text, &terminals) => return Some(text),
+                    _ => (),
+                }
+            }
+            // only sentences that are kept count towards coverage
+            self.uses = uses;
+        }
+        None
+    }
+}
+
+/// Appends a random string the regex matches, returning false if it can't
+/// find one that's valid UTF-8. Anchors and word boundaries are ignored, and
+/// left to the caller to check by lexing.
+fn sample_hir(hir: &Hir, rng: &mut Rng, out: &mut String) -> bool {
+    match hir.kind() {
+        HirKind::Empty | HirKind::Anchor(_) | HirKind::WordBoundary(_) => true,
+        HirKind::Literal(hir::Literal::Unicode(c)) => {
+            out.push(*c);
+            true
+        }
+        HirKind::Literal(hir::Literal::Byte(b)) => push_byte(*b, out),
+        HirKind::Class(hir::Class::Unicode(class)) => {
+            let ranges = class
+                .ranges()
+                .iter()
+                .map(|range| (range.start() as u32, range.end() as u32))
+                .collect::<Vec<_>>();
+            let c = sample_ranges(&ranges, rng);
+            match c.and_then(std::char::from_u32) {
+                Some(c) => {
+                    out.push(c);
+                    true
+                }
+                None => false,
+            }
+        }
+        HirKind::Class(hir::Class::Bytes(class)) => {
+            let ranges = class
+                .ranges()
+                .iter()
+                .map(|range| (range.start() as u32, range.end() as u32))
+                .collect::<Vec<_>>();
+            match sample_ranges(&ranges, rng) {
+                Some(b) => push_byte(b as u8, out),
+                None => false,
+            }
+        }
+        HirKind::Repetition(repetition) => {
+            let (min, max) = match &repetition.kind {
+                RepetitionKind::ZeroOrOne => (0, 1),
+                RepetitionKind::ZeroOrMore => (0, EXTRA_REPEATS),
+                RepetitionKind::OneOrMore => (1, 1 + EXTRA_REPEATS),
+                RepetitionKind::Range(RepetitionRange::Exactly(n)) => (*n, *n),
+                RepetitionKind::Range(RepetitionRange::AtLeast(n)) => (*n, n + EXTRA_REPEATS),
+                RepetitionKind::Range(RepetitionRange::Bounded(m, n)) => (*m, *n),
+            };
+            let n = min + rng.below((max - min + 1) as usize) as u32;
+            (0..n).all(|_| sample_hir(&repetition.hir, rng, out))
+        }
+        HirKind::Group(group) => sample_hir(&group.hir, rng, out),
+        HirKind::Concat(hirs) => hirs.iter().all(|hir| sample_hir(hir, rng, out)),
+        HirKind::Alternation(hirs) => sample_hir(&hirs[rng.below(hirs.len())], rng, out),
+    }
+}
+
+fn push_byte(b: u8, out: &mut String) -> bool {
+    if b.is_ascii() {
+        out.push(b as char);
+    }
+    b.is_ascii()
+}
+
+/// Picks a value from the inclusive ranges, preferring printable ASCII so
+/// that sentences stay readable.
+fn sample_ranges(ranges: &[(u32, u32)], rng: &mut Rng) -> Option<u32> {
+    let printable = ranges
+        .iter()
+        .map(|(start, end)| (*start.max(&0x20), *end.min(&0x7e)))
+        .filter(|(start, end)| start <= end)
+        .collect::<Vec<_>>();
+    let ranges = match printable.is_empty() {
+        true => ranges,
+        false => &printable[..],
+    };
+    let total = ranges
+        .iter()
+        .map(|(start, end)| (end - start + 1) as usize)
+        .sum::<usize>();
+    if total == 0 {
+        return None;
+    }
+    let mut i = rng.below(total) as u32;
+    for (start, end) in ranges.iter() {
+        if i <= end - start {
+            return Some(start + i);
+        }
+        i -= end - start + 1;
+    }
+    None
+}
+
+/// A small seeded random number generator (SplitMix64), so that sentences
+/// can be made again from their seed.
+#[derive(Clone, Debug)]
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use symbol::Symbol as Id;
+
+    use super::GenerateOptions;
+    use crate::lints::Lint;
+    use crate::{Grammar, GrammarError};
+
+    fn generate(
+        grammar: &Grammar,
+        start: &str,
+        options: &GenerateOptions,
+        n: usize,
+    ) -> Vec<String> {
+        let generator = grammar.generator(start, options).unwrap();
+        let sentences = generator.take(n).collect::<Vec<_>>();
+        assert_eq!(sentences.len(), n);
+        sentences
+    }
+
+    #[test]
+    fn test_sentences_parse() {
+        for (grammar, start) in &[
+            (crate::this::pgen_grammar(), "E"),
+            (crate::this::ll_grammar(), "E"),
+            (crate::this::stmts_grammar(), "Stmts"),
+        ] {
+            let earley = grammar.clone().build_earley().unwrap();
+            let options = GenerateOptions::default();
+            let mut generator = grammar.generator(start, &options).unwrap();
+            for _ in 0..100 {
+                let sentence = generator.next().unwrap();
+                assert!(earley.recognize(start, &sentence).is_ok(), "{:?}", sentence);
+            }
+
+            // every production gets used, except the error production
+            let uncovered = generator.uncovered();
+            match *start {
+                "Stmts" => assert_eq!(uncovered, vec![3]),
+                _ => assert_eq!(uncovered, vec![]),
+            }
+
+            // and the seed decides the sentences
+            let first = generate(grammar, start, &options, 20);
+            assert_eq!(first, generate(grammar, start, &options, 20));
+            let options = GenerateOptions { seed: 1, ..options };
+            assert_ne!(first, generate(grammar, start, &options, 20));
+        }
+    }
+
+    #[test]
+    fn test_limits() {
+        // E -> E Mul B is as deep as a derivation three deep can go
+        let grammar = crate::this::pgen_grammar();
+        let options = GenerateOptions {
+            max_depth: 3,
+            ..GenerateOptions::default()
+        };
+        let sentences = generate(&grammar, "E", &options, 50);
+        assert!(sentences.iter().all(|sentence| sentence.len() <= 3));
+        assert!(sentences.iter().any(|sentence| sentence.contains('*')));
+        assert!(sentences.iter().any(|sentence| sentence.contains('+')));
+
+        // past the token limit, the rest of the derivation is as short as it
+        // can be: a number to end the innermost parentheses, and closing them
+        let grammar = crate::this::ll_grammar();
+        let options = GenerateOptions {
+            max_tokens: 5,
+            ..GenerateOptions::default()
+        };
+        for sentence in generate(&grammar, "E", &options, 50) {
+            let numbers = sentence
+                .split(|c: char| !c.is_ascii_digit())
+                .filter(|number| !number.is_empty())
+                .count();
+            let others = sentence.chars().filter(|c| "(+*".contains(*c)).count();
+            assert!(numbers + others <= 6, "{:?}", sentence);
+        }
+    }
+
+    #[test]
+    fn test_samples() {
+        let grammar = make_grammar! {
+            start_symbols: [S],
+            terminals: {
+                If: r"if",
+                Ident: r"[a-z]+",
+                Num: r"[0-9]{2,3}(\.[0-9])?",
+                Str: r#""[^"\n]*""#,
+                LP: r"\(",
+                RP: r"\)",
+            },
+            productions: {
+                S: [ [If, LP, E, RP, S], [Ident, LP, E, RP] ],
+                E: [ [Num], [Str], [Ident] ],
+            }
+        };
+        let earley = grammar.clone().build_earley().unwrap();
+        let options = GenerateOptions::default();
+        let sentences = generate(&grammar, "S", &options, 100);
+        for sentence in sentences.iter() {
+            assert!(earley.recognize("S", sentence).is_ok(), "{:?}", sentence);
+            assert!(sentence.is_ascii(), "{:?}", sentence);
+        }
+        // identifiers sampled as `if` lex as the keyword, so are resampled
+        assert!(sentences
+            .iter()
+            .all(|sentence| !sentence.starts_with("if(if")));
+    }
+
+    #[test]
+    fn test_generator_errors() {
+        let options = GenerateOptions::default();
+        let mut grammar = make_grammar! {
+            start_symbols: [S],
+            terminals: {
+                A: r"a",
+                B: r"b",
+            },
+            productions: {
+                S: [ [A, S] ],
+                T: [ [B] ],
+            }
+        };
+        assert!(matches!(
+            grammar.generator("S", &options),
+            Err(GrammarError::Lints(lints)) if lints == vec![Lint::Unproductive(Id::from("S"))]
+        ));
+        assert!(matches!(
+            grammar.generator("U", &options),
+            Err(GrammarError::UndefinedNonterminal(_))
+        ));
+        assert!(matches!(
+            grammar.generator("A", &options),
+            Err(GrammarError::StartingTerminal(_))
+        ));
+        grammar.terminals.insert(Id::from("B"), r"(b".to_owned());
+        assert!(matches!(
+            grammar.generator("T", &options),
+            Err(GrammarError::InvalidPattern(terminal)) if terminal == Id::from("B")
+        ));
+    }
+}
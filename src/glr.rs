@@ -0,0 +1,815 @@
+//! Generalized LR parsing, for grammars whose tables have conflicts.
+//!
+//! Every action of a conflicted cell is tried at once, with the parser's
+//! stacks sharing their common parts in a graph-structured stack, and the
+//! parse trees sharing their common parts in a shared packed parse forest.
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use symbol::Symbol as Id;
+
+use crate::grammar::Symbol;
+use crate::parser::Action;
+use crate::runtime::{ParseError, Tree};
+use crate::Parser;
+
+/// The index of a node in a `Forest`.
+pub type NodeId = usize;
+
+/// A shared packed parse forest: every parse of the input at once.
+///
+/// Each nonterminal node stands for all the ways that nonterminal derives its
+/// span of the input, with a `Family` of children for each of them. A node
+/// with more than one family is an ambiguity.
+#[derive(Clone, Debug)]
+pub struct Forest {
+    pub(crate) nodes: Vec<ForestNode>,
+    pub(crate) root: NodeId,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ForestNode {
+    /// A token: its terminal, the text it matched and where that starts
+    Leaf {
+        terminal: usize,
+        text: String,
+        start: usize,
+    },
+
+    /// A nonterminal deriving the input from `start` to `end`
+    Symbol {
+        nonterminal: Id,
+        start: usize,
+        end: usize,
+        families: Vec<Family>,
+    },
+}
+
+impl ForestNode {
+    /// The bytes of the input the node covers.
+    pub fn span(&self) -> Range<usize> {
+        match self {
+            ForestNode::Leaf { text, start, .. } => *start..*start + text.len(),
+            ForestNode::Symbol { start, end, .. } => *start..*end,
+        }
+    }
+}
+
+/// One way of deriving a nonterminal node: a production and the nodes of its
+/// right hand side.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Family {
+    pub production: usize,
+    pub children: Vec<NodeId>,
+}
+
+impl Forest {
+    /// The node for the start symbol, spanning the whole input.
+    pub fn root(&self) -> NodeId {
+        self.root
+    }
+
+    pub fn node(&self, id: NodeId) -> &ForestNode {
+        &self.nodes[id]
+    }
+
+    /// Whether some node has more than one family.
+    pub fn is_ambiguous(&self) -> bool {
+        self.nodes.iter().any(|node| match node {
+            ForestNode::Symbol { families, .. } => families.len() > 1,
+            _ => false,
+        })
+    }
+
+    /// How many parse trees the forest holds, not counting the infinitely
+    /// many that go around a cycle of the grammar.
+    pub fn count_trees(&self) -> usize {
+        self.fold(|_, families| match families {
+            None => 1,
+            Some(families) => families
+                .into_iter()
+                .map(|(_, counts)| counts.into_iter().fold(1, usize::saturating_mul))
+                .fold(0, usize::saturating_add),
+        })
+    }
+
+    /// Every parse tree the forest holds, leaving out the ones that go around
+    /// a cycle of the grammar. There can be exponentially many of them.
+    pub fn trees(&self) -> Vec<Tree> {
+        self.fold(|node, families| {
+            let families = match (node, families) {
+                (ForestNode::Leaf { terminal, text, .. }, _) => {
+                    return vec![Tree::Leaf(*terminal, text.clone(), node.span())]
+                }
+                (_, families) => families.unwrap_or_default(),
+            };
+            let mut trees = Vec::new();
+            for (family, children) in families {
+                // every combination of the children's trees
+                let mut combinations = vec![Vec::new()];
+                for child in children {
+                    combinations = combinations
+                        .into_iter()
+                        .flat_map(|prefix: Vec<Tree>| {
+                            child.iter().map(move |tree| {
+                                let mut prefix = prefix.clone();
+                                prefix.push(tree.clone());
+                                prefix
+                            })
+                        })
+                        .collect();
+                }
+                for children in combinations {
+                    trees.push(Tree::Node(family.production, children, node.span()));
+                }
+            }
+            trees
+        })
+    }
+
+    /// Filters out families, keeping the ones `keep` returns true for.
+    ///
+    /// This is how disambiguation rules like precedence and associativity are
+    /// applied after the fact. Nodes left without a family make their parents'
+    /// families that use them invalid too, so those are dropped as well.
+    pub fn retain(&mut self, mut keep: impl FnMut(&Forest, NodeId, &Family) -> bool) {
+        let decisions = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(id, node)| match node {
+                ForestNode::Leaf { .. } => Vec::new(),
+                ForestNode::Symbol { families, .. } => families
+                    .iter()
+                    .map(|family| keep(self, id, family))
+                    .collect(),
+            })
+            .collect::<Vec<Vec<bool>>>();
+        for (node, decisions) in self.nodes.iter_mut().zip(decisions) {
+            if let ForestNode::Symbol { families, .. } = node {
+                let mut decisions = decisions.into_iter();
+                families.retain(|_| decisions.next().unwrap());
+            }
+        }
+
+        // drop families that use a node with none left, until nothing changes
+        loop {
+            let empty = self
+                .nodes
+                .iter()
+                .map(|node| match node {
+                    ForestNode::Leaf { .. } => false,
+                    ForestNode::Symbol { families, .. } => families.is_empty(),
+                })
+                .collect::<Vec<_>>();
+            let mut changes = false;
+            for node in self.nodes.iter_mut() {
+                if let ForestNode::Symbol { families, .. } = node {
+                    let before = families.len();
+                    families.retain(|family| family.children.iter().all(|child| !empty[*child]));
+                    changes |= families.len() != before;
+                }
+            }
+            if !changes {
+                break;
+            }
+        }
+    }
+
+    /// Picks a single parse tree, calling `merge` with the two alternatives
+    /// wherever a node is ambiguous, like Bison's `%merge`. Returns None if
+    /// filtering left no tree at all.
+    pub fn tree(&self, mut merge: impl FnMut(Tree, Tree) -> Tree) -> Option<Tree> {
+        self.fold(|node, families| {
+            let families = match (node, families) {
+                (ForestNode::Leaf { terminal, text, .. }, _) => {
+                    return Some(Tree::Leaf(*terminal, text.clone(), node.span()))
+                }
+                (_, families) => families.unwrap_or_default(),
+            };
+            let mut tree: Option<Tree> = None;
+            for (family, children) in families {
+                let children = children.into_iter().collect::<Option<Vec<_>>>();
+                if let Some(children) = children {
+                    let alternative = Tree::Node(family.production, children, node.span());
+                    tree = Some(match tree {
+                        Some(tree) => merge(tree, alternative),
+                        None => alternative,
+                    });
+                }
+            }
+            tree
+        })
+    }
+
+    /// Computes a value for the root from the values of the nodes below it.
+    ///
+    /// `f` gets every node with, unless it's a leaf, the values of the children
+    /// of each of its families. Families that go back to a node being computed
+    /// are left out, and values that depend on which ones were left out aren't
+    /// remembered, since they'd be wrong when reached from elsewhere.
+    fn fold<T: Clone>(
+        &self,
+        mut f: impl FnMut(&ForestNode, Option<Vec<(&Family, Vec<T>)>>) -> T,
+    ) -> T {
+        let mut values = HashMap::new();
+        let mut walk = Walk::new(self);
+        self.fold_node(self.root, &mut f, &mut values, &mut walk)
+            .unwrap()
+    }
+
+    /// Returns None for a node being computed.
+    fn fold_node<'f, T: Clone>(
+        &'f self,
+        id: NodeId,
+        f: &mut impl FnMut(&'f ForestNode, Option<Vec<(&'f Family, Vec<T>)>>) -> T,
+        values: &mut HashMap<NodeId, T>,
+        walk: &mut Walk,
+    ) -> Option<T> {
+        let families = match &self.nodes[id] {
+            node @ ForestNode::Leaf { .. } => return Some(f(node, None)),
+            ForestNode::Symbol { families, .. } => families,
+        };
+        let shared = walk.is_shared(id);
+        if shared {
+            if let Some(value) = values.get(&id) {
+                return Some(value.clone());
+            }
+        }
+        if !walk.enter(id) {
+            return None;
+        }
+
+        let mut computed = Vec::new();
+        'families: for family in families {
+            let mut children = Vec::new();
+            for child in family.children.iter() {
+                match self.fold_node(*child, f, values, walk) {
+                    Some(value) => children.push(value),
+                    None => continue 'families,
+                }
+            }
+            computed.push((family, children));
+        }
+        walk.leave(id);
+
+        let value = f(&self.nodes[id], Some(computed));
+        if shared {
+            values.insert(id, value.clone());
+        }
+        Some(value)
+    }
+
+    /// Numbers the strongly connected components of the forest, so that two
+    /// nodes get the same number exactly when each can reach the other.
+    fn components(&self) -> Vec<usize> {
+        let mut tarjan = Tarjan {
+            index: vec![None; self.nodes.len()],
+            lowlink: vec![0; self.nodes.len()],
+            stack: Vec::new(),
+            on_stack: vec![false; self.nodes.len()],
+            components: vec![0; self.nodes.len()],
+            next_index: 0,
+            next_component: 0,
+        };
+        for id in 0..self.nodes.len() {
+            if tarjan.index[id].is_none() {
+                self.connect(id, &mut tarjan);
+            }
+        }
+        tarjan.components
+    }
+
+    fn connect(&self, id: NodeId, tarjan: &mut Tarjan) {
+        tarjan.index[id] = Some(tarjan.next_index);
+        tarjan.lowlink[id] = tarjan.next_index;
+        tarjan.next_index += 1;
+        tarjan.stack.push(id);
+        tarjan.on_stack[id] = true;
+
+        if let ForestNode::Symbol { families, .. } = &self.nodes[id] {
+            for child in families.iter().flat_map(|family| family.children.iter()) {
+                match tarjan.index[*child] {
+                    None => {
+                        self.connect(*child, tarjan);
+                        tarjan.lowlink[id] = tarjan.lowlink[id].min(tarjan.lowlink[*child]);
+                    }
+                    Some(index) if tarjan.on_stack[*child] => {
+                        tarjan.lowlink[id] = tarjan.lowlink[id].min(index);
+                    }
+                    Some(_) => (),
+                }
+            }
+        }
+
+        if Some(tarjan.lowlink[id]) == tarjan.index[id] {
+            loop {
+                let node = tarjan.stack.pop().unwrap();
+                tarjan.on_stack[node] = false;
+                tarjan.components[node] = tarjan.next_component;
+                if node == id {
+                    break;
+                }
+            }
+            tarjan.next_component += 1;
+        }
+    }
+}
+
+/// The state of Tarjan's strongly connected components algorithm.
+struct Tarjan {
+    index: Vec<Option<usize>>,
+    lowlink: Vec<usize>,
+    stack: Vec<NodeId>,
+    on_stack: Vec<bool>,
+    components: Vec<usize>,
+    next_index: usize,
+    next_component: usize,
+}
+
+/// The nodes on the path from the root down to the one being visited.
+///
+/// Going around a cycle is cut off where it gets back to a node on the path,
+/// so what a node gives depends on the path whenever another node of its
+/// strongly connected component is on it. Only values computed without one
+/// are remembered, and they're only reused without one either.
+struct Walk {
+    components: Vec<usize>,
+
+    /// How many nodes of each component are on the path
+    open: Vec<usize>,
+    path: HashSet<NodeId>,
+}
+
+impl Walk {
+    fn new(forest: &Forest) -> Self {
+        let components = forest.components();
+        let open = vec![0; components.iter().max().map_or(0, |max| max + 1)];
+        Walk {
+            components,
+            open,
+            path: HashSet::new(),
+        }
+    }
+
+    /// Whether what the node gives doesn't depend on the path to it.
+    fn is_shared(&self, id: NodeId) -> bool {
+        self.open[self.components[id]] == 0
+    }
+
+    /// Puts the node on the path, returning false if it already was.
+    fn enter(&mut self, id: NodeId) -> bool {
+        if !self.path.insert(id) {
+            return false;
+        }
+        self.open[self.components[id]] += 1;
+        true
+    }
+
+    fn leave(&mut self, id: NodeId) {
+        self.path.remove(&id);
+        self.open[self.components[id]] -= 1;
+    }
+}
+
+/// A node of the graph-structured stack: a state at a position in the input,
+/// with edges to the nodes below it labeled by the forest node in between.
+#[derive(Debug)]
+struct StackNode {
+    state: usize,
+    edges: Vec<(usize, NodeId)>,
+}
+
+/// The graph-structured stack and forest of a GLR parse in progress.
+struct Glr<'a> {
+    parser: &'a Parser,
+    stack: Vec<StackNode>,
+
+    /// The level (token index) of every stack node
+    levels: Vec<usize>,
+
+    /// The byte offset every level starts at
+    offsets: Vec<usize>,
+    nodes: Vec<ForestNode>,
+    symbol_nodes: HashMap<(Id, usize, usize), NodeId>,
+    conflicts: HashMap<(usize, Symbol), Vec<Action>>,
+}
+
+impl Parser {
+    /// Parses the input with every action of conflicted cells, returning the
+    /// forest of all the parses.
+    pub fn parse_glr(
+        &self,
+        start_symbol: impl AsRef<str>,
+        input: impl AsRef<str>,
+    ) -> Result<Forest, ParseError> {
+        let input = input.as_ref();
+        let start_symbol = Id::from(start_symbol.as_ref());
+        let start_state = self.entry_states[&start_symbol];
+        let lexer = self.lexer();
+        let terminals = self.terminal_columns();
+
+        let mut glr = Glr {
+            parser: self,
+            stack: vec![StackNode {
+                state: start_state,
+                edges: Vec::new(),
+            }],
+            levels: vec![0],
+            offsets: vec![0],
+            nodes: Vec::new(),
+            symbol_nodes: HashMap::new(),
+            conflicts: self
+                .conflicts
+                .iter()
+                .map(|conflict| {
+                    let key = (conflict.state, conflict.symbol.clone());
+                    (key, conflict.actions.clone())
+                })
+                .collect(),
+        };
+
+        let mut frontier = vec![0];
+        let mut position = 0;
+        loop {
+            let (terminal, end) = lexer
+                .next(input, position)
+                .ok_or_else(|| ParseError::unlexable(input, position))?;
+            let lookahead = &terminals[terminal];
+            glr.reduce_all(&mut frontier, lookahead);
+
+            if *lookahead == Symbol::EOF {
+                let accepted = frontier.iter().any(|node| {
+                    glr.actions(glr.stack[*node].state, lookahead)
+                        .contains(&Action::Accept)
+                });
+                let root = glr.symbol_nodes.get(&(start_symbol, 0, position));
+                return match (accepted, root) {
+                    (true, Some(root)) => Ok(Forest {
+                        nodes: glr.nodes,
+                        root: *root,
+                    }),
+                    _ => Err(glr.error(&frontier, input, position..end, terminal)),
+                };
+            }
+
+            let leaf = glr.nodes.len();
+            glr.nodes.push(ForestNode::Leaf {
+                terminal,
+                text: input[position..end].to_owned(),
+                start: position,
+            });
+            let level = glr.offsets.len();
+            glr.offsets.push(end);
+            let mut shifted: Vec<usize> = Vec::new();
+            for node in frontier.iter().cloned() {
+                for action in glr.actions(glr.stack[node].state, lookahead) {
+                    if let Action::Shift(next) = action {
+                        let existing = shifted.iter().find(|n| glr.stack[**n].state == next);
+                        match existing {
+                            Some(existing) => glr.stack[*existing].edges.push((node, leaf)),
+                            None => shifted.push(glr.push_node(next, level, (node, leaf))),
+                        }
+                    }
+                }
+            }
+            if shifted.is_empty() {
+                return Err(glr.error(&frontier, input, position..end, terminal));
+            }
+            frontier = shifted;
+            position = end;
+        }
+    }
+}
+
+impl<'a> Glr<'a> {
+    /// Every action in a cell, including the ones that lost a conflict.
+    fn actions(&self, state: usize, lookahead: &Symbol) -> Vec<Action> {
+        match self.conflicts.get(&(state, lookahead.clone())) {
+            Some(actions) => actions.clone(),
+            None => self.parser.action(state, lookahead).into_iter().collect(),
+        }
+    }
+
+    /// A syntax error on a token, expecting every terminal some state of the
+    /// frontier shifts or accepts on. Terminals only reduced on aren't
+    /// counted, since SLR reduces on lookaheads it then can't shift.
+    fn error(
+        &self,
+        frontier: &[usize],
+        input: &str,
+        span: Range<usize>,
+        terminal: usize,
+    ) -> ParseError {
+        let expected = self
+            .parser
+            .terminal_columns()
+            .iter()
+            .enumerate()
+            .filter(|(_, symbol)| {
+                frontier.iter().any(|node| {
+                    let actions = self.actions(self.stack[*node].state, symbol);
+                    actions
+                        .iter()
+                        .any(|action| !matches!(action, Action::Reduce(_)))
+                })
+            })
+            .map(|(column, _)| column)
+            .collect();
+        ParseError::new(input, span, Some(terminal), expected)
+    }
+
+    fn push_node(&mut self, state: usize, level: usize, edge: (usize, NodeId)) -> usize {
+        self.stack.push(StackNode {
+            state,
+            edges: vec![edge],
+        });
+        self.levels.push(level);
+        self.stack.len() - 1
+    }
+
+    /// Makes every reduction possible on the lookahead from the frontier,
+    /// adding the nodes they go to to the frontier.
+    ///
+    /// Adding an edge to a node whose reductions were already made opens up
+    /// new paths, so all the reductions are made again until no new edges
+    /// show up; the forest and stack ignore what they already have.
+    fn reduce_all(&mut self, frontier: &mut Vec<usize>, lookahead: &Symbol) {
+        let mut pending = frontier.clone();
+        loop {
+            let mut new_edges = false;
+            while let Some(node) = pending.pop() {
+                for action in self.actions(self.stack[node].state, lookahead) {
+                    if let Action::Reduce(n) = action {
+                        new_edges |= self.reduce(node, n, frontier, &mut pending);
+                    }
+                }
+            }
+            if !new_edges {
+                break;
+            }
+            pending = frontier.clone();
+        }
+    }
+
+    /// Reduces by production `n` along every path down from `node`, returning
+    /// whether an edge was added to a node already on the frontier.
+    fn reduce(
+        &mut self,
+        node: usize,
+        n: usize,
+        frontier: &mut Vec<usize>,
+        pending: &mut Vec<usize>,
+    ) -> bool {
+        let (lhs, symbols) = &self.parser.productions[n];
+        let end = self.offsets[self.levels[node]];
+        let mut new_edges = false;
+        for (target, children) in self.paths(node, symbols.len()) {
+            let start = self.offsets[self.levels[target]];
+            let symbol_node = self.symbol_node(*lhs, start, end);
+            let family = Family {
+                production: n,
+                children,
+            };
+            if let ForestNode::Symbol { families, .. } = &mut self.nodes[symbol_node] {
+                if !families.contains(&family) {
+                    families.push(family);
+                }
+            }
+
+            let next = self.parser.table.0[self.stack[target].state].1[&Symbol::NT(*lhs)];
+            match frontier.iter().find(|n| self.stack[**n].state == next) {
+                Some(existing) => {
+                    let edges = &mut self.stack[*existing].edges;
+                    if !edges.iter().any(|(below, _)| *below == target) {
+                        edges.push((target, symbol_node));
+                        new_edges = true;
+                    }
+                }
+                None => {
+                    let new = self.push_node(next, self.levels[node], (target, symbol_node));
+                    frontier.push(new);
+                    pending.push(new);
+                }
+            }
+        }
+        new_edges
+    }
+
+    /// Every path of `len` edges down from `node`: where it ends, and the
+    /// forest nodes along it from left to right.
+    fn paths(&self, node: usize, len: usize) -> Vec<(usize, Vec<NodeId>)> {
+        if len == 0 {
+            return vec![(node, Vec::new())];
+        }
+        let mut paths = Vec::new();
+        for (below, label) in self.stack[node].edges.iter() {
+            for (target, mut children) in self.paths(*below, len - 1) {
+                children.push(*label);
+                paths.push((target, children));
+            }
+        }
+        paths
+    }
+
+    fn symbol_node(&mut self, nonterminal: Id, start: usize, end: usize) -> NodeId {
+        if let Some(id) = self.symbol_nodes.get(&(nonterminal, start, end)) {
+            return *id;
+        }
+        let id = self.nodes.len();
+        self.nodes.push(ForestNode::Symbol {
+            nonterminal,
+            start,
+            end,
+            families: Vec::new(),
+        });
+        self.symbol_nodes.insert((nonterminal, start, end), id);
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Forest, ForestNode};
+    use crate::runtime::Tree;
+    use crate::Grammar;
+
+    fn make_ambiguous() -> Grammar {
+        make_grammar! {
+            start_symbols: [E],
+            terminals: {
+                Add: r"\+",
+                N: r"1",
+            },
+            productions: {
+                E: [ [E, Add, E], [N] ],
+            }
+        }
+    }
+
+    fn is_sum(forest: &Forest, id: usize) -> bool {
+        match forest.node(id) {
+            ForestNode::Symbol { families, .. } => families.iter().any(|f| f.production == 0),
+            ForestNode::Leaf { .. } => false,
+        }
+    }
+
+    #[test]
+    fn test_ambiguous() {
+        let parser = make_ambiguous().build().unwrap();
+        assert!(!parser.conflicts.is_empty());
+
+        let forest = parser.parse_glr("E", "1+1").unwrap();
+        assert!(!forest.is_ambiguous());
+        assert_eq!(forest.count_trees(), 1);
+
+        let forest = parser.parse_glr("E", "1+1+1").unwrap();
+        assert!(forest.is_ambiguous());
+        assert_eq!(forest.count_trees(), 2);
+        // the two parses share everything but the root and one sum
+        let sums = (0..forest.nodes.len())
+            .filter(|id| is_sum(&forest, *id))
+            .count();
+        assert_eq!(sums, 3);
+
+        assert_eq!(parser.parse_glr("E", "1+1+1+1").unwrap().count_trees(), 5);
+        assert_eq!(parser.parse_glr("E", "1++1").unwrap_err().position(), 2);
+        assert_eq!(parser.parse_glr("E", "1+").unwrap_err().position(), 2);
+    }
+
+    #[test]
+    fn test_disambiguate() {
+        let parser = make_ambiguous().build().unwrap();
+        let n = |at: usize| Tree::node(1, vec![Tree::Leaf(1, "1".to_owned(), at..at + 1)], at);
+        let add = |left: Tree, right| {
+            let at = left.span().end;
+            let plus = Tree::Leaf(0, "+".to_owned(), at..at + 1);
+            Tree::node(0, vec![left, plus, right], at)
+        };
+        let left = add(add(n(0), n(2)), n(4));
+        let right = add(n(0), add(n(2), n(4)));
+
+        // a merge function picks between the alternatives
+        let forest = parser.parse_glr("E", "1+1+1").unwrap();
+        let mut merges = 0;
+        let tree = forest.tree(|a, b| {
+            merges += 1;
+            if a == right {
+                a
+            } else {
+                b
+            }
+        });
+        assert_eq!(merges, 1);
+        assert_eq!(tree, Some(right));
+
+        // a filter makes sums left associative
+        let mut forest = forest;
+        forest.retain(|forest, _, family| {
+            family.production != 0 || !is_sum(forest, family.children[2])
+        });
+        assert!(!forest.is_ambiguous());
+        assert_eq!(forest.count_trees(), 1);
+        assert_eq!(forest.tree(|_, _| unreachable!()), Some(left));
+
+        // filtering everything out leaves no tree
+        forest.retain(|_, _, family| family.production != 1);
+        assert_eq!(forest.count_trees(), 0);
+        assert_eq!(forest.tree(|a, _| a), None);
+    }
+
+    #[test]
+    fn test_more_lookahead() {
+        // unambiguous, but telling A from B takes two tokens of lookahead
+        let grammar = make_grammar! {
+            start_symbols: [S],
+            terminals: {
+                A: r"a",
+                X: r"x",
+                Y: r"y",
+                Z: r"z",
+            },
+            productions: {
+                S: [ [P, X, Y], [Q, X, Z] ],
+                P: [ [A] ],
+                Q: [ [A] ],
+            }
+        };
+        let parser = grammar.build().unwrap();
+        assert!(!parser.conflicts.is_empty());
+
+        let forest = parser.parse_glr("S", "axz").unwrap();
+        assert!(!forest.is_ambiguous());
+        let tree = forest.tree(|_, _| unreachable!()).unwrap();
+        match tree {
+            Tree::Node(1, children, span) => {
+                assert_eq!(span, 0..3);
+                assert_eq!(
+                    children[0],
+                    Tree::Node(3, vec![Tree::Leaf(0, "a".to_owned(), 0..1)], 0..1)
+                )
+            }
+            other => panic!("expected S -> Q X Z, got {:?}", other),
+        }
+        assert!(parser.parse_glr("S", "axx").is_err());
+    }
+
+    #[test]
+    fn test_epsilon_and_cycles() {
+        // empty productions, and a cycle that would give infinitely many trees
+        let grammar = make_grammar! {
+            start_symbols: [S],
+            terminals: {
+                B: r"b",
+            },
+            productions: {
+                S: [ [A, S, A], [B], [S] ],
+                A: [ [ɛ] ],
+            }
+        };
+        let parser = grammar.build().unwrap();
+        let forest = parser.parse_glr("S", "b").unwrap();
+        assert!(forest.is_ambiguous());
+        // only S -> B doesn't go around a cycle
+        assert_eq!(forest.count_trees(), 1);
+        match forest.node(forest.root()) {
+            ForestNode::Symbol {
+                start,
+                end,
+                families,
+                ..
+            } => {
+                assert_eq!((*start, *end), (0, 1));
+                let mut productions = families.iter().map(|f| f.production).collect::<Vec<_>>();
+                productions.sort();
+                assert_eq!(productions, vec![0, 1, 2]);
+            }
+            other => panic!("expected a symbol node, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_shared_cycles() {
+        // S and T form a cycle that U reaches from outside it, so what T gives
+        // depends on whether it's reached through S
+        let grammar = make_grammar! {
+            start_symbols: [R],
+            terminals: {
+                B: r"b",
+            },
+            productions: {
+                R: [ [S], [U] ],
+                S: [ [T], [B] ],
+                T: [ [S], [B] ],
+                U: [ [T] ],
+            }
+        };
+        let parser = grammar.build().unwrap();
+        let forest = parser.parse_glr("R", "b").unwrap();
+        // R -> S -> B, R -> S -> T -> B, R -> U -> T -> B and
+        // R -> U -> T -> S -> B, but not R -> S -> T -> S -> B
+        assert_eq!(forest.count_trees(), 4);
+        assert!(forest.tree(|a, _| a).is_some());
+    }
+}
@@ -1,11 +1,17 @@
+use std::cmp::Ordering;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
-use std::io::Write;
+use std::fmt;
+use std::sync::OnceLock;
 
 use indexmap::IndexMap;
+use regex::Regex;
 use symbol::Symbol as Id;
 
-use crate::items::LR0Item;
-use crate::parser::{Action, ParseTable};
+use crate::analysis::GrammarAnalysis;
+use crate::items::{Item, LR0Item};
+use crate::lints::{Lint, Lints};
+use crate::ll::LLConflict;
+use crate::parser::{Action, Conflict, ParseTable};
 use crate::Parser;
 
 #[derive(Debug, Error)]
@@ -16,74 +22,170 @@ pub enum GrammarError {
     #[error("Invalid symbol: {0}")]
     InvalidSymbol(Id),
 
+    #[error("Invalid pattern for terminal: {0}")]
+    InvalidPattern(Id),
+
     #[error("Start symbols must be nonterminals: {0}")]
     StartingTerminal(Id),
+
+    #[error("Undefined nonterminal: {}", display_names(.0))]
+    UndefinedNonterminal(Vec<Id>),
+
+    #[error("{}", display_lines(.0))]
+    Lints(Vec<Lint>),
+
+    #[error("Not LL(1):\n{}", display_lines(.0))]
+    NotLL1(Vec<LLConflict>),
 }
 
-#[derive(Debug)]
+fn display_lines(lints: &[impl ToString]) -> String {
+    let lints = lints.iter().map(ToString::to_string).collect::<Vec<_>>();
+    lints.join("\n")
+}
+
+fn display_names(names: &[Id]) -> String {
+    let names = names.iter().map(|name| name.as_str()).collect::<Vec<_>>();
+    names.join(", ")
+}
+
+/// Options for `Grammar::build_with`.
+#[derive(Clone, Debug, Default)]
+pub struct BuildOptions {
+    pub lints: Lints,
+    pub default_reductions: DefaultReductions,
+}
+
+/// Which states get a default reduction, made whenever the lookahead has no
+/// action of its own.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DefaultReductions {
+    /// No state; every reduction is keyed by its lookahead
+    #[default]
+    None,
+
+    /// States whose only action is a single reduction, which then never need
+    /// to look at the lookahead
+    Consistent,
+
+    /// Every state that can reduce, using its most common reduction. Errors
+    /// are then only caught once the parser tries to shift.
+    All,
+}
+
+#[derive(Clone, Debug)]
 pub struct Grammar {
     pub(crate) start_symbols: Vec<Id>,
     pub(crate) terminals: IndexMap<Id, String>,
     pub(crate) productions: IndexMap<Id, Vec<Production>>,
+
+    /// What inserting or deleting each terminal costs error repair, for the
+    /// terminals that don't cost the default of 1
+    pub(crate) repair_costs: IndexMap<Id, usize>,
 }
 
 impl Grammar {
-    fn create_grammar_helper(&self) -> Result<GrammarHelper, GrammarError> {
-        // name -> symbol map
-        let grammar_symbols = {
-            let mut symbols = HashMap::new();
-            // should have no problem with terminals only
-            for terminal in self.terminals.keys() {
-                symbols.insert(terminal.to_owned(), Symbol::T(terminal.clone()));
+    /// Maps the name of every terminal and nonterminal to its symbol, in the
+    /// order they're declared (terminals first, then `error`).
+    pub(crate) fn grammar_symbols(&self) -> Result<IndexMap<Id, Symbol>, GrammarError> {
+        let mut symbols = IndexMap::new();
+        for terminal in self.terminals.keys() {
+            if terminal.as_str() == ERROR {
+                return Err(GrammarError::NameConflict(*terminal));
             }
-            for nonterminal in self.productions.keys() {
-                if symbols.contains_key(nonterminal) {
-                    return Err(GrammarError::NameConflict(nonterminal.clone()));
-                }
-                symbols.insert(nonterminal.to_owned(), Symbol::NT(nonterminal.clone()));
+            symbols.insert(terminal.to_owned(), Symbol::T(*terminal));
+        }
+        symbols.insert(Id::from(ERROR), Symbol::error());
+        for nonterminal in self.productions.keys() {
+            if symbols.contains_key(nonterminal) {
+                return Err(GrammarError::NameConflict(*nonterminal));
             }
-            symbols
-        };
+            symbols.insert(nonterminal.to_owned(), Symbol::NT(*nonterminal));
+        }
+        Ok(symbols)
+    }
 
-        // build items
-        let mut counter = 0;
-        let mut grammar_productions = HashMap::new();
-        let items = {
-            let mut items = Vec::new();
-            for (nonterminal, productions) in self.productions.iter() {
-                grammar_productions.insert(nonterminal.clone(), Vec::new());
-                for production in productions {
-                    grammar_productions
-                        .get_mut(nonterminal)
-                        .unwrap()
-                        .push((counter, production.clone()));
-                    items.push(LR0Item {
-                        lhs: nonterminal.clone(),
-                        dot: 0,
-                        symbols: production.symbols(&grammar_symbols)?,
-                        is_start: false,
-                        production_number: Some(counter),
-                    });
-                    counter += 1;
+    /// Checks that every symbol the productions use is defined, listing the
+    /// ones that aren't in the order they're first used.
+    pub(crate) fn check_symbols(
+        &self,
+        grammar_symbols: &IndexMap<Id, Symbol>,
+    ) -> Result<(), GrammarError> {
+        let mut undefined = Vec::new();
+        for production in self.productions.values().flatten() {
+            for symbol in production.0.iter() {
+                let defined = symbol.as_str() == "\u{025b}" || grammar_symbols.contains_key(symbol);
+                if !defined && !undefined.contains(symbol) {
+                    undefined.push(*symbol);
                 }
             }
-            items
-        };
+        }
+        if undefined.is_empty() {
+            Ok(())
+        } else {
+            Err(GrammarError::UndefinedNonterminal(undefined))
+        }
+    }
 
-        // start symbols must be nonterminals
-        for symbol in self.start_symbols.iter() {
-            if let Some(Symbol::T(symbol)) = grammar_symbols.get(symbol) {
-                return Err(GrammarError::StartingTerminal(symbol.clone()));
+    /// Checks that the start symbols are nonterminals, each listed once.
+    pub(crate) fn check_start_symbols(
+        &self,
+        grammar_symbols: &IndexMap<Id, Symbol>,
+    ) -> Result<(), GrammarError> {
+        for (i, symbol) in self.start_symbols.iter().enumerate() {
+            if self.start_symbols[..i].contains(symbol) {
+                return Err(GrammarError::NameConflict(*symbol));
+            }
+            match grammar_symbols.get(symbol) {
+                Some(Symbol::NT(_)) => (),
+                Some(_) => return Err(GrammarError::StartingTerminal(*symbol)),
+                None => return Err(GrammarError::UndefinedNonterminal(vec![*symbol])),
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that every terminal's pattern is a regex the lexer can compile.
+    pub(crate) fn check_patterns(&self) -> Result<(), GrammarError> {
+        for (terminal, pattern) in self.terminals.iter() {
+            if Regex::new(&format!("^(?:{})", pattern)).is_err() {
+                return Err(GrammarError::InvalidPattern(*terminal));
+            }
+        }
+        Ok(())
+    }
+
+    fn create_grammar_helper(&self) -> Result<GrammarHelper, GrammarError> {
+        // name -> symbol map
+        let grammar_symbols = self.grammar_symbols()?;
+        let analysis = self.analyze()?;
+
+        // intern the productions, and index them by their lhs
+        let productions = analysis.productions.clone();
+        let mut productions_by_lhs = HashMap::new();
+        for (i, (lhs, _)) in productions.iter().enumerate() {
+            productions_by_lhs
+                .entry(*lhs)
+                .or_insert_with(Vec::new)
+                .push(i);
+        }
+
+        self.check_start_symbols(&grammar_symbols)?;
+        self.check_patterns()?;
+        for terminal in self.repair_costs.keys() {
+            if !self.terminals.contains_key(terminal) {
+                return Err(GrammarError::InvalidSymbol(*terminal));
             }
         }
 
         let grammar_helper = GrammarHelper {
-            grammar: &self,
             grammar_symbols,
-            canonical_collection: BTreeSet::new(),
-            first_sets: BTreeMap::new(),
-            follow_sets: BTreeMap::new(),
-            productions: grammar_productions,
+            n_productions: productions.len(),
+            productions,
+            productions_by_lhs,
+            kernels: Vec::new(),
+            state_numbers: HashMap::new(),
+            transitions: Vec::new(),
+            analysis,
         };
 
         Ok(grammar_helper)
@@ -91,32 +193,126 @@ impl Grammar {
 
     /// Builds the main Parser struct.
     pub fn build(self) -> Result<Parser, GrammarError> {
-        let mut grammar_helper = self.create_grammar_helper()?;
-        grammar_helper.init(self.start_symbols.clone());
-        grammar_helper.build();
+        self.build_with(&BuildOptions::default())
+    }
 
-        println!("canonical collection:");
-        for (i, item_set) in grammar_helper.canonical_collection.iter().enumerate() {
-            println!("state {}:", i);
-            for item in item_set {
-                println!(" - {:?}", item);
-            }
-            println!("");
+    /// Builds the main Parser struct with the given options.
+    ///
+    /// Lints set to `Deny` fail the build with `GrammarError::Lints`, while
+    /// lints set to `Warn` end up in `Parser::warnings`.
+    pub fn build_with(self, options: &BuildOptions) -> Result<Parser, GrammarError> {
+        let mut grammar_helper = self.create_grammar_helper()?;
+        let (warnings, denied) = options.lints.check(&self, &grammar_helper.analysis);
+        if !denied.is_empty() {
+            return Err(GrammarError::Lints(denied));
         }
 
-        let table = grammar_helper.parse_table();
+        let entry_states = grammar_helper.init(self.start_symbols.clone());
+        grammar_helper.build();
+
+        let states = grammar_helper.item_sets();
+        let (mut table, conflicts) = grammar_helper.parse_table(&states);
+        let default_reductions = default_reductions(&mut table, options.default_reductions);
+        let analysis = grammar_helper.analysis;
+        let entry_states = self
+            .start_symbols
+            .iter()
+            .cloned()
+            .zip(entry_states)
+            .collect();
+        let repair_costs = self
+            .terminals
+            .keys()
+            .map(|terminal| self.repair_costs.get(terminal).cloned().unwrap_or(1))
+            .chain(Some(1))
+            .collect();
 
         Ok(Parser {
             start_symbols: self.start_symbols,
+            entry_states,
             terminals: self.terminals,
-            nonterminals: self.productions.keys().cloned().collect::<HashSet<Id>>(),
-            // productions: grammar_productions,
+            nonterminals: self.productions.keys().cloned().collect(),
+            productions: analysis.productions.clone(),
+            analysis,
+            states,
             table,
+            default_reductions,
+            conflicts,
+            warnings,
+            repair_costs,
+            dense_tables: OnceLock::new(),
         })
     }
 }
 
-#[derive(Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+/// Renders the grammar back as a `make_grammar!` invocation.
+impl fmt::Display for Grammar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "make_grammar! {{")?;
+
+        let start_symbols = self
+            .start_symbols
+            .iter()
+            .map(|symbol| symbol.as_str())
+            .collect::<Vec<_>>();
+        writeln!(f, "    start_symbols: [{}],", start_symbols.join(", "))?;
+
+        writeln!(f, "    terminals: {{")?;
+        for (name, regex) in self.terminals.iter() {
+            writeln!(f, "        {}: {},", name, raw_string(regex))?;
+        }
+        writeln!(f, "    }},")?;
+
+        writeln!(f, "    productions: {{")?;
+        for (name, productions) in self.productions.iter() {
+            let productions = productions
+                .iter()
+                .map(|production| {
+                    let symbols = production
+                        .0
+                        .iter()
+                        .map(|symbol| symbol.as_str())
+                        .collect::<Vec<_>>();
+                    format!("[{}]", symbols.join(", "))
+                })
+                .collect::<Vec<_>>();
+            writeln!(f, "        {}: [ {} ],", name, productions.join(", "))?;
+        }
+        if self.repair_costs.is_empty() {
+            writeln!(f, "    }}")?;
+        } else {
+            writeln!(f, "    }},")?;
+            writeln!(f, "    repair_costs: {{")?;
+            for (name, cost) in self.repair_costs.iter() {
+                writeln!(f, "        {}: {},", name, cost)?;
+            }
+            writeln!(f, "    }}")?;
+        }
+
+        write!(f, "}}")
+    }
+}
+
+/// Quotes a string as a raw string literal, with enough `#`s to contain it.
+fn raw_string(s: &str) -> String {
+    let mut hashes = 0;
+    let mut rest = s;
+    while let Some(i) = rest.find('"') {
+        rest = &rest[i + 1..];
+        let run = rest.len() - rest.trim_start_matches('#').len();
+        hashes = hashes.max(run + 1);
+    }
+    let hashes = "#".repeat(hashes);
+    format!("r{}\"{}\"{}", hashes, s, hashes)
+}
+
+/// The terminal that error productions use, like in Yacc. It's never lexed;
+/// the parser shifts it in place of the input it skips to recover from a
+/// syntax error.
+pub const ERROR: &str = "error";
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
 pub enum Symbol {
     T(Id),
     NT(Id),
@@ -125,6 +321,11 @@ pub enum Symbol {
 }
 
 impl Symbol {
+    /// The reserved `error` terminal.
+    pub fn error() -> Symbol {
+        Symbol::T(Id::from(ERROR))
+    }
+
     pub fn name(&self) -> Id {
         match self {
             Symbol::T(name) | Symbol::NT(name) => *name,
@@ -132,6 +333,49 @@ impl Symbol {
             Symbol::EOF => Id::from("$"),
         }
     }
+
+    fn sort_key(&self) -> (u8, &'static str) {
+        match self {
+            Symbol::T(name) => (0, name.as_str()),
+            Symbol::NT(name) => (1, name.as_str()),
+            Symbol::Epsilon => (2, ""),
+            Symbol::EOF => (3, ""),
+        }
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+// `Id`'s `Ord` compares addresses while its `PartialOrd` compares contents,
+// which breaks sorted collections that end up using both, so symbols are
+// ordered by name instead.
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+}
+
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Writes a right hand side as its symbols separated by spaces, or `ɛ` if it's
+/// empty.
+pub(crate) fn symbol_list(symbols: &[Symbol]) -> String {
+    if symbols.is_empty() {
+        return "\u{025b}".to_owned();
+    }
+    symbols
+        .iter()
+        .map(Symbol::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
 }
 
 #[derive(Clone, Debug)]
@@ -143,19 +387,35 @@ impl<T: Iterator<Item = Id>> From<T> for Production {
     }
 }
 
+impl fmt::Display for Production {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "\u{025b}");
+        }
+        for (i, symbol) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", symbol)?;
+        }
+        Ok(())
+    }
+}
+
 impl Production {
     pub fn symbols(
         &self,
-        grammar_symbols: &HashMap<Id, Symbol>,
+        grammar_symbols: &IndexMap<Id, Symbol>,
     ) -> Result<Vec<Symbol>, GrammarError> {
         let mut symbols = Vec::new();
         for symbol_candidate in self.0.iter() {
             if symbol_candidate.as_str() == "\u{025b}" {
-                symbols.push(Symbol::Epsilon);
+                // epsilon just means the production is empty
+                continue;
             } else if let Some(symbol) = grammar_symbols.get(symbol_candidate) {
                 symbols.push(symbol.clone());
             } else {
-                return Err(GrammarError::InvalidSymbol(symbol_candidate.clone()));
+                return Err(GrammarError::InvalidSymbol(*symbol_candidate));
             }
         }
         Ok(symbols)
@@ -163,352 +423,300 @@ impl Production {
 }
 
 #[derive(Debug)]
-struct GrammarHelper<'a> {
-    /// The reference to the actual grammar
-    grammar: &'a Grammar,
-
+struct GrammarHelper {
     /// This is a map from the name to the actual symbol
-    grammar_symbols: HashMap<Id, Symbol>,
+    grammar_symbols: IndexMap<Id, Symbol>,
 
-    /// A map from the name to the index of the production
-    productions: HashMap<Id, Vec<(usize, Production)>>,
-    canonical_collection: BTreeSet<BTreeSet<LR0Item>>,
-    first_sets: BTreeMap<Symbol, BTreeSet<Symbol>>,
-    follow_sets: BTreeMap<Symbol, BTreeSet<Symbol>>,
-}
+    /// The grammar's productions, followed by the augmented start productions
+    productions: Vec<(Id, Vec<Symbol>)>,
 
-impl<'a> GrammarHelper<'a> {
-    pub fn init(&mut self, start_symbols: Vec<Id>) {
-        self.compute_first_sets();
-        self.compute_follow_sets();
-        // TODO: predict sets?
+    /// How many of `productions` come from the grammar
+    n_productions: usize,
 
-        println!("First sets:");
-        for (sym, set) in self.first_sets.iter() {
-            println!(" {:?} - {:?}", sym, set);
-        }
-        println!("");
+    /// A map from the name to the indices of its productions
+    productions_by_lhs: HashMap<Id, Vec<usize>>,
 
-        println!("Follow sets:");
-        for (sym, set) in self.follow_sets.iter() {
-            println!(" {:?} - {:?}", sym, set);
-        }
-        println!("");
+    /// The kernel of each state, sorted, indexed by state number
+    kernels: Vec<Vec<Item>>,
+
+    /// Maps each kernel back to its state number
+    state_numbers: HashMap<Vec<Item>, usize>,
+
+    /// The outgoing shifts and gotos of each state, in declaration order
+    transitions: Vec<Vec<(Symbol, usize)>>,
+    analysis: GrammarAnalysis,
+}
 
+impl GrammarHelper {
+    /// Adds the entry state of every start symbol to the canonical collection
+    /// and returns their state numbers in the same order.
+    ///
+    /// Each start symbol S gets its own augmented production S' -> S, so the
+    /// entry states (and the states that accept) are distinct per start symbol.
+    pub fn init(&mut self, start_symbols: Vec<Id>) -> Vec<usize> {
+        let mut entry_states = Vec::new();
         for start_symbol in start_symbols {
-            let mut new_set = BTreeSet::new();
-            let new_item = LR0Item {
-                lhs: Id::from(format!("{}'", start_symbol)),
+            let lhs = Id::from(format!("{}'", start_symbol));
+            self.productions.push((lhs, vec![Symbol::NT(start_symbol)]));
+            let item = Item {
+                production: self.productions.len() - 1,
                 dot: 0,
-                symbols: vec![Symbol::NT(start_symbol)],
-                is_start: true,
-                production_number: None,
             };
-            new_set.insert(new_item);
-            self.canonical_collection.insert(self.closure(new_set));
+            entry_states.push(self.add_state(vec![item]));
         }
+        entry_states
     }
 
-    fn compute_first_sets(&mut self) {
-        loop {
-            let mut changes = false;
-            for (name, symbol) in self.grammar_symbols.iter() {
-                match symbol {
-                    Symbol::T(_) => {
-                        if !self.first_sets.contains_key(symbol) {
-                            self.first_sets
-                                .insert(symbol.clone(), vec![symbol.clone()].into_iter().collect());
-                            changes = true;
-                        }
-                    }
-                    Symbol::NT(_) => {
-                        if !self.first_sets.contains_key(symbol) {
-                            self.first_sets.insert(symbol.clone(), BTreeSet::new());
-                        }
-                        let mut first_set = self.first_sets.get_mut(symbol).unwrap().clone();
-                        'outer: for production in self.grammar.productions.get(name).unwrap() {
-                            let symbol_list = production.symbols(&self.grammar_symbols).unwrap();
-                            for yi in symbol_list {
-                                if yi == Symbol::Epsilon {
-                                    if first_set.insert(Symbol::Epsilon) {
-                                        changes = true;
-                                    }
-                                }
-
-                                if let Some(sym_first_set) = self.first_sets.get(&yi) {
-                                    // if there's extra elements in FIRST(Yi)
-                                    if !first_set.is_superset(sym_first_set) {
-                                        first_set =
-                                            first_set.union(sym_first_set).cloned().collect();
-                                        changes = true;
-                                    }
-
-                                    // if it contains epsilon, continue looking
-                                    // this means we see X -> ... "" "" Yi
-                                    // and this current one is also "", so keep going
-                                    // otherwise, break
-                                    if !sym_first_set.contains(&Symbol::Epsilon) {
-                                        continue 'outer;
-                                    }
-                                }
-
-                                // no symbols calculated here yet, continue
-                                continue 'outer;
-                            }
-
-                            // at this point we've reached the end of the list
-                            // since we haven't broken out, it means the last one also contains epsilon
-                            // add epsilon to the first set now
-                            first_set.insert(Symbol::Epsilon);
-                            changes = true;
-                        }
-                        self.first_sets.insert(symbol.clone(), first_set);
-                    }
-                    Symbol::EOF => {
-                        if !self.first_sets.contains_key(symbol) {
-                            self.first_sets.insert(symbol.clone(), BTreeSet::new());
-                        }
-                        if self.first_sets.get_mut(symbol).unwrap().insert(Symbol::EOF) {
-                            changes = true;
-                        }
-                    }
-                    Symbol::Epsilon => {}
-                }
-            }
-
-            if !changes {
-                break;
-            }
+    /// Returns the number of the state with the given kernel, numbering it
+    /// first if it's new.
+    fn add_state(&mut self, kernel: Vec<Item>) -> usize {
+        if let Some(n) = self.state_numbers.get(&kernel) {
+            return *n;
         }
+        let n = self.kernels.len();
+        self.kernels.push(kernel.clone());
+        self.state_numbers.insert(kernel, n);
+        n
     }
 
-    #[allow(non_snake_case)]
-    fn compute_follow_sets(&mut self) {
-        for start_symbol in self.grammar.start_symbols.iter() {
-            let start_symbol = Symbol::NT(start_symbol.to_owned());
-            if !self.follow_sets.contains_key(&start_symbol) {
-                self.follow_sets
-                    .insert(start_symbol.clone(), BTreeSet::new());
-            }
-            // put $ in FOLLOW(S) for every start symbol
-            self.follow_sets
-                .get_mut(&start_symbol)
-                .unwrap()
-                .insert(Symbol::EOF);
-        }
-
-        for nonterminal in self.grammar.productions.keys() {
-            let nonterminal = Symbol::NT(nonterminal.to_owned());
-            if !self.follow_sets.contains_key(&nonterminal) {
-                self.follow_sets
-                    .insert(nonterminal.clone(), BTreeSet::new());
-            }
-        }
-
-        loop {
-            let mut changes = false;
-
-            for (nonterminal, productions) in self.grammar.productions.iter() {
-                let nonterminal = Symbol::NT(nonterminal.to_owned());
-                for production in productions {
-                    let symbol_list = production.symbols(&self.grammar_symbols).unwrap();
-                    // if A -> aBb, then take all of {FIRST(b) - e} and add it to FOLLOW(B)
-                    if symbol_list.len() >= 2 {
-                        for window in symbol_list.windows(2) {
-                            let B = &window[0];
-                            let b = &window[1];
-
-                            let mut b_first_set = self.first_sets.get(b).unwrap().clone();
-                            b_first_set.remove(&Symbol::Epsilon);
-
-                            if let Some(follow_set) = self.follow_sets.get_mut(&B) {
-                                if !follow_set.is_superset(&b_first_set) {
-                                    follow_set.append(&mut b_first_set);
-                                    changes = true;
-                                }
-                            }
-                        }
-                    }
-
-                    // if A -> aB, then everythign in FOLLOW(A) is in FOLLOW(B)
-                    if symbol_list.len() >= 1 {
-                        for window in symbol_list.windows(2) {
-                            let B = &window[1];
-
-                            let mut a_follow_set =
-                                self.follow_sets.get_mut(&nonterminal).unwrap().clone();
-                            if let Some(follow_set) = self.follow_sets.get_mut(&B) {
-                                if !follow_set.is_superset(&a_follow_set) {
-                                    follow_set.append(&mut a_follow_set);
-                                    changes = true;
-                                }
-                            }
-                        }
-                    }
-
-                    // if A -> aBb, and FIRST(b) contains epsilon, then everything in FOLLOW(A) is in FOLLOW(B)
-                    if symbol_list.len() >= 2 {
-                        let b = &symbol_list[symbol_list.len() - 1];
-                        let B = &symbol_list[symbol_list.len() - 2];
-                        let b_first_set = self.first_sets.get(b).unwrap().clone();
-                        if b_first_set.contains(&Symbol::Epsilon) {
-                            let mut a_follow_set =
-                                self.follow_sets.get_mut(&nonterminal).unwrap().clone();
-                            if let Some(follow_set) = self.follow_sets.get_mut(&B) {
-                                if !follow_set.is_superset(&a_follow_set) {
-                                    follow_set.append(&mut a_follow_set);
-                                    changes = true;
-                                }
-                            }
-                        }
-                    }
+    // Figure 4.34 of the dragon book, as a worklist over kernels: states are
+    // numbered in the order they're discovered from the entry states, trying
+    // symbols in declaration order, so the numbering only depends on the
+    // grammar.
+    pub fn build(&mut self) {
+        let mut i = 0;
+        while i < self.kernels.len() {
+            // group the advanced items by the symbol they're advanced over
+            let mut successors = BTreeMap::new();
+            for item in self.closure(&self.kernels[i]) {
+                if let Some(symbol) = self.symbol_after_dot(item) {
+                    let (index, _, _) = self.grammar_symbols.get_full(&symbol.name()).unwrap();
+                    successors.entry(index).or_insert_with(Vec::new).push(Item {
+                        production: item.production,
+                        dot: item.dot + 1,
+                    });
                 }
             }
 
-            if !changes {
-                break;
+            let mut transitions = Vec::new();
+            for (index, mut kernel) in successors {
+                kernel.sort();
+                let (_, symbol) = self.grammar_symbols.get_index(index).unwrap();
+                let symbol = symbol.clone();
+                transitions.push((symbol, self.add_state(kernel)));
             }
+            self.transitions.push(transitions);
+            i += 1;
         }
     }
 
-    // Figure 4.34 of the dragon book
-    #[allow(non_snake_case)]
-    pub fn build(&mut self) {
-        let mut to_add = BTreeSet::new();
-        loop {
-            for item_set in self.canonical_collection.iter() {
-                for X in self.grammar_symbols.values() {
-                    let g = self.goto(item_set.clone(), X.clone());
-                    if !g.is_empty() && !self.canonical_collection.contains(&g) {
-                        to_add.insert(g);
-                    }
-                }
-            }
-
-            if to_add.is_empty() {
-                break;
-            } else {
-                self.canonical_collection.extend(to_add.clone());
-                to_add.clear();
-            }
-        }
+    /// Expands every state's kernel into its full item set.
+    pub fn item_sets(&self) -> Vec<BTreeSet<LR0Item>> {
+        self.kernels
+            .iter()
+            .map(|kernel| {
+                self.closure(kernel)
+                    .into_iter()
+                    .map(|item| self.lr0_item(item))
+                    .collect()
+            })
+            .collect()
     }
 
     /// Converts this GrammarHelper instance into a ParseTable.
-    pub fn parse_table(&self) -> ParseTable {
+    ///
+    /// Reductions are placed on FOLLOW(lhs) of the reduced nonterminal (SLR).
+    /// Cells that would receive more than one action are recorded as
+    /// conflicts; the table keeps a shift over a reduce, and otherwise the
+    /// reduce with the lowest production number, like yacc does.
+    pub fn parse_table(&self, item_sets: &[BTreeSet<LR0Item>]) -> (ParseTable, Vec<Conflict>) {
         let mut states = Vec::new();
-        let reverse_map: BTreeMap<_, _> = self
-            .canonical_collection
-            .iter()
-            .enumerate()
-            .map(|(a, b)| (b, a))
-            .collect();
-        for (i, item_set) in self.canonical_collection.iter().enumerate() {
+        let mut conflicts = Vec::new();
+        for (i, item_set) in item_sets.iter().enumerate() {
             let mut action = HashMap::new();
             let mut goto = HashMap::new();
+            let transitions = self.transitions[i]
+                .iter()
+                .cloned()
+                .collect::<HashMap<_, _>>();
             for item in item_set {
-                if let Some(next_symbol) = item.symbol_after_dot() {
-                    let g = self.goto(item_set.clone(), next_symbol.clone());
-                    if let Some(num) = reverse_map.get(&g) {
-                        action.insert(next_symbol, Action::Shift(*num));
-                    }
+                if let Some(next_symbol @ Symbol::T(_)) = item.symbol_after_dot() {
+                    let num = transitions[&next_symbol];
+                    insert_action(
+                        &mut action,
+                        &mut conflicts,
+                        i,
+                        next_symbol,
+                        Action::Shift(num),
+                    );
                 }
 
                 if item.dot_at_end() {
                     if item.is_start {
-                        action.insert(Symbol::EOF, Action::Accept);
-                    } else {
-                        let prev_symbol = item.symbol_before_dot().unwrap();
-                        if let Some(n) = item.production_number {
-                            action.insert(prev_symbol, Action::Reduce(n));
+                        insert_action(&mut action, &mut conflicts, i, Symbol::EOF, Action::Accept);
+                    } else if let Some(n) = item.production_number {
+                        let follow_set = &self.analysis.follow_sets[&Symbol::NT(item.lhs)];
+                        for lookahead in follow_set {
+                            insert_action(
+                                &mut action,
+                                &mut conflicts,
+                                i,
+                                lookahead.clone(),
+                                Action::Reduce(n),
+                            );
                         }
                     }
                 }
+            }
 
-                for nonterminal in self.grammar.productions.keys() {
-                    let nterm = Symbol::NT(nonterminal.to_owned());
-                    let g = self.goto(item_set.clone(), nterm.clone());
-                    if let Some(num) = reverse_map.get(&g) {
-                        goto.insert(nterm, *num);
-                    }
+            for (symbol, num) in self.transitions[i].iter() {
+                if let Symbol::NT(_) = symbol {
+                    goto.insert(symbol.clone(), *num);
                 }
             }
             states.push((action, goto));
         }
-        ParseTable(states)
+        (ParseTable(states), conflicts)
     }
 
-    fn goto(&self, item_set: BTreeSet<LR0Item>, X: Symbol) -> BTreeSet<LR0Item> {
-        let mut valid_items = BTreeSet::new();
-        for item in item_set {
-            if let Some(next_symbol) = item.name_after_dot() {
-                if next_symbol == X.name() {
-                    let mut new_item = item.clone();
-                    new_item.dot += 1;
-                    valid_items.insert(new_item);
-                }
-            }
-        }
-        self.closure(valid_items)
-    }
-
-    fn closure(&self, mut item_set: BTreeSet<LR0Item>) -> BTreeSet<LR0Item> {
-        let mut to_add = BTreeSet::new();
-        loop {
-            for item in item_set.iter() {
-                if let Some(Symbol::NT(next_symbol)) = item.symbol_after_dot() {
-                    for production in self
-                        .productions
-                        .get(&next_symbol)
-                        .expect("this better succeed")
-                    {
-                        let (counter, production) = production;
-                        let new_item = LR0Item {
-                            lhs: next_symbol.clone(),
+    fn symbol_after_dot(&self, item: Item) -> Option<&Symbol> {
+        let (_, symbols) = &self.productions[item.production];
+        symbols.get(item.dot)
+    }
+
+    /// Adds the items for every nonterminal right after a dot to the kernel.
+    fn closure(&self, kernel: &[Item]) -> Vec<Item> {
+        let mut items = kernel.to_vec();
+        let mut expanded = HashSet::new();
+        let mut i = 0;
+        while i < items.len() {
+            if let Some(Symbol::NT(next_symbol)) = self.symbol_after_dot(items[i]) {
+                if expanded.insert(*next_symbol) {
+                    // a nonterminal with no productions has nothing to add
+                    let productions = self.productions_by_lhs.get(next_symbol);
+                    for production in productions.into_iter().flatten() {
+                        items.push(Item {
+                            production: *production,
                             dot: 0,
-                            symbols: production.symbols(&self.grammar_symbols).unwrap(),
-                            is_start: false,
-                            production_number: Some(*counter),
-                        };
-                        if !item_set.contains(&new_item) {
-                            to_add.insert(new_item);
-                        }
+                        });
                     }
                 }
             }
+            i += 1;
+        }
+        items
+    }
 
-            if to_add.is_empty() {
-                break;
+    fn lr0_item(&self, item: Item) -> LR0Item {
+        let (lhs, symbols) = &self.productions[item.production];
+        let is_start = item.production >= self.n_productions;
+        LR0Item {
+            lhs: *lhs,
+            symbols: symbols.clone(),
+            dot: item.dot,
+            is_start,
+            production_number: if is_start {
+                None
             } else {
-                item_set.extend(to_add.clone());
-                to_add.clear();
+                Some(item.production)
+            },
+        }
+    }
+}
+
+/// Picks the default reduction of every state, removing the cells it makes
+/// redundant from the table.
+fn default_reductions(table: &mut ParseTable, mode: DefaultReductions) -> Vec<Option<usize>> {
+    let mut defaults = Vec::new();
+    for (action, _) in table.0.iter_mut() {
+        let mut counts = BTreeMap::new();
+        for cell in action.values() {
+            if let Action::Reduce(n) = cell {
+                *counts.entry(*n).or_insert(0) += 1;
             }
         }
-        item_set
+
+        // the most common reduction, preferring the lowest production on ties
+        let most_common = counts
+            .iter()
+            .max_by_key(|(n, count)| (**count, std::cmp::Reverse(**n)))
+            .map(|(n, count)| (*n, *count));
+        let default = match (mode, most_common) {
+            (DefaultReductions::None, _) => None,
+            (DefaultReductions::Consistent, Some((n, count))) if count == action.len() => Some(n),
+            (DefaultReductions::Consistent, _) => None,
+            (DefaultReductions::All, most_common) => most_common.map(|(n, _)| n),
+        };
+
+        if let Some(n) = default {
+            action.retain(|_, cell| *cell != Action::Reduce(n));
+        }
+        defaults.push(default);
+    }
+    defaults
+}
+
+/// Inserts an action into a row of the action table, recording a conflict if
+/// the cell is already occupied by a different action.
+fn insert_action(
+    row: &mut HashMap<Symbol, Action>,
+    conflicts: &mut Vec<Conflict>,
+    state: usize,
+    symbol: Symbol,
+    action: Action,
+) {
+    let existing = match row.get(&symbol) {
+        Some(existing) if *existing == action => return,
+        Some(existing) => existing.clone(),
+        None => {
+            row.insert(symbol, action);
+            return;
+        }
+    };
+
+    let keep_new = match (&existing, &action) {
+        (Action::Shift(_), _) => false,
+        (_, Action::Shift(_)) => true,
+        (Action::Reduce(a), Action::Reduce(b)) => b < a,
+        _ => false,
+    };
+
+    let index = conflicts
+        .iter()
+        .position(|conflict| conflict.state == state && conflict.symbol == symbol);
+    let conflict = match index {
+        Some(index) => &mut conflicts[index],
+        None => {
+            conflicts.push(Conflict {
+                state,
+                symbol,
+                actions: vec![existing],
+            });
+            conflicts.last_mut().unwrap()
+        }
+    };
+    if !conflict.actions.contains(&action) {
+        if keep_new {
+            conflict.actions.insert(0, action.clone());
+        } else {
+            conflict.actions.push(action.clone());
+        }
+    }
+
+    if keep_new {
+        row.insert(conflict.symbol.clone(), action);
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Grammar;
     use super::Symbol;
-    use std::collections::{BTreeMap, BTreeSet};
+    use super::{BuildOptions, DefaultReductions, Grammar, GrammarError};
+    use crate::items::LR0Item;
+    use crate::parser::Action;
+    use std::collections::BTreeSet;
     use symbol::Symbol as Id;
 
-    fn remove_terminals<V: Clone>(map: &BTreeMap<Symbol, V>) -> BTreeMap<Symbol, V> {
-        let mut map = map.clone();
-        let mut to_remove = BTreeSet::new();
-        for key in map.keys() {
-            if let Symbol::T(_) = key {
-                to_remove.insert(key.clone());
-            }
-        }
-        for key in to_remove {
-            map.remove(&key);
-        }
-        map
-    }
-
     fn make_arith_1() -> Grammar {
         make_grammar! {
             start_symbols: [E],
@@ -526,37 +734,263 @@ mod tests {
     }
 
     #[test]
-    fn test_arith_1() {
-        use super::Symbol::*;
+    fn test_display() {
         let grammar = make_arith_1();
-        let mut helper = grammar.create_grammar_helper().unwrap();
-
-        // First sets
-        helper.compute_first_sets();
-        let actual_first_sets = remove_terminals(&helper.first_sets);
-        let expected_first_sets = btreemap! {
-            NT(Id::from("E")) => btreeset!{ T(Id::from("N0")), T(Id::from("N1")), },
-            NT(Id::from("B")) => btreeset!{ T(Id::from("N0")), T(Id::from("N1")), },
-        };
-        assert!(
-            actual_first_sets.iter().eq(expected_first_sets.iter()),
-            "Expected: {:?}, Got: {:?}",
-            expected_first_sets,
-            actual_first_sets
+        assert_eq!(
+            grammar.to_string(),
+            r#"make_grammar! {
+    start_symbols: [E],
+    terminals: {
+        Add: r"\+",
+        Mul: r"\*",
+        N0: r"0",
+        N1: r"1",
+    },
+    productions: {
+        E: [ [E, Mul, B], [E, Add, B], [B] ],
+        B: [ [N0], [N1] ],
+    }
+}"#
         );
 
-        // Follow sets
-        helper.compute_follow_sets();
-        let actual_follow_sets = remove_terminals(&helper.follow_sets);
-        let expected_follow_sets = btreemap! {
-            NT(Id::from("E")) => btreeset!{ T(Id::from("Add")), T(Id::from("Mul")), EOF, },
-            NT(Id::from("B")) => btreeset!{ T(Id::from("Add")), T(Id::from("Mul")), EOF, },
+        let production = &grammar.productions[&Id::from("E")][0];
+        assert_eq!(production.to_string(), "E Mul B");
+        assert_eq!(super::raw_string(r##"a"#b"##), r###"r##"a"#b"##"###);
+
+        let item = LR0Item {
+            lhs: Id::from("E"),
+            symbols: vec![
+                Symbol::NT(Id::from("E")),
+                Symbol::T(Id::from("Add")),
+                Symbol::NT(Id::from("B")),
+            ],
+            dot: 1,
+            is_start: false,
+            production_number: Some(1),
         };
-        assert!(
-            actual_follow_sets.iter().eq(expected_follow_sets.iter()),
-            "Expected: {:?}, Got: {:?}",
-            expected_follow_sets,
-            actual_follow_sets
+        assert_eq!(item.to_string(), "E -> E \u{2022} Add B");
+
+        assert_eq!(Action::Shift(7).to_string(), "s7");
+        assert_eq!(Action::Reduce(3).to_string(), "r3");
+        assert_eq!(Action::Accept.to_string(), "acc");
+    }
+
+    #[test]
+    fn test_multiple_start_symbols() {
+        let parser = make_grammar! {
+            start_symbols: [Stmt, Expr],
+            terminals: {
+                Let: r"let",
+                Name: r"[a-z]",
+                Eq: r"=",
+                Add: r"\+",
+                N: r"[0-9]",
+            },
+            productions: {
+                Stmt: [ [Let, Name, Eq, Expr] ],
+                Expr: [ [Expr, Add, N], [N] ],
+            }
+        }
+        .build()
+        .unwrap();
+
+        // entry states are numbered first, in the order the start symbols are listed
+        let stmt = parser.start_state("Stmt").unwrap();
+        let expr = parser.start_state("Expr").unwrap();
+        assert_eq!((stmt, expr), (0, 1));
+        assert_eq!(parser.start_state("N"), None);
+
+        // each start symbol accepts in its own state
+        let accept_state = |entry: usize, start: &str| {
+            let (_, gotos) = &parser.table.0[entry];
+            let state = gotos[&Symbol::NT(Id::from(start))];
+            let (actions, _) = &parser.table.0[state];
+            assert_eq!(actions.get(&Symbol::EOF), Some(&Action::Accept));
+            state
+        };
+        assert_ne!(accept_state(stmt, "Stmt"), accept_state(expr, "Expr"));
+
+        let mut buf = Vec::new();
+        parser.codegen(&mut buf).unwrap();
+        let code = String::from_utf8(buf).unwrap();
+        assert!(code.contains(&format!(
+            "pub fn parse_Stmt(&mut self) -> Result<Tree, ParseError> {{\nself.generic_parse({})",
+            stmt
+        )));
+        assert!(code.contains(&format!(
+            "pub fn parse_Expr(&mut self) -> Result<Tree, ParseError> {{\nself.generic_parse({})",
+            expr
+        )));
+
+        parser.interpret("Stmt", "letx=1+2").unwrap();
+        parser.interpret("Expr", "1+2").unwrap();
+    }
+
+    #[test]
+    fn test_stable_numbering() {
+        let codegen = |grammar: Grammar| {
+            let mut buf = Vec::new();
+            grammar.build().unwrap().codegen(&mut buf).unwrap();
+            String::from_utf8(buf).unwrap()
+        };
+        assert_eq!(codegen(make_arith_1()), codegen(make_arith_1()));
+
+        // states are numbered breadth-first from the entry state, trying
+        // terminals and then nonterminals in declaration order
+        let parser = make_arith_1().build().unwrap();
+        let kernel = |state: usize| {
+            parser.states[state]
+                .iter()
+                .filter(|item| item.dot > 0)
+                .map(|item| item.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(kernel(1), vec!["B -> N0 \u{2022}"]);
+        assert_eq!(kernel(2), vec!["B -> N1 \u{2022}"]);
+        assert_eq!(
+            kernel(3),
+            vec![
+                "E -> E \u{2022} Add B",
+                "E -> E \u{2022} Mul B",
+                "E' -> E \u{2022}"
+            ]
         );
+        assert_eq!(kernel(4), vec!["E -> B \u{2022}"]);
+    }
+
+    #[test]
+    fn test_default_reductions() {
+        let build = |mode| {
+            let options = BuildOptions {
+                default_reductions: mode,
+                ..BuildOptions::default()
+            };
+            make_arith_1().build_with(&options).unwrap()
+        };
+
+        let none = build(DefaultReductions::None);
+        assert!(none.default_reductions.iter().all(Option::is_none));
+
+        // B -> N0 . and E -> B . only ever reduce, so they're consistent
+        let consistent = build(DefaultReductions::Consistent);
+        assert_eq!(consistent.default_reductions[1], Some(3));
+        assert_eq!(consistent.default_reductions[4], Some(2));
+        assert!(consistent.table.0[1].0.is_empty());
+        // E' -> E . also accepts, and shifts Add and Mul
+        assert_eq!(consistent.default_reductions[3], None);
+
+        let all = build(DefaultReductions::All);
+        for (i, (actions, _)) in none.table_iter() {
+            let reduces = actions.values().any(|a| matches!(a, Action::Reduce(_)));
+            assert_eq!(all.default_reductions[i].is_some(), reduces);
+            for (symbol, action) in actions {
+                assert_eq!(all.action(i, symbol).as_ref(), Some(action));
+            }
+        }
+
+        // generated parsers carry the defaults along
+        let mut buf = Vec::new();
+        all.codegen(&mut buf).unwrap();
+        let code = String::from_utf8(buf).unwrap();
+        assert!(code.contains("const DEFAULTS: &[i8] = &["));
+        assert!(code.contains("    defaults: DEFAULTS,"));
+    }
+
+    #[test]
+    fn test_builtin_grammars() {
+        for name in &["pgen", "ll", "stmts", "c", "sql"] {
+            let parser = crate::this::by_name(name).unwrap().build().unwrap();
+            assert!(
+                parser.warnings().is_empty(),
+                "{}: {:?}",
+                name,
+                parser.warnings()
+            );
+        }
+    }
+
+    #[test]
+    fn test_reserved_error() {
+        let grammar = make_grammar! {
+            start_symbols: [S],
+            terminals: {
+                error: r"e",
+            },
+            productions: {
+                S: [ [error] ],
+            }
+        };
+        match grammar.build() {
+            Err(GrammarError::NameConflict(name)) => assert_eq!(name, Id::from("error")),
+            other => panic!("expected a name conflict, got {:?}", other),
+        }
+
+        // `error` is a terminal with a FIRST set of its own, but no pattern
+        let analysis = crate::this::stmts_grammar().analyze().unwrap();
+        let first = analysis.first_set(&Symbol::NT(Id::from("Stmt"))).unwrap();
+        assert!(first.contains(&Symbol::error()));
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        let grammar = make_grammar! {
+            start_symbols: [S],
+            terminals: {
+                A: r"a",
+                B: r"(b",
+            },
+            productions: {
+                S: [ [A], [B] ],
+            }
+        };
+        let invalid = |result: Result<(), GrammarError>| match result {
+            Err(GrammarError::InvalidPattern(name)) => assert_eq!(name, Id::from("B")),
+            other => panic!("expected an invalid pattern, got {:?}", other),
+        };
+        invalid(grammar.clone().build().map(drop));
+        invalid(grammar.clone().build_ll().map(drop));
+        invalid(grammar.clone().build_earley().map(drop));
+    }
+
+    #[test]
+    fn test_symbol_order() {
+        use super::Symbol::*;
+
+        // terminals, then nonterminals, then epsilon and EOF, each by name
+        let symbols = vec![
+            EOF,
+            NT(Id::from("E")),
+            Epsilon,
+            T(Id::from("Mul")),
+            NT(Id::from("B")),
+            T(Id::from("Add")),
+        ];
+        let sorted = symbols.into_iter().collect::<BTreeSet<_>>();
+        let expected = vec![
+            T(Id::from("Add")),
+            T(Id::from("Mul")),
+            NT(Id::from("B")),
+            NT(Id::from("E")),
+            Epsilon,
+            EOF,
+        ];
+        assert!(sorted.into_iter().eq(expected));
+
+        // items order by the name of their lhs first
+        let item = |lhs: &str| LR0Item {
+            lhs: Id::from(lhs),
+            symbols: vec![T(Id::from("N0"))],
+            dot: 0,
+            is_start: false,
+            production_number: None,
+        };
+        let items = vec![item("E"), item("B"), item("C")];
+        let lhs = items
+            .into_iter()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|item| item.lhs.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(lhs, vec!["B", "C", "E"]);
     }
 }
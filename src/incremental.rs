@@ -0,0 +1,603 @@
+//! Incremental parsing: after an edit, the input is only relexed around the
+//! edit, and the subtrees of the previous parse the edit didn't touch are
+//! shifted whole instead of being parsed again.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use indexmap::IndexMap;
+use regex::Regex;
+use regex_syntax::hir::{Hir, HirKind, Repetition, RepetitionKind, RepetitionRange};
+use symbol::Symbol as Id;
+
+use crate::grammar::Symbol;
+use crate::parser::Action;
+use crate::runtime::{ParseError, Tree};
+use crate::Parser;
+
+/// An edit to the input: the bytes in `range` replaced with `text`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+impl TextEdit {
+    /// Applies the edit to the text it was made to.
+    pub fn apply(&self, input: &str) -> String {
+        let mut edited = input[..self.range.start].to_owned();
+        edited += &self.text;
+        edited += &input[self.range.end..];
+        edited
+    }
+}
+
+/// A parse that can be redone after edits to its input, reusing the parts of
+/// the tree the edit didn't touch.
+#[derive(Clone, Debug)]
+pub struct IncrementalParse {
+    input: String,
+    start_state: usize,
+    root: Node,
+    lexed: usize,
+    reused: usize,
+}
+
+impl IncrementalParse {
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// The parse tree.
+    pub fn tree(&self) -> Tree {
+        self.root.tree(&self.input)
+    }
+
+    /// How many tokens were lexed to make this parse, rather than taken from
+    /// the previous one.
+    pub fn tokens_lexed(&self) -> usize {
+        self.lexed
+    }
+
+    /// How many subtrees of the previous parse were shifted whole.
+    pub fn nodes_reused(&self) -> usize {
+        self.reused
+    }
+}
+
+/// A node of an incremental parse, with what it takes to reuse it.
+#[derive(Clone, Debug)]
+struct Node {
+    kind: NodeKind,
+    span: Range<usize>,
+
+    /// The state the parser was in right before shifting the node's first
+    /// token
+    state: usize,
+
+    /// Where the furthest token the parser looked at while building the node
+    /// ends, lookahead included
+    lookahead_end: usize,
+
+    /// How far the input could still have been matched by some pattern when
+    /// the node's tokens were lexed: an edit up to there can relex them
+    lexed_end: usize,
+    children: Vec<Node>,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum NodeKind {
+    Leaf(usize),
+    Node(usize),
+}
+
+impl Node {
+    fn tree(&self, input: &str) -> Tree {
+        match self.kind {
+            NodeKind::Leaf(terminal) => Tree::Leaf(
+                terminal,
+                input[self.span.clone()].to_owned(),
+                self.span.clone(),
+            ),
+            NodeKind::Node(production) => {
+                let children = self
+                    .children
+                    .iter()
+                    .map(|child| child.tree(input))
+                    .collect();
+                Tree::Node(production, children, self.span.clone())
+            }
+        }
+    }
+
+    /// The node moved `delta` bytes along the input.
+    fn moved(&self, delta: isize) -> Node {
+        let add = |position: usize| (position as isize + delta) as usize;
+        Node {
+            kind: self.kind,
+            span: add(self.span.start)..add(self.span.end),
+            state: self.state,
+            lookahead_end: add(self.lookahead_end),
+            lexed_end: add(self.lexed_end),
+            children: self
+                .children
+                .iter()
+                .map(|child| child.moved(delta))
+                .collect(),
+        }
+    }
+
+    /// Indexes every node that covers some input by where it starts,
+    /// outermost first.
+    fn index<'a>(&'a self, starts: &mut HashMap<usize, Vec<&'a Node>>) {
+        if self.span.is_empty() {
+            return;
+        }
+        starts.entry(self.span.start).or_default().push(self);
+        for child in self.children.iter() {
+            child.index(starts);
+        }
+    }
+}
+
+/// The previous parse, and the part of its input an edit changed.
+struct Previous<'a> {
+    starts: HashMap<usize, Vec<&'a Node>>,
+    edit: &'a TextEdit,
+
+    /// Where relexing starts: the first token whose lexing looked as far as
+    /// the edit
+    relex_from: usize,
+
+    /// How far the edit moves what comes after it
+    delta: isize,
+    old_len: usize,
+}
+
+/// What the parser takes next: a fresh token, or old nodes starting at the
+/// same place, outermost first and ending in a leaf.
+enum Next<'a> {
+    Token(usize, Range<usize>),
+    Old {
+        nodes: Vec<&'a Node>,
+        delta: isize,
+        after_edit: bool,
+    },
+}
+
+impl Parser {
+    /// Parses the input, keeping what `reparse` needs to redo the parse after
+    /// an edit.
+    pub fn parse_incremental(
+        &self,
+        start_symbol: impl AsRef<str>,
+        input: impl Into<String>,
+    ) -> Result<IncrementalParse, ParseError> {
+        let start_state = self
+            .start_state(start_symbol.as_ref())
+            .expect("not a start symbol");
+        self.parse_reusing(start_state, input.into(), None)
+    }
+
+    /// Parses a previous parse's input after an edit, like Wagner and Graham.
+    ///
+    /// Only tokens from the first one whose lexing looked as far as the edit,
+    /// which is as far as some pattern could still have matched there, up to
+    /// where the new tokens line up with the old ones again are lexed. Before
+    /// shifting the next token the parser tries the old subtrees starting
+    /// there, biggest first: one is shifted whole if the parser is in the
+    /// state it was first built in and neither it nor the token after it,
+    /// which its reductions looked at, is relexed.
+    pub fn reparse(
+        &self,
+        previous: &IncrementalParse,
+        edit: &TextEdit,
+    ) -> Result<IncrementalParse, ParseError> {
+        let mut starts = HashMap::new();
+        previous.root.index(&mut starts);
+        let mut leaves = starts
+            .values()
+            .filter_map(|nodes| nodes.last())
+            .collect::<Vec<_>>();
+        leaves.sort_unstable_by_key(|leaf| leaf.span.start);
+        let relex_from = leaves
+            .iter()
+            .find(|leaf| leaf.lexed_end >= edit.range.start)
+            .map_or(edit.range.start, |leaf| leaf.span.start)
+            .min(edit.range.start);
+        let old = Previous {
+            starts,
+            edit,
+            relex_from,
+            delta: edit.text.len() as isize - edit.range.len() as isize,
+            old_len: previous.input.len(),
+        };
+        let input = edit.apply(&previous.input);
+        self.parse_reusing(previous.start_state, input, Some(old))
+    }
+
+    fn parse_reusing(
+        &self,
+        start_state: usize,
+        input: String,
+        old: Option<Previous>,
+    ) -> Result<IncrementalParse, ParseError> {
+        let lexer = self.lexer();
+        let prefixes = Prefixes::new(&self.terminals);
+        let columns = self.terminal_columns();
+        let (mut lexed, mut reused) = (0, 0);
+
+        let mut states = vec![start_state];
+        let mut nodes: Vec<Node> = Vec::new();
+        let mut position = 0;
+        // the old nodes are used again once the new tokens line up with them
+        let mut synced = old.is_none();
+
+        loop {
+            let next = match &old {
+                Some(old) if position < old.relex_from => Next::Old {
+                    nodes: old.starts[&position].clone(),
+                    delta: 0,
+                    after_edit: false,
+                },
+                Some(old) if !synced => {
+                    let old_position = position as isize - old.delta;
+                    let new_end = old.edit.range.start + old.edit.text.len();
+                    if position >= new_end
+                        && (old.starts.contains_key(&(old_position as usize))
+                            || old_position as usize == old.old_len)
+                    {
+                        synced = true;
+                        continue;
+                    }
+                    lexed += 1;
+                    let (terminal, end) = lexer
+                        .next(&input, position)
+                        .ok_or_else(|| ParseError::unlexable(&input, position))?;
+                    Next::Token(terminal, position..end)
+                }
+                Some(old) if position < input.len() => {
+                    let old_position = (position as isize - old.delta) as usize;
+                    Next::Old {
+                        nodes: old.starts[&old_position].clone(),
+                        delta: old.delta,
+                        after_edit: true,
+                    }
+                }
+                _ => {
+                    if position < input.len() {
+                        lexed += 1;
+                    }
+                    let (terminal, end) = lexer
+                        .next(&input, position)
+                        .ok_or_else(|| ParseError::unlexable(&input, position))?;
+                    Next::Token(terminal, position..end)
+                }
+            };
+            let (terminal, token, lexed_end) = match &next {
+                Next::Token(terminal, span) => {
+                    (*terminal, span.clone(), prefixes.viable_end(&input, span))
+                }
+                Next::Old { nodes, delta, .. } => {
+                    let leaf = nodes.last().unwrap().moved(*delta);
+                    match leaf.kind {
+                        NodeKind::Leaf(terminal) => (terminal, leaf.span, leaf.lexed_end),
+                        NodeKind::Node(_) => unreachable!("old nodes end in a leaf"),
+                    }
+                }
+            };
+            let lookahead = &columns[terminal];
+
+            // make the reductions the lookahead calls for
+            let action = loop {
+                let state = *states.last().unwrap();
+                match self.action(state, lookahead) {
+                    Some(Action::Reduce(n)) => {
+                        let (lhs, symbols) = &self.productions[n];
+                        let children = nodes.split_off(nodes.len() - symbols.len());
+                        states.truncate(states.len() - symbols.len());
+                        let uncovered = *states.last().unwrap();
+                        let span = match (children.first(), children.last()) {
+                            (Some(first), Some(last)) => first.span.start..last.span.end,
+                            _ => token.start..token.start,
+                        };
+                        let lookahead_end = children
+                            .iter()
+                            .map(|child| child.lookahead_end)
+                            .fold(token.end, usize::max);
+                        let lexed_end = children
+                            .iter()
+                            .map(|child| child.lexed_end)
+                            .fold(span.end, usize::max);
+                        nodes.push(Node {
+                            kind: NodeKind::Node(n),
+                            span,
+                            state: uncovered,
+                            lookahead_end,
+                            lexed_end,
+                            children,
+                        });
+                        let (_, gotos) = &self.table.0[uncovered];
+                        states.push(gotos[&Symbol::NT(*lhs)]);
+                    }
+                    action => break action,
+                }
+            };
+            let state = *states.last().unwrap();
+
+            // shift the biggest old subtree that fits, if any
+            if let (
+                Some(old),
+                Next::Old {
+                    nodes: old_nodes,
+                    delta,
+                    after_edit,
+                },
+            ) = (&old, &next)
+            {
+                let reusable = old_nodes.iter().find(|node| {
+                    let untouched = *after_edit || node.lookahead_end <= old.relex_from;
+                    untouched && node.state == state && matches!(node.kind, NodeKind::Node(_))
+                });
+                if let Some(node) = reusable {
+                    let node = node.moved(*delta);
+                    let lhs = match node.kind {
+                        NodeKind::Node(n) => self.productions[n].0,
+                        NodeKind::Leaf(_) => unreachable!(),
+                    };
+                    let (_, gotos) = &self.table.0[state];
+                    states.push(gotos[&Symbol::NT(lhs)]);
+                    position = node.span.end;
+                    nodes.push(node);
+                    reused += 1;
+                    continue;
+                }
+            }
+
+            match action {
+                Some(Action::Shift(next)) => {
+                    nodes.push(Node {
+                        kind: NodeKind::Leaf(terminal),
+                        span: token.clone(),
+                        state,
+                        lookahead_end: token.end,
+                        lexed_end,
+                        children: Vec::new(),
+                    });
+                    states.push(next);
+                    position = token.end;
+                }
+                Some(Action::Accept) => {
+                    return Ok(IncrementalParse {
+                        input,
+                        start_state,
+                        root: nodes.pop().unwrap(),
+                        lexed,
+                        reused,
+                    });
+                }
+                _ => {
+                    let (actions, _) = &self.table.0[state];
+                    let expected = (0..columns.len())
+                        .filter(|column| actions.contains_key(&columns[*column]))
+                        .collect();
+                    let error = ParseError::new(&input, token, Some(terminal), expected);
+                    return Err(ParseError {
+                        state: Some(state),
+                        ..error
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// What each terminal's pattern could still match if the input went on, to
+/// tell how far the lexer looked while lexing a token.
+struct Prefixes(Vec<Regex>);
+
+impl Prefixes {
+    fn new(terminals: &IndexMap<Id, String>) -> Self {
+        let regexes = terminals
+            .values()
+            .map(|pattern| {
+                let hir = regex_syntax::Parser::new()
+                    .parse(pattern)
+                    .expect("patterns are checked by Grammar::check_patterns");
+                Regex::new(&format!("^(?:{})$", prefixes(hir))).unwrap()
+            })
+            .collect();
+        Prefixes(regexes)
+    }
+
+    /// Where the longest stretch of input from the token's start that some
+    /// pattern could still match ends. Anything the lexer looked at lies
+    /// before it, or is the character right there.
+    fn viable_end(&self, input: &str, token: &Range<usize>) -> usize {
+        let mut end = token.end;
+        for (i, c) in input[token.end..].char_indices() {
+            let next = token.end + i + c.len_utf8();
+            let text = &input[token.start..next];
+            if !self.0.iter().any(|regex| regex.is_match(text)) {
+                break;
+            }
+            end = next;
+        }
+        end
+    }
+}
+
+/// An expression matching every prefix of what `hir` matches. Assertions
+/// are dropped, which can only make it match more.
+fn prefixes(hir: Hir) -> Hir {
+    let optional = |hir| {
+        Hir::repetition(Repetition {
+            kind: RepetitionKind::ZeroOrOne,
+            greedy: true,
+            hir: Box::new(hir),
+        })
+    };
+    match hir.into_kind() {
+        HirKind::Empty | HirKind::Anchor(_) | HirKind::WordBoundary(_) => Hir::empty(),
+        HirKind::Literal(literal) => optional(Hir::literal(literal)),
+        HirKind::Class(class) => optional(Hir::class(class)),
+        HirKind::Group(group) => prefixes(*group.hir),
+        // all of some and a prefix of the next
+        HirKind::Concat(hirs) => Hir::alternation(
+            (0..hirs.len())
+                .map(|i| {
+                    let mut concat = hirs[..i].to_vec();
+                    concat.push(prefixes(hirs[i].clone()));
+                    Hir::concat(concat)
+                })
+                .collect(),
+        ),
+        HirKind::Alternation(hirs) => Hir::alternation(hirs.into_iter().map(prefixes).collect()),
+        // fewer than the most repetitions and a prefix of one more
+        HirKind::Repetition(repetition) => {
+            let most = match repetition.kind {
+                RepetitionKind::ZeroOrOne => Some(1),
+                RepetitionKind::Range(RepetitionRange::Exactly(n))
+                | RepetitionKind::Range(RepetitionRange::Bounded(_, n)) => Some(n),
+                _ => None,
+            };
+            let kind = match most {
+                Some(0) => return Hir::empty(),
+                Some(n) => RepetitionKind::Range(RepetitionRange::Bounded(0, n - 1)),
+                None => RepetitionKind::ZeroOrMore,
+            };
+            let prefix = prefixes((*repetition.hir).clone());
+            let repeated = Hir::repetition(Repetition {
+                kind,
+                greedy: true,
+                hir: repetition.hir,
+            });
+            Hir::concat(vec![repeated, prefix])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TextEdit;
+    use crate::{Grammar, Parser};
+
+    fn edit(range: std::ops::Range<usize>, text: &str) -> TextEdit {
+        TextEdit {
+            range,
+            text: text.to_owned(),
+        }
+    }
+
+    /// Makes each edit to the input in turn, checking that reparsing agrees
+    /// with parsing from scratch.
+    fn check_edits(parser: &Parser, start: &str, input: &str, edits: &[TextEdit]) {
+        let mut parse = parser.parse_incremental(start, input).unwrap();
+        assert_eq!(parse.tree(), parser.interpret(start, input).unwrap());
+        for edit in edits {
+            let input = edit.apply(parse.input());
+            let expected = parser.interpret(start, &input);
+            match parser.reparse(&parse, edit) {
+                Ok(reparsed) => {
+                    assert_eq!(Ok(reparsed.tree()), expected, "{:?}", input);
+                    parse = reparsed;
+                }
+                Err(error) => assert_eq!(Err(error), expected, "{:?}", input),
+            }
+        }
+    }
+
+    #[test]
+    fn test_reparse() {
+        let parser = crate::this::stmts_grammar().build().unwrap();
+        check_edits(
+            &parser,
+            "Stmts",
+            "1+2;3+4;5",
+            &[
+                // a number in the middle
+                edit(4..5, "30"),
+                // a token that merges with the ones around it
+                edit(4..4, "9"),
+                // a new statement at either end
+                edit(0..0, "7;"),
+                edit(13..13, ";8+8"),
+                // deletions, down to a single number
+                edit(2..6, ""),
+                edit(0..8, ""),
+                edit(0..4, "1"),
+                // and errors, which leave the last good parse
+                edit(0..0, ";"),
+                edit(1..1, "x"),
+                edit(1..1, "+"),
+            ],
+        );
+
+        let parser = crate::this::ll_grammar().build().unwrap();
+        check_edits(
+            &parser,
+            "E",
+            "(1+2)*3+4",
+            &[
+                edit(1..2, "(7*8)"),
+                edit(11..13, ""),
+                edit(0..0, "5+"),
+                edit(3..4, ""),
+                edit(12..13, "3+1"),
+            ],
+        );
+
+        // a token that the edit turns into a longer match ending in it
+        let parser = make_grammar! {
+            start_symbols: [S],
+            terminals: {
+                Dot: r"\.",
+                Ell: r"\.\.\.",
+            },
+            productions: {
+                S: [ [Item], [S, Item] ],
+                Item: [ [Dot], [Ell] ],
+            }
+        }
+        .build()
+        .unwrap();
+        check_edits(&parser, "S", "..", &[edit(2..2, "."), edit(1..1, ".")]);
+
+        // and one that the edit stretches back over several old tokens
+        let parser = make_grammar! {
+            start_symbols: [S],
+            terminals: {
+                A: r"a",
+                B: r"b",
+                Long: r"abab",
+            },
+            productions: {
+                S: [ [Item], [S, Item] ],
+                Item: [ [A], [B], [Long] ],
+            }
+        }
+        .build()
+        .unwrap();
+        check_edits(&parser, "S", "aba", &[edit(3..3, "b"), edit(3..4, "")]);
+    }
+
+    #[test]
+    fn test_reuse() {
+        let parser = crate::this::stmts_grammar().build().unwrap();
+        let input = vec!["1+2+3"; 100].join(";");
+        let parse = parser.parse_incremental("Stmts", input.as_str()).unwrap();
+        assert_eq!(parse.tokens_lexed(), 100 * 5 + 99);
+
+        // only the tokens around the edit are lexed again
+        let middle = 50 * 6 + 2;
+        let reparsed = parser
+            .reparse(&parse, &edit(middle..middle + 1, "20"))
+            .unwrap();
+        assert!(reparsed.tokens_lexed() <= 3, "{}", reparsed.tokens_lexed());
+        assert!(reparsed.nodes_reused() > 0);
+        assert_eq!(
+            reparsed.tree(),
+            parser.interpret("Stmts", reparsed.input()).unwrap()
+        );
+    }
+}
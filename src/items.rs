@@ -1,8 +1,19 @@
+use std::cmp::Ordering;
+use std::fmt;
+
 use symbol::Symbol as Id;
 
 use crate::grammar::Symbol;
 
-#[derive(Clone, Debug, Ord, PartialOrd, Eq, PartialEq)]
+/// An item while the automaton is being built: just the index of its
+/// production and the position of the dot in it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub(crate) struct Item {
+    pub(crate) production: usize,
+    pub(crate) dot: usize,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct LR0Item {
     pub(crate) lhs: Id,
     pub(crate) symbols: Vec<Symbol>,
@@ -12,27 +23,54 @@ pub struct LR0Item {
 }
 
 impl LR0Item {
-    pub fn name_after_dot(&self) -> Option<Id> {
-        self.symbol_after_dot().map(|symbol| symbol.name())
+    pub fn symbol_after_dot(&self) -> Option<Symbol> {
+        self.symbols.get(self.dot).cloned()
     }
 
-    pub fn symbol_after_dot(&self) -> Option<Symbol> {
-        if self.dot >= self.symbols.len() {
-            return None;
-        } else {
-            return Some(self.symbols[self.dot].clone());
-        }
+    pub fn dot_at_end(&self) -> bool {
+        self.dot == self.symbols.len()
     }
+}
 
-    pub fn symbol_before_dot(&self) -> Option<Symbol> {
-        if self.dot == 0 {
-            return None;
-        } else {
-            return Some(self.symbols[self.dot - 1].clone());
-        }
+// ordered by the lhs's name rather than `Id`'s address-based `Ord`, see `Symbol`
+impl Ord for LR0Item {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (
+            self.lhs.as_str(),
+            &self.symbols,
+            self.dot,
+            self.is_start,
+            self.production_number,
+        )
+            .cmp(&(
+                other.lhs.as_str(),
+                &other.symbols,
+                other.dot,
+                other.is_start,
+                other.production_number,
+            ))
     }
+}
 
-    pub fn dot_at_end(&self) -> bool {
-        self.dot == self.symbols.len()
+impl PartialOrd for LR0Item {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Renders the item as `E -> E • Add B`.
+impl fmt::Display for LR0Item {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ->", self.lhs)?;
+        for (i, symbol) in self.symbols.iter().enumerate() {
+            if i == self.dot {
+                write!(f, " \u{2022}")?;
+            }
+            write!(f, " {}", symbol)?;
+        }
+        if self.dot_at_end() {
+            write!(f, " \u{2022}")?;
+        }
+        Ok(())
     }
 }
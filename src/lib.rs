@@ -1,3 +1,6 @@
+// grammars written with make_grammar! spell empty productions as `ɛ`
+#![allow(uncommon_codepoints)]
+
 #[macro_use]
 extern crate prettytable;
 #[macro_use]
@@ -11,10 +14,38 @@ pub extern crate regex;
 #[macro_use]
 mod utils;
 
+mod analysis;
+#[cfg(test)]
+mod differential;
+mod direct;
+mod dot;
+mod earley;
+mod generate;
+mod glr;
 mod grammar;
+mod incremental;
 mod items;
+mod lints;
+mod ll;
+mod messages;
 mod parser;
+mod report;
+pub mod runtime;
+mod tables;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub mod this;
+mod transform;
 
-pub use crate::grammar::Grammar;
-pub use crate::parser::Parser;
+pub use crate::analysis::GrammarAnalysis;
+pub use crate::earley::EarleyParser;
+pub use crate::generate::{GenerateOptions, Generator};
+pub use crate::glr::{Family, Forest, ForestNode, NodeId};
+pub use crate::grammar::{BuildOptions, DefaultReductions, Grammar, GrammarError, Symbol};
+pub use crate::incremental::{IncrementalParse, TextEdit};
+pub use crate::lints::{Lint, LintLevel, Lints};
+pub use crate::ll::{LLConflict, LLParser};
+pub use crate::messages::{ErrorMessages, MessageEntry, MessageError, Messages, Sentence};
+pub use crate::parser::{Action, Parser};
+pub use crate::tables::{Backend, CodegenOptions, TableLayout};
+pub use crate::transform::{Transformed, TreeMapping};
@@ -0,0 +1,236 @@
+use symbol::Symbol as Id;
+
+use crate::analysis::GrammarAnalysis;
+use crate::grammar::Symbol;
+use crate::Grammar;
+
+/// How seriously a lint is taken when building a grammar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LintLevel {
+    /// Don't check for it at all
+    Allow,
+
+    /// Report it in `Parser::warnings`
+    Warn,
+
+    /// Fail the build
+    Deny,
+}
+
+/// Which grammar lints are checked by `Grammar::build`, and how.
+///
+/// Nonterminals that are used but never defined always fail the build, with
+/// all of them listed in `GrammarError::UndefinedNonterminal`.
+#[derive(Clone, Debug)]
+pub struct Lints {
+    /// Nonterminals that can never derive a string of terminals
+    pub unproductive: LintLevel,
+
+    /// Nonterminals that can't be reached from any start symbol
+    pub unreachable: LintLevel,
+
+    /// Terminals that don't appear in any production
+    pub unused_terminals: LintLevel,
+}
+
+impl Default for Lints {
+    fn default() -> Self {
+        Lints {
+            unproductive: LintLevel::Deny,
+            unreachable: LintLevel::Warn,
+            unused_terminals: LintLevel::Warn,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum Lint {
+    #[error("Unproductive nonterminal: {0}")]
+    Unproductive(Id),
+
+    #[error("Unreachable nonterminal: {0}")]
+    Unreachable(Id),
+
+    #[error("Unused terminal: {0}")]
+    UnusedTerminal(Id),
+}
+
+impl Lints {
+    fn level(&self, lint: &Lint) -> LintLevel {
+        match lint {
+            Lint::Unproductive(_) => self.unproductive,
+            Lint::Unreachable(_) => self.unreachable,
+            Lint::UnusedTerminal(_) => self.unused_terminals,
+        }
+    }
+
+    /// Runs every lint, returning the warnings and the denied lints separately.
+    pub(crate) fn check(
+        &self,
+        grammar: &Grammar,
+        analysis: &GrammarAnalysis,
+    ) -> (Vec<Lint>, Vec<Lint>) {
+        let mut found = Vec::new();
+        for nonterminal in grammar.productions.keys() {
+            if !analysis.is_productive(*nonterminal) {
+                found.push(Lint::Unproductive(*nonterminal));
+            }
+        }
+        for nonterminal in grammar.productions.keys() {
+            if !analysis.is_reachable(*nonterminal) {
+                found.push(Lint::Unreachable(*nonterminal));
+            }
+        }
+        for terminal in grammar.terminals.keys() {
+            let used = analysis
+                .productions
+                .iter()
+                .any(|(_, symbols)| symbols.contains(&Symbol::T(*terminal)));
+            if !used {
+                found.push(Lint::UnusedTerminal(*terminal));
+            }
+        }
+
+        let mut warnings = Vec::new();
+        let mut denied = Vec::new();
+        for lint in found {
+            match self.level(&lint) {
+                LintLevel::Allow => (),
+                LintLevel::Warn => warnings.push(lint),
+                LintLevel::Deny => denied.push(lint),
+            }
+        }
+        (warnings, denied)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use symbol::Symbol as Id;
+
+    use super::{Lint, LintLevel, Lints};
+    use crate::grammar::{BuildOptions, GrammarError};
+    use crate::Grammar;
+
+    fn make_sloppy() -> Grammar {
+        make_grammar! {
+            start_symbols: [S],
+            terminals: {
+                A: r"a",
+                B: r"b",
+            },
+            productions: {
+                S: [ [A], [L] ],
+                L: [ [A, L] ],
+                U: [ [A] ],
+            }
+        }
+    }
+
+    #[test]
+    fn test_default_lints() {
+        match make_sloppy().build() {
+            Err(GrammarError::Lints(lints)) => {
+                assert_eq!(lints, vec![Lint::Unproductive(Id::from("L"))])
+            }
+            other => panic!("expected denied lints, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_warnings() {
+        let options = BuildOptions {
+            lints: Lints {
+                unproductive: LintLevel::Warn,
+                unreachable: LintLevel::Warn,
+                unused_terminals: LintLevel::Allow,
+            },
+            ..BuildOptions::default()
+        };
+        let parser = make_sloppy().build_with(&options).unwrap();
+        assert_eq!(
+            parser.warnings(),
+            &[
+                Lint::Unproductive(Id::from("L")),
+                Lint::Unreachable(Id::from("U")),
+            ][..]
+        );
+    }
+
+    #[test]
+    fn test_undefined_start_symbol() {
+        let grammar = make_grammar! {
+            start_symbols: [Missing],
+            terminals: {
+                A: r"a",
+            },
+            productions: {
+                S: [ [A] ],
+            }
+        };
+        match grammar.build() {
+            Err(GrammarError::UndefinedNonterminal(names)) => {
+                assert_eq!(names, vec![Id::from("Missing")])
+            }
+            other => panic!("expected an undefined nonterminal, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_undefined_symbol() {
+        // not a lint, even with every lint allowed
+        let options = BuildOptions {
+            lints: Lints {
+                unproductive: LintLevel::Allow,
+                unreachable: LintLevel::Allow,
+                unused_terminals: LintLevel::Allow,
+            },
+            ..BuildOptions::default()
+        };
+        let grammar = make_grammar! {
+            start_symbols: [S],
+            terminals: {
+                A: r"a",
+            },
+            productions: {
+                S: [ [A, Missing], [Other, A, Missing] ],
+            }
+        };
+        match grammar.build_with(&options) {
+            Err(GrammarError::UndefinedNonterminal(names)) => {
+                assert_eq!(names, vec![Id::from("Missing"), Id::from("Other")])
+            }
+            other => panic!("expected undefined nonterminals, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_empty_nonterminal() {
+        // L has no productions at all, which only the lints catch
+        let options = BuildOptions {
+            lints: Lints {
+                unproductive: LintLevel::Warn,
+                unreachable: LintLevel::Warn,
+                unused_terminals: LintLevel::Warn,
+            },
+            ..BuildOptions::default()
+        };
+        let grammar = make_grammar! {
+            start_symbols: [S],
+            terminals: {
+                A: r"a",
+            },
+            productions: {
+                S: [ [A], [L] ],
+                L: [],
+            }
+        };
+        let parser = grammar.clone().build_with(&options).unwrap();
+        assert_eq!(parser.warnings(), &[Lint::Unproductive(Id::from("L"))][..]);
+        assert!(parser.interpret("S", "a").is_ok());
+        let parser = grammar.clone().build_ll_with(&options).unwrap();
+        assert!(parser.parse("S", "a").is_ok());
+        let parser = grammar.build_earley().unwrap();
+        assert!(parser.recognize("S", "a").is_ok());
+    }
+}
@@ -0,0 +1,400 @@
+//! LL(1) parsing: a predict table built from the FIRST and FOLLOW sets, and a
+//! recursive-descent parser generated from it.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Write};
+
+use indexmap::IndexMap;
+use symbol::Symbol as Id;
+
+use crate::analysis::GrammarAnalysis;
+use crate::grammar::{symbol_list, BuildOptions, GrammarError, Symbol};
+use crate::lints::Lint;
+use crate::runtime::{Lexer, ParseError, Tokens, Tree};
+use crate::Grammar;
+
+/// A predictive parser for an LL(1) grammar.
+#[derive(Debug)]
+pub struct LLParser {
+    pub(crate) start_symbols: Vec<Id>,
+    pub(crate) terminals: IndexMap<Id, String>,
+    pub(crate) productions: Vec<(Id, Vec<Symbol>)>,
+    pub(crate) analysis: GrammarAnalysis,
+
+    /// For every nonterminal, the production to expand it by on each lookahead
+    pub(crate) table: IndexMap<Id, BTreeMap<Symbol, usize>>,
+    pub(crate) warnings: Vec<Lint>,
+}
+
+/// A nonterminal with more than one production predicted on the same
+/// lookahead, which makes the grammar not LL(1).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LLConflict {
+    pub nonterminal: Id,
+    pub symbol: Symbol,
+    pub productions: Vec<usize>,
+}
+
+impl fmt::Display for LLConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let productions = self
+            .productions
+            .iter()
+            .map(usize::to_string)
+            .collect::<Vec<_>>();
+        write!(
+            f,
+            "{} predicts productions {} on {}",
+            self.nonterminal,
+            productions.join(", "),
+            self.symbol
+        )
+    }
+}
+
+impl Grammar {
+    /// Builds an LL(1) parser.
+    pub fn build_ll(self) -> Result<LLParser, GrammarError> {
+        self.build_ll_with(&BuildOptions::default())
+    }
+
+    /// Builds an LL(1) parser, linting the grammar as the options say.
+    ///
+    /// Fails with `GrammarError::NotLL1` listing every conflict if some
+    /// nonterminal has two productions predicted on the same lookahead.
+    pub fn build_ll_with(self, options: &BuildOptions) -> Result<LLParser, GrammarError> {
+        let grammar_symbols = self.grammar_symbols()?;
+        self.check_start_symbols(&grammar_symbols)?;
+        self.check_patterns()?;
+        let analysis = self.analyze()?;
+        let (warnings, denied) = options.lints.check(&self, &analysis);
+        if !denied.is_empty() {
+            return Err(GrammarError::Lints(denied));
+        }
+
+        let mut table = self
+            .productions
+            .keys()
+            .map(|nonterminal| (*nonterminal, BTreeMap::new()))
+            .collect::<IndexMap<_, _>>();
+        let mut conflicts: Vec<LLConflict> = Vec::new();
+        for (n, (lhs, _)) in analysis.productions.iter().enumerate() {
+            let row = table.get_mut(lhs).unwrap();
+            for symbol in analysis.predict_set(n) {
+                let existing = match row.get(&symbol) {
+                    Some(existing) => *existing,
+                    None => {
+                        row.insert(symbol, n);
+                        continue;
+                    }
+                };
+                let conflict = conflicts
+                    .iter_mut()
+                    .find(|conflict| conflict.nonterminal == *lhs && conflict.symbol == symbol);
+                match conflict {
+                    Some(conflict) => conflict.productions.push(n),
+                    None => conflicts.push(LLConflict {
+                        nonterminal: *lhs,
+                        symbol,
+                        productions: vec![existing, n],
+                    }),
+                }
+            }
+        }
+        if !conflicts.is_empty() {
+            return Err(GrammarError::NotLL1(conflicts));
+        }
+
+        Ok(LLParser {
+            start_symbols: self.start_symbols,
+            terminals: self.terminals,
+            productions: analysis.productions.clone(),
+            analysis,
+            table,
+            warnings,
+        })
+    }
+}
+
+impl LLParser {
+    /// The nullable, FIRST and FOLLOW sets this parser was built from.
+    pub fn analysis(&self) -> &GrammarAnalysis {
+        &self.analysis
+    }
+
+    /// Lints that were set to warn and fired while building this parser.
+    pub fn warnings(&self) -> &[Lint] {
+        &self.warnings
+    }
+
+    /// The production to expand a nonterminal by on a lookahead, if any.
+    pub fn predict(&self, nonterminal: Id, lookahead: &Symbol) -> Option<usize> {
+        self.table.get(&nonterminal)?.get(lookahead).cloned()
+    }
+
+    /// The terminals in the order the lexer numbers them, ending with EOF.
+    fn terminal_columns(&self) -> Vec<Symbol> {
+        let mut terminals: Vec<_> = self.terminals.keys().map(|term| Symbol::T(*term)).collect();
+        terminals.push(Symbol::EOF);
+        terminals
+    }
+
+    /// A lexer for the terminals, numbering them in column order.
+    pub(crate) fn lexer(&self) -> Lexer {
+        Lexer::for_terminals(&self.terminals)
+    }
+
+    /// The terminals some production of a nonterminal is predicted on.
+    fn expected(&self, terminals: &[Symbol], nonterminal: Id) -> Vec<usize> {
+        let row = &self.table[&nonterminal];
+        (0..terminals.len())
+            .filter(|column| row.contains_key(&terminals[*column]))
+            .collect()
+    }
+
+    /// Describes a syntax error from `parse`, with a snippet of the input.
+    pub fn render_error(&self, input: &str, error: &ParseError) -> String {
+        let names = self
+            .terminal_columns()
+            .iter()
+            .map(Symbol::name)
+            .collect::<Vec<_>>();
+        let names = names.iter().map(|name| name.as_str()).collect::<Vec<_>>();
+        error.render(input, &names)
+    }
+
+    /// Parses the input straight off the predict table.
+    pub fn parse(
+        &self,
+        start_symbol: impl AsRef<str>,
+        input: impl AsRef<str>,
+    ) -> Result<Tree, ParseError> {
+        let start_symbol = Id::from(start_symbol.as_ref());
+        assert!(
+            self.start_symbols.contains(&start_symbol),
+            "not a start symbol"
+        );
+        let lexer = self.lexer();
+        let terminals = self.terminal_columns();
+
+        let mut tokens = Tokens::new(&lexer, input.as_ref())?;
+        let tree = self.expand(&terminals, &mut tokens, start_symbol)?;
+        tokens.end()?;
+        Ok(tree)
+    }
+
+    fn expand(
+        &self,
+        terminals: &[Symbol],
+        tokens: &mut Tokens,
+        nonterminal: Id,
+    ) -> Result<Tree, ParseError> {
+        let lookahead = &terminals[tokens.peek()];
+        let n = self.predict(nonterminal, lookahead).ok_or_else(|| {
+            let expected = self.expected(terminals, nonterminal);
+            tokens.error(&expected)
+        })?;
+        let mut children = Vec::new();
+        for symbol in self.productions[n].1.iter() {
+            let child = match symbol {
+                Symbol::NT(name) => self.expand(terminals, tokens, *name)?,
+                _ => {
+                    let terminal = terminals.iter().position(|t| t == symbol).unwrap();
+                    tokens.expect(terminal)?
+                }
+            };
+            children.push(child);
+        }
+        Ok(Tree::node(n, children, tokens.position()))
+    }
+
+    /// Generates a recursive-descent parser, with a function per nonterminal
+    /// that matches on the lookahead's terminal number.
+    pub fn codegen(&self, mut w: impl Write) -> io::Result<()> {
+        let terminals = self.terminal_columns();
+
+        // header
+        writeln!(w, "// Code was generated by pgen")?;
+        writeln!(
+            w,
+            "#![allow(dead_code, non_snake_case, unused, clippy::all)]"
+        )?;
+        writeln!(w, "use pgen::runtime::{{Lexer, ParseError, Tokens, Tree}};")?;
+
+        // write patterns for the lexer
+        writeln!(w, "const PATTERNS: &[&str] = &[")?;
+        for regex in self.terminals.values() {
+            writeln!(w, "    {:?},", regex)?;
+        }
+        writeln!(w, "];")?;
+
+        // and the names of the terminals, for error messages
+        writeln!(w, "pub const TERMINALS: &[&str] = &[")?;
+        for terminal in self.terminal_columns() {
+            writeln!(w, "    {:?},", terminal.name().as_str())?;
+        }
+        writeln!(w, "];")?;
+
+        for (nonterminal, row) in self.table.iter() {
+            // group the lookaheads that predict the same production into one arm
+            let mut arms: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+            for (column, terminal) in terminals.iter().enumerate() {
+                if let Some(n) = row.get(terminal) {
+                    arms.entry(*n).or_default().push(column);
+                }
+            }
+
+            writeln!(
+                w,
+                "fn rule_{}(tokens: &mut Tokens<'_>) -> Result<Tree, ParseError> {{",
+                nonterminal
+            )?;
+            writeln!(w, "    match tokens.peek() {{")?;
+            for (n, columns) in arms {
+                let names = columns
+                    .iter()
+                    .map(|column| terminals[*column].name().to_string())
+                    .collect::<Vec<_>>();
+                let columns = columns.iter().map(usize::to_string).collect::<Vec<_>>();
+                let (lhs, symbols) = &self.productions[n];
+                let children = symbols
+                    .iter()
+                    .map(|symbol| match symbol {
+                        Symbol::NT(name) => format!("rule_{}(tokens)?", name),
+                        _ => {
+                            let terminal = terminals.iter().position(|t| t == symbol).unwrap();
+                            format!("tokens.expect({})?", terminal)
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                writeln!(w, "        // {}", names.join(" | "))?;
+                writeln!(w, "        {} => {{", columns.join(" | "))?;
+                writeln!(w, "            // {} -> {}", lhs, symbol_list(symbols))?;
+                writeln!(
+                    w,
+                    "            let children = vec![{}];",
+                    children.join(", ")
+                )?;
+                writeln!(
+                    w,
+                    "            Ok(Tree::node({}, children, tokens.position()))",
+                    n
+                )?;
+                writeln!(w, "        }}")?;
+            }
+            let expected = self.expected(&terminals, *nonterminal);
+            let expected = expected.iter().map(usize::to_string).collect::<Vec<_>>();
+            writeln!(
+                w,
+                "        _ => Err(tokens.error(&[{}])),",
+                expected.join(", ")
+            )?;
+            writeln!(w, "    }}")?;
+            writeln!(w, "}}")?;
+        }
+
+        // main parser struct
+        writeln!(w, "pub struct Parser<'a> {{")?;
+        writeln!(w, "    lexer: Lexer,")?;
+        writeln!(w, "    input: &'a str,")?;
+        writeln!(w, "}}")?;
+        writeln!(w, "impl<'a> Parser<'a> {{")?;
+        writeln!(w, "    pub fn new(input: &'a str) -> Self {{")?;
+        writeln!(w, "let lexer = Lexer::new(PATTERNS).unwrap();")?;
+        writeln!(w, "Parser {{ lexer, input }}")?;
+        writeln!(w, "}}")?;
+        writeln!(
+            w,
+            "pub fn render_error(&self, error: &ParseError) -> String {{"
+        )?;
+        writeln!(w, "error.render(self.input, TERMINALS)")?;
+        writeln!(w, "}}")?;
+        for nonterminal in self.start_symbols.iter() {
+            writeln!(
+                w,
+                "pub fn parse_{}(&mut self) -> Result<Tree, ParseError> {{",
+                nonterminal
+            )?;
+            writeln!(w, "let mut tokens = Tokens::new(&self.lexer, self.input)?;")?;
+            writeln!(w, "let tree = rule_{}(&mut tokens)?;", nonterminal)?;
+            writeln!(w, "tokens.end()?;")?;
+            writeln!(w, "Ok(tree)")?;
+            writeln!(w, "}}")?;
+        }
+        writeln!(w, "}}")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use symbol::Symbol as Id;
+
+    use super::LLConflict;
+    use crate::grammar::{GrammarError, Symbol};
+
+    #[test]
+    fn test_predict_sets() {
+        let parser = crate::this::ll_grammar().build_ll().unwrap();
+        let predict_set = |n: usize| {
+            let set = parser.analysis().predict_set(n);
+            set.iter().map(|s| s.to_string()).collect::<Vec<_>>()
+        };
+        // E_ -> ɛ is predicted on FOLLOW(E_)
+        assert_eq!(predict_set(1), vec!["Add"]);
+        assert_eq!(predict_set(2), vec!["RP", "$"]);
+        assert_eq!(predict_set(6), vec!["LP"]);
+        assert_eq!(
+            parser.predict(Id::from("T_"), &Symbol::T(Id::from("Add"))),
+            Some(5)
+        );
+        assert_eq!(parser.predict(Id::from("F"), &Symbol::EOF), None);
+    }
+
+    #[test]
+    fn test_agrees_with_lr() {
+        let ll = crate::this::ll_grammar().build_ll().unwrap();
+        let lr = crate::this::ll_grammar().build().unwrap();
+        for input in &["1", "1+2", "(1+2)*3", "4*(5+6*7)+8"] {
+            assert_eq!(
+                ll.parse("E", input).unwrap(),
+                lr.interpret("E", input).unwrap()
+            );
+        }
+        for input in &["", "+", "1+", "(1", "1)", "1 2"] {
+            assert!(ll.parse("E", input).is_err(), "{:?} parsed", input);
+        }
+    }
+
+    #[test]
+    fn test_not_ll1() {
+        // left recursion always conflicts
+        match crate::this::pgen_grammar().build_ll() {
+            Err(GrammarError::NotLL1(conflicts)) => {
+                assert!(conflicts.contains(&LLConflict {
+                    nonterminal: Id::from("E"),
+                    symbol: Symbol::T(Id::from("N0")),
+                    productions: vec![0, 1, 2],
+                }));
+                assert_eq!(
+                    conflicts[0].to_string(),
+                    "E predicts productions 0, 1, 2 on N0"
+                );
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_codegen() {
+        let parser = crate::this::ll_grammar().build_ll().unwrap();
+        let mut buf = Vec::new();
+        parser.codegen(&mut buf).unwrap();
+        let code = String::from_utf8(buf).unwrap();
+        assert!(code.contains(
+            "fn rule_E_(tokens: &mut Tokens<'_>) -> Result<Tree, ParseError> {\n    match tokens.peek() {\n        // Add\n        1 => {\n            // E_ -> Add T E_\n            let children = vec![tokens.expect(1)?, rule_T(tokens)?, rule_E_(tokens)?];\n"
+        ));
+        assert!(code.contains("        // RP | $\n        4 | 5 => {\n            // E_ -> \u{025b}\n            let children = vec![];\n"));
+    }
+}
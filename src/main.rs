@@ -1,14 +1,124 @@
-use std::fs::File;
-
-use pgen::Grammar;
-use pgen::Parser;
+use std::env;
+use std::fs::{self, File};
+use std::io;
+use std::process;
 
 fn main() {
-    let grammar = pgen::this::pgen_grammar();
-    let parser = grammar.build().unwrap();
+    let args = env::args().collect::<Vec<_>>();
+    match args.get(1).map(String::as_str) {
+        // pgen report <grammar> > report.html
+        Some("report") => {
+            let name = args.get(2).map(String::as_str).unwrap_or("pgen");
+            let grammar = match pgen::this::by_name(name) {
+                Some(grammar) => grammar,
+                None => {
+                    eprintln!("Unknown grammar: {}", name);
+                    process::exit(1);
+                }
+            };
+            let parser = build(grammar);
+            let stdout = io::stdout();
+            parser.write_report(stdout.lock()).unwrap();
+        }
+        // pgen messages <grammar> [file.messages]
+        Some("messages") => {
+            let name = args.get(2).map(String::as_str).unwrap_or("pgen");
+            let grammar = match pgen::this::by_name(name) {
+                Some(grammar) => grammar,
+                None => {
+                    eprintln!("Unknown grammar: {}", name);
+                    process::exit(1);
+                }
+            };
+            let parser = build(grammar);
+            // without a file, list every error state, to start one from
+            let missing = match args.get(3) {
+                Some(path) => {
+                    let messages = load_messages(&parser, path);
+                    parser.missing_messages(&messages)
+                }
+                None => parser.list_errors(),
+            };
+            print!("{}", missing);
+        }
+        _ => {
+            let grammar = pgen::this::pgen_grammar();
+            let parser = build(grammar);
+
+            let messages = load_messages(&parser, "wtf/pgen.messages");
+            for (path, backend) in &[
+                ("wtf/src/table.rs", pgen::Backend::Table),
+                ("wtf/src/direct.rs", pgen::Backend::Direct),
+            ] {
+                let options = pgen::CodegenOptions {
+                    backend: *backend,
+                    messages: messages.clone(),
+                    ..pgen::CodegenOptions::default()
+                };
+                let file = File::create(path).unwrap();
+                parser.codegen_with(file, &options).unwrap();
+            }
+
+            let options = pgen::CodegenOptions {
+                backend: pgen::Backend::Direct,
+                ..pgen::CodegenOptions::default()
+            };
+            let stmts_parser = build(pgen::this::stmts_grammar());
+            let file = File::create("wtf/src/stmts.rs").unwrap();
+            stmts_parser.codegen_with(file, &options).unwrap();
+
+            let ll_parser = match pgen::this::ll_grammar().build_ll() {
+                Ok(parser) => parser,
+                Err(err) => {
+                    eprintln!("error: {}", err);
+                    process::exit(1);
+                }
+            };
+            let file = File::create("wtf/src/ll.rs").unwrap();
+            ll_parser.codegen(file).unwrap();
 
-    let file = File::create("wtf/src/lib.rs").unwrap();
-    parser.codegen(file).unwrap();
+            parser.interpret("E", "1+1").unwrap();
+        }
+    }
+}
+
+fn build(grammar: pgen::Grammar) -> pgen::Parser {
+    let parser = match grammar.build() {
+        Ok(parser) => parser,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        }
+    };
+    for warning in parser.warnings() {
+        eprintln!("warning: {}", warning);
+    }
+    parser
+}
 
-    parser.interpret("E", "1+1");
+/// Reads a `.messages` file and checks it against the parser.
+fn load_messages(parser: &pgen::Parser, path: &str) -> pgen::ErrorMessages {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) => {
+            eprintln!("error: {}: {}", path, err);
+            process::exit(1);
+        }
+    };
+    let messages = match text.parse() {
+        Ok(messages) => messages,
+        Err(err) => {
+            eprintln!("error: {}: {}", path, err);
+            process::exit(1);
+        }
+    };
+    match parser.compile_messages(&messages) {
+        Ok(messages) => messages,
+        Err(errors) => {
+            for err in errors {
+                eprintln!("error: {}: {}", path, err);
+            }
+            process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,582 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+use symbol::Symbol as Id;
+
+use crate::grammar::Symbol;
+use crate::parser::Action;
+use crate::Parser;
+
+/// The message `Parser::list_errors` leaves for every state, to be filled in.
+pub const PLACEHOLDER: &str = "<YOUR SYNTAX ERROR MESSAGE HERE>";
+
+/// Hand-written syntax error messages, each given for the states some example
+/// sentences fail in, like Menhir's `.messages` files.
+///
+/// In the file, every entry is one or more sentences, a blank line, and then
+/// the message, which runs up to the next blank line. A sentence is a start
+/// symbol, a colon and the names of the terminals, with `$` for the end of
+/// input, ending on the one the error is found at:
+///
+/// ```text
+/// ## a comment
+/// E: N1 Add Add
+/// E: N1 Mul Add
+///
+/// Expected a number after this operator.
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Messages {
+    pub entries: Vec<MessageEntry>,
+}
+
+/// Some example sentences and the message for the states they fail in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MessageEntry {
+    pub sentences: Vec<Sentence>,
+    pub message: String,
+}
+
+/// A sentence that ends in a syntax error at its last terminal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sentence {
+    /// The line of the file it's on, counting from 1, or 0 if it isn't from
+    /// a file
+    pub line: usize,
+    pub start: Id,
+    pub terminals: Vec<Id>,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MessageError {
+    #[error("line {0}: expected a sentence like `Start: Terminal Terminal`")]
+    Syntax(usize),
+
+    #[error("line {0}: these sentences have no message")]
+    NoMessage(usize),
+
+    #[error("line {line}: unknown start symbol {symbol}")]
+    UnknownStart { line: usize, symbol: Id },
+
+    #[error("line {line}: unknown terminal {symbol}")]
+    UnknownTerminal { line: usize, symbol: Id },
+
+    #[error("line {0}: the sentence doesn't end in a syntax error")]
+    NoError(usize),
+
+    #[error("line {line}: the sentence fails early, at token {token}")]
+    FailsEarly { line: usize, token: usize },
+
+    #[error("line {line}: state {state} already has a message, from line {first}")]
+    Duplicate {
+        line: usize,
+        first: usize,
+        state: usize,
+    },
+}
+
+/// Messages checked against a parser, keyed by the state they're for.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ErrorMessages {
+    /// Sorted by state
+    messages: Vec<(usize, String)>,
+}
+
+impl ErrorMessages {
+    /// The message for errors found in the state, if it has one.
+    pub fn get(&self, state: usize) -> Option<&str> {
+        let i = self
+            .messages
+            .binary_search_by_key(&state, |(state, _)| *state)
+            .ok()?;
+        Some(&self.messages[i].1)
+    }
+
+    /// The messages in the form `ParseError::render_with_messages` takes.
+    pub fn as_slice(&self) -> Vec<(usize, &str)> {
+        self.messages
+            .iter()
+            .map(|(state, message)| (*state, message.as_str()))
+            .collect()
+    }
+}
+
+/// Where running a sentence through the automaton gets.
+enum Run {
+    Fails { at: usize, state: usize },
+    Accepts,
+    Incomplete,
+}
+
+impl Parser {
+    /// Checks messages against this parser, mapping every sentence to the
+    /// state it fails in.
+    ///
+    /// Sentences that have gone stale, because they no longer fail or fail
+    /// before their last terminal, are errors, and so are two entries with
+    /// sentences failing in the same state.
+    pub fn compile_messages(
+        &self,
+        messages: &Messages,
+    ) -> Result<ErrorMessages, Vec<MessageError>> {
+        let mut errors = Vec::new();
+        // state -> (entry, line, message)
+        let mut by_state: BTreeMap<usize, (usize, usize, &str)> = BTreeMap::new();
+        for (i, entry) in messages.entries.iter().enumerate() {
+            for sentence in entry.sentences.iter() {
+                let state = match self.error_state(sentence) {
+                    Ok(state) => state,
+                    Err(error) => {
+                        errors.push(error);
+                        continue;
+                    }
+                };
+                match by_state.get(&state) {
+                    Some((entry, _, _)) if *entry == i => (),
+                    Some((_, first, _)) => errors.push(MessageError::Duplicate {
+                        line: sentence.line,
+                        first: *first,
+                        state,
+                    }),
+                    None => {
+                        by_state.insert(state, (i, sentence.line, &entry.message));
+                    }
+                }
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        let messages = by_state
+            .into_iter()
+            .map(|(state, (_, _, message))| (state, message.to_owned()))
+            .collect();
+        Ok(ErrorMessages { messages })
+    }
+
+    /// Lists a shortest sentence failing in every state that a syntax error
+    /// can be found in, with a placeholder message for each.
+    pub fn list_errors(&self) -> Messages {
+        self.missing_messages(&ErrorMessages::default())
+    }
+
+    /// Lists the states errors can be found in that have no message, like
+    /// `list_errors` does.
+    pub fn missing_messages(&self, messages: &ErrorMessages) -> Messages {
+        let entries = self
+            .error_sentences()
+            .into_iter()
+            .filter(|(state, _)| messages.get(*state).is_none())
+            .map(|(_, sentence)| MessageEntry {
+                sentences: vec![sentence],
+                message: PLACEHOLDER.to_owned(),
+            })
+            .collect();
+        Messages { entries }
+    }
+
+    /// The state a sentence fails in at its last terminal.
+    fn error_state(&self, sentence: &Sentence) -> Result<usize, MessageError> {
+        let line = sentence.line;
+        let start_state = match self.entry_states.get(&sentence.start) {
+            Some(state) => *state,
+            None => {
+                return Err(MessageError::UnknownStart {
+                    line,
+                    symbol: sentence.start,
+                })
+            }
+        };
+        let mut terminals = Vec::new();
+        for symbol in sentence.terminals.iter() {
+            match symbol.as_str() {
+                "$" => terminals.push(Symbol::EOF),
+                _ if self.terminals.contains_key(symbol) => terminals.push(Symbol::T(*symbol)),
+                _ => {
+                    return Err(MessageError::UnknownTerminal {
+                        line,
+                        symbol: *symbol,
+                    })
+                }
+            }
+        }
+        match self.run(start_state, &terminals) {
+            Run::Fails { at, state } if at + 1 == terminals.len() => Ok(state),
+            Run::Fails { at, .. } => Err(MessageError::FailsEarly {
+                line,
+                token: at + 1,
+            }),
+            Run::Accepts | Run::Incomplete => Err(MessageError::NoError(line)),
+        }
+    }
+
+    /// Runs the terminals through the automaton from a state, without
+    /// building a tree.
+    fn run(&self, start_state: usize, terminals: &[Symbol]) -> Run {
+        let mut states = vec![start_state];
+        for (i, terminal) in terminals.iter().enumerate() {
+            loop {
+                let state = *states.last().unwrap();
+                match self.action(state, terminal) {
+                    Some(Action::Shift(next)) => {
+                        states.push(next);
+                        break;
+                    }
+                    Some(Action::Reduce(n)) => {
+                        let (lhs, symbols) = &self.productions[n];
+                        states.truncate(states.len() - symbols.len());
+                        let (_, gotos) = &self.table.0[*states.last().unwrap()];
+                        states.push(gotos[&Symbol::NT(*lhs)]);
+                    }
+                    Some(Action::Accept) => return Run::Accepts,
+                    None => return Run::Fails { at: i, state },
+                }
+            }
+        }
+        Run::Incomplete
+    }
+
+    /// A sentence for every state errors can be found in, made of a shortest
+    /// way to reach some state and a terminal it has no action on.
+    fn error_sentences(&self) -> Vec<(usize, Sentence)> {
+        let columns = self.terminal_columns();
+        let shortest = self.shortest_sentences();
+
+        // the shortest terminals that reach every state, by Dijkstra
+        let mut prefixes: HashMap<usize, (Id, Vec<Symbol>)> = HashMap::new();
+        for (start, entry_state) in self.entry_states.iter() {
+            let mut queue = BinaryHeap::new();
+            let mut reached = HashMap::new();
+            reached.insert(*entry_state, Vec::new());
+            queue.push(Reverse((0, *entry_state)));
+            while let Some(Reverse((len, state))) = queue.pop() {
+                if reached[&state].len() < len {
+                    continue;
+                }
+                let (actions, gotos) = &self.table.0[state];
+                let shifts = columns
+                    .iter()
+                    .filter_map(|terminal| match actions.get(terminal) {
+                        Some(Action::Shift(next)) => Some((*next, vec![terminal.clone()])),
+                        _ => None,
+                    });
+                let gotos = self.nonterminals.iter().filter_map(|nonterminal| {
+                    let next = gotos.get(&Symbol::NT(*nonterminal))?;
+                    Some((*next, shortest.get(nonterminal)?.clone()))
+                });
+                let edges = shifts.chain(gotos).collect::<Vec<_>>();
+                for (next, symbols) in edges {
+                    let mut prefix = reached[&state].clone();
+                    prefix.extend(symbols);
+                    if reached
+                        .get(&next)
+                        .is_none_or(|old| prefix.len() < old.len())
+                    {
+                        queue.push(Reverse((prefix.len(), next)));
+                        reached.insert(next, prefix);
+                    }
+                }
+            }
+            for (state, prefix) in reached {
+                let shorter = prefixes
+                    .get(&state)
+                    .is_none_or(|(_, old)| prefix.len() < old.len());
+                if shorter {
+                    prefixes.insert(state, (*start, prefix));
+                }
+            }
+        }
+
+        let mut sentences = BTreeMap::new();
+        for state in 0..self.table.0.len() {
+            let (start, prefix) = match prefixes.get(&state) {
+                Some(prefix) => prefix,
+                None => continue,
+            };
+            for terminal in columns.iter() {
+                if self.action(state, terminal).is_some() {
+                    continue;
+                }
+                let mut terminals = prefix.clone();
+                terminals.push(terminal.clone());
+                match self.run(self.entry_states[start], &terminals) {
+                    Run::Fails { at, state } if at + 1 == terminals.len() => {
+                        sentences.entry(state).or_insert_with(|| Sentence {
+                            line: 0,
+                            start: *start,
+                            terminals: terminals.iter().map(Symbol::name).collect(),
+                        });
+                    }
+                    _ => (),
+                }
+            }
+        }
+        sentences.into_iter().collect()
+    }
+
+    /// The shortest terminals every nonterminal derives, leaving out the ones
+    /// that only derive sentences with `error` in them.
+    fn shortest_sentences(&self) -> HashMap<Id, Vec<Symbol>> {
+        let mut shortest: HashMap<Id, Vec<Symbol>> = HashMap::new();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (lhs, symbols) in self.productions.iter() {
+                let mut sentence = Vec::new();
+                let derivable = symbols.iter().all(|symbol| match symbol {
+                    Symbol::NT(nonterminal) => match shortest.get(nonterminal) {
+                        Some(terminals) => {
+                            sentence.extend(terminals.iter().cloned());
+                            true
+                        }
+                        None => false,
+                    },
+                    _ if *symbol == Symbol::error() => false,
+                    _ => {
+                        sentence.push(symbol.clone());
+                        true
+                    }
+                });
+                let shorter = shortest
+                    .get(lhs)
+                    .is_none_or(|old| sentence.len() < old.len());
+                if derivable && shorter {
+                    shortest.insert(*lhs, sentence);
+                    changed = true;
+                }
+            }
+        }
+        shortest
+    }
+}
+
+impl FromStr for Messages {
+    type Err = MessageError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim_end()))
+            .peekable();
+        let mut entries = Vec::new();
+        loop {
+            while let Some((_, line)) = lines.peek() {
+                match line.is_empty() || line.starts_with('#') {
+                    true => lines.next(),
+                    false => break,
+                };
+            }
+            let first = match lines.peek() {
+                Some((number, _)) => *number,
+                None => break,
+            };
+
+            // sentences, and comments between them, up to a blank line
+            let mut sentences = Vec::new();
+            while let Some((number, line)) = lines.next_if(|(_, line)| !line.is_empty()) {
+                if !line.starts_with('#') {
+                    sentences.push(Sentence::parse(number, line)?);
+                }
+            }
+            while lines.next_if(|(_, line)| line.is_empty()).is_some() {}
+
+            let mut message = Vec::new();
+            while let Some((_, line)) = lines.next_if(|(_, line)| !line.is_empty()) {
+                message.push(line);
+            }
+            if message.is_empty() {
+                return Err(MessageError::NoMessage(first));
+            }
+            entries.push(MessageEntry {
+                sentences,
+                message: message.join("\n"),
+            });
+        }
+        Ok(Messages { entries })
+    }
+}
+
+impl Sentence {
+    fn parse(line: usize, text: &str) -> Result<Self, MessageError> {
+        let (start, terminals) = text.split_once(':').ok_or(MessageError::Syntax(line))?;
+        let start = start.trim();
+        let terminals = terminals
+            .split_whitespace()
+            .map(Id::from)
+            .collect::<Vec<_>>();
+        if start.is_empty() || start.contains(char::is_whitespace) || terminals.is_empty() {
+            return Err(MessageError::Syntax(line));
+        }
+        Ok(Sentence {
+            line,
+            start: Id::from(start),
+            terminals,
+        })
+    }
+}
+
+impl fmt::Display for Sentence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let terminals = self
+            .terminals
+            .iter()
+            .map(|terminal| terminal.as_str())
+            .collect::<Vec<_>>();
+        write!(f, "{}: {}", self.start, terminals.join(" "))
+    }
+}
+
+/// Writes the messages back out in the format they're parsed from.
+impl fmt::Display for Messages {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for entry in self.entries.iter() {
+            for sentence in entry.sentences.iter() {
+                writeln!(f, "{}", sentence)?;
+            }
+            writeln!(f)?;
+            writeln!(f, "{}", entry.message)?;
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MessageError, Messages, PLACEHOLDER};
+    use crate::tables::TableLayout;
+    use crate::CodegenOptions;
+
+    const MESSAGES: &str = "\
+## operators need a number on each side
+E: Add
+E: $
+
+A number has to come first.
+
+E: N0 Add Add
+E: N1 Mul $
+E: N1 Mul Mul
+
+Expected a number after this operator.
+";
+
+    #[test]
+    fn test_messages() {
+        let parser = crate::this::pgen_grammar().build().unwrap();
+        let messages = MESSAGES.parse::<Messages>().unwrap();
+        assert_eq!(messages.entries.len(), 2);
+        assert_eq!(messages.entries[0].sentences[0].line, 2);
+        assert_eq!(messages.entries[1].sentences.len(), 3);
+        assert_eq!(
+            messages
+                .to_string()
+                .parse::<Messages>()
+                .unwrap()
+                .to_string(),
+            messages.to_string()
+        );
+
+        let compiled = parser.compile_messages(&messages).unwrap();
+        let error = parser.interpret("E", "1+*").unwrap_err();
+        assert_eq!(
+            compiled.get(error.state.unwrap()),
+            Some("Expected a number after this operator.")
+        );
+        assert!(parser
+            .render_error_with("1+*", &error, &compiled)
+            .starts_with("error: Expected a number after this operator.\n --> 1:3\n"));
+        let error = parser.interpret("E", "").unwrap_err();
+        assert_eq!(
+            compiled.get(error.state.unwrap()),
+            Some("A number has to come first.")
+        );
+
+        // the states left without a message are the ones after a number
+        let missing = parser.missing_messages(&compiled);
+        let missing = missing.to_string();
+        assert_eq!(
+            missing,
+            format!("E: N0 N0\n\n{0}\n\nE: N1 N0\n\n{0}\n\n", PLACEHOLDER)
+        );
+
+        // and they make it into generated code
+        let mut code = Vec::new();
+        let options = CodegenOptions {
+            layout: TableLayout::Dense,
+            messages: compiled.clone(),
+            ..CodegenOptions::default()
+        };
+        parser.codegen_with(&mut code, &options).unwrap();
+        let code = String::from_utf8(code).unwrap();
+        let state = parser.interpret("E", "1+*").unwrap_err().state.unwrap();
+        assert!(code.contains(&format!(
+            "({}, \"Expected a number after this operator.\")",
+            state
+        )));
+    }
+
+    #[test]
+    fn test_list_errors() {
+        for name in &["pgen", "stmts", "c"] {
+            let parser = crate::this::by_name(name).unwrap().build().unwrap();
+            let listed = parser.list_errors();
+            assert!(!listed.entries.is_empty());
+            let compiled = parser
+                .compile_messages(&listed.to_string().parse().unwrap())
+                .unwrap();
+            assert_eq!(compiled.as_slice().len(), listed.entries.len());
+            assert!(parser.missing_messages(&compiled).entries.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_stale_messages() {
+        let parser = crate::this::pgen_grammar().build().unwrap();
+        let check = |text: &str| parser.compile_messages(&text.parse().unwrap()).unwrap_err();
+        assert_eq!(
+            check("E: N1 Add N1\n\nNo error.\n"),
+            vec![MessageError::NoError(1)]
+        );
+        assert_eq!(
+            check("E: N1 $ Add\n\nAccepted first.\n"),
+            vec![MessageError::NoError(1)]
+        );
+        assert_eq!(
+            check("E: Add Add\n\nFails first.\n"),
+            vec![MessageError::FailsEarly { line: 1, token: 1 }]
+        );
+        assert_eq!(
+            check("E: N1 Add Add\n\nOne.\n\nE: N0 Add Mul\n\nTwo.\n"),
+            vec![MessageError::Duplicate {
+                line: 5,
+                first: 1,
+                state: 5
+            }]
+        );
+        assert!(matches!(
+            check("B: N1\nE: N2\n\nUnknown.\n")[..],
+            [
+                MessageError::UnknownStart { line: 1, .. },
+                MessageError::UnknownTerminal { line: 2, .. }
+            ]
+        ));
+
+        // and files that don't parse
+        assert_eq!(
+            "E: N1 N1\n".parse::<Messages>(),
+            Err(MessageError::NoMessage(1))
+        );
+        assert_eq!(
+            "\nN1 N1\n\nNo start.\n".parse::<Messages>(),
+            Err(MessageError::Syntax(2))
+        );
+        assert_eq!(
+            "E:\n\nEmpty.\n".parse::<Messages>(),
+            Err(MessageError::Syntax(1))
+        );
+    }
+}
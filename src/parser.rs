@@ -1,194 +1,559 @@
 use indexmap::IndexMap;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
 use std::io::{self, Write};
+use std::sync::OnceLock;
 
 use prettytable::Table;
 use symbol::Symbol as Id;
 
+use crate::analysis::GrammarAnalysis;
 use crate::grammar::Symbol;
+use crate::items::LR0Item;
+use crate::lints::Lint;
+use crate::messages::ErrorMessages;
+use crate::runtime::{self, ParseError, PushParser, Recovered, Stack, Tree};
+use crate::tables::{Backend, CodegenOptions, PackedTables, TableLayout};
 
 #[derive(Debug)]
 pub struct Parser {
     pub(crate) start_symbols: Vec<Id>,
+    pub(crate) entry_states: IndexMap<Id, usize>,
     pub(crate) terminals: IndexMap<Id, String>,
-    pub(crate) nonterminals: HashSet<Id>,
+    pub(crate) nonterminals: Vec<Id>,
+    pub(crate) productions: Vec<(Id, Vec<Symbol>)>,
+    pub(crate) analysis: GrammarAnalysis,
+    pub(crate) states: Vec<BTreeSet<LR0Item>>,
     pub(crate) table: ParseTable,
+    pub(crate) default_reductions: Vec<Option<usize>>,
+    pub(crate) conflicts: Vec<Conflict>,
+    pub(crate) warnings: Vec<Lint>,
+
+    /// The repair cost of every terminal column, EOF included
+    pub(crate) repair_costs: Vec<usize>,
+
+    /// The dense tables that repairing parses run on, packed the first time
+    /// one is made
+    pub(crate) dense_tables: OnceLock<PackedTables>,
 }
 
 impl Parser {
-    pub fn codegen(&self, mut w: impl Write) -> io::Result<()> {
-        let mut terminals: Vec<_> = self
-            .terminals
-            .keys()
-            .map(|term| Symbol::T(term.clone()))
-            .collect();
-        terminals.push(Symbol::EOF);
+    /// The nullable, FIRST and FOLLOW sets this parser was built from.
+    pub fn analysis(&self) -> &GrammarAnalysis {
+        &self.analysis
+    }
 
-        let n_states = self.table.0.len();
+    /// Lints that were set to warn and fired while building this parser.
+    pub fn warnings(&self) -> &[Lint] {
+        &self.warnings
+    }
 
-        let mut table = Table::new();
-        let mut row = row!["table"];
-        row.extend(terminals.iter().map(|s| s.name()));
-        row.extend(self.nonterminals.iter());
-        table.add_row(row);
-        for (i, (action, goto)) in self.table_iter() {
-            let mut row = row![i];
-            row.extend(terminals.iter().map(|term| {
-                if let Some(action) = action.get(term) {
-                    action.shortname()
-                } else {
-                    "".to_owned()
-                }
-            }));
-            row.extend(self.nonterminals.iter().map(|nonterm| {
-                if let Some(goto) = goto.get(&Symbol::NT(nonterm.to_owned())) {
-                    goto.to_string()
-                } else {
-                    "".to_owned()
-                }
-            }));
-            table.add_row(row);
-        }
-        table.printstd();
+    pub fn codegen(&self, w: impl Write) -> io::Result<()> {
+        self.codegen_with(w, &CodegenOptions::default())
+    }
+
+    /// Generates a parser module with the backend the options ask for.
+    ///
+    /// The table backend drives `pgen::runtime` with this parser's tables,
+    /// laid out as the options say, using the narrowest integer types that
+    /// fit, and can also repair errors and make push parsers. The direct-coded
+    /// backend turns every state into a function instead, and only recovers
+    /// in panic mode.
+    ///
+    /// Either way the generated `Parser` has `expected_terminals` and
+    /// `expected_nonterminals` to tell what can come next after an LR stack,
+    /// like this parser's own, numbering the symbols as `TERMINALS` and
+    /// `NONTERMINALS` name them. A table parser's push parsers answer the
+    /// same for the text pushed so far.
+    pub fn codegen_with(&self, mut w: impl Write, options: &CodegenOptions) -> io::Result<()> {
+        self.pretty_table().printstd();
 
         // header
         writeln!(w, "// Code was generated by pgen")?;
-        writeln!(w, "use std::io::Read;")?;
-        writeln!(w, "use pgen::regex::RegexSet;")?;
+        writeln!(
+            w,
+            "#![allow(dead_code, non_snake_case, unused, clippy::all)]"
+        )?;
+        match options.backend {
+            Backend::Table => writeln!(
+                w,
+                "use pgen::runtime::{{self, Comb, CombTables, DenseTables, Lexer, ParseError, Recovered, Tree}};"
+            )?,
+            Backend::Direct => writeln!(
+                w,
+                "use pgen::runtime::{{self, Lexer, ParseError, Recovered, Stack, Tree}};"
+            )?,
+        }
 
         // write patterns for the lexer
-        writeln!(w, "const PATTERNS: &[&'static str] = &[")?;
+        writeln!(w, "const PATTERNS: &[&str] = &[")?;
         for regex in self.terminals.values() {
             writeln!(w, "    {:?},", regex)?;
         }
         writeln!(w, "];")?;
 
-        // write actions table
-        writeln!(w, "const ACTIONS: &[[i8;{}]] = &[", terminals.len())?;
-        for (i, (action, _)) in self.table_iter() {
-            write!(w, "    [")?;
-            for terminal in terminals.iter() {
-                if let Some(action) = action.get(&terminal) {
-                    write!(w, "{},", action.code(n_states))?;
-                } else {
-                    write!(w, "-2,")?;
-                }
-            }
-            writeln!(w, "],")?;
+        // and the names of the terminals, for error messages
+        writeln!(w, "pub const TERMINALS: &[&str] = &[")?;
+        for terminal in self.terminal_columns() {
+            writeln!(w, "    {:?},", terminal.name().as_str())?;
         }
         writeln!(w, "];")?;
 
-        // write gotos table
-        writeln!(w, "const GOTOS: &[[i8;{}]] = &[", self.nonterminals.len())?;
-        for (i, (_, goto)) in self.table_iter() {
-            write!(w, "    [")?;
-            for nonterminal in self.nonterminals.iter() {
-                if let Some(goto) = goto.get(&Symbol::NT(nonterminal.clone())) {
-                    write!(w, "{},", goto)?;
-                } else {
-                    write!(w, "-1,")?;
+        // and of the nonterminals, for what push parsers expect
+        writeln!(w, "pub const NONTERMINALS: &[&str] = &[")?;
+        for nonterminal in self.nonterminals.iter() {
+            writeln!(w, "    {:?},", nonterminal.as_str())?;
+        }
+        writeln!(w, "];")?;
+
+        // custom error messages, by the state the error is found in
+        writeln!(w, "const MESSAGES: &[(usize, &str)] = &[")?;
+        for (state, message) in options.messages.as_slice() {
+            writeln!(w, "    ({}, {:?}),", state, message)?;
+        }
+        writeln!(w, "];")?;
+
+        match options.backend {
+            Backend::Table => {
+                // write the nonterminal and length of every production
+                writeln!(w, "const PRODUCTIONS: &[(usize, usize)] = &[")?;
+                for (lhs, len) in self.production_shapes() {
+                    writeln!(w, "    ({}, {}),", lhs, len)?;
+                }
+                writeln!(w, "];")?;
+
+                // and what error repair pays for each terminal
+                writeln!(w, "const REPAIR_COSTS: &[usize] = &[")?;
+                for cost in self.repair_costs.iter() {
+                    writeln!(w, "    {},", cost)?;
                 }
+                writeln!(w, "];")?;
+
+                self.pack_tables(options.layout).write(&mut w)?;
             }
-            writeln!(w, "],")?;
+            Backend::Direct => self.write_states(&mut w)?,
         }
-        writeln!(w, "];")?;
 
         // main parser struct
         writeln!(w, "pub struct Parser<'a> {{")?;
-        writeln!(w, "    regex_set: RegexSet,")?;
-        writeln!(w, "    position: usize,")?;
+        writeln!(w, "    lexer: Lexer,")?;
         writeln!(w, "    input: &'a str,")?;
         writeln!(w, "}}")?;
         writeln!(w, "impl<'a> Parser<'a> {{")?;
         writeln!(w, "    pub fn new(input: &'a str) -> Self {{")?;
-        // TODO: check that the RegexSet doesn't fail to compile
+        writeln!(w, "let lexer = Lexer::new(PATTERNS).unwrap();")?;
+        writeln!(w, "Parser {{ lexer, input }}")?;
+        writeln!(w, "}}")?;
         writeln!(
             w,
-            "let regex_set = RegexSet::new(PATTERNS.clone()).unwrap();"
+            "pub fn render_error(&self, error: &ParseError) -> String {{"
         )?;
         writeln!(
             w,
-            "Parser {{ input: input.as_ref(), regex_set, position: 0 }}"
+            "error.render_with_messages(self.input, TERMINALS, MESSAGES)"
         )?;
         writeln!(w, "}}")?;
         for nonterminal in self.start_symbols.iter() {
-            writeln!(w, "pub fn parse_{}(&mut self) {{", nonterminal)?;
-            writeln!(w, "self.generic_parse(0)")?;
+            writeln!(
+                w,
+                "pub fn parse_{}(&mut self) -> Result<Tree, ParseError> {{",
+                nonterminal
+            )?;
+            writeln!(
+                w,
+                "self.generic_parse({}).into_result()",
+                self.entry_states[nonterminal]
+            )?;
+            writeln!(w, "}}")?;
+            writeln!(
+                w,
+                "pub fn parse_{}_recovering(&mut self) -> Recovered {{",
+                nonterminal
+            )?;
+            writeln!(w, "self.generic_parse({})", self.entry_states[nonterminal])?;
             writeln!(w, "}}")?;
+            if options.backend == Backend::Table {
+                writeln!(
+                    w,
+                    "pub fn parse_{}_repairing(&mut self) -> Recovered {{",
+                    nonterminal
+                )?;
+                writeln!(
+                    w,
+                    "runtime::parse_repairing(&TABLES, PRODUCTIONS, REPAIR_COSTS, &self.lexer, {}, self.input)",
+                    self.entry_states[nonterminal]
+                )?;
+                writeln!(w, "}}")?;
+                writeln!(
+                    w,
+                    "pub fn push_{}() -> runtime::PushParser<'static, impl runtime::Tables> {{",
+                    nonterminal
+                )?;
+                writeln!(
+                    w,
+                    "runtime::PushParser::new(TABLES, PRODUCTIONS, Lexer::new(PATTERNS).unwrap(), {})",
+                    self.entry_states[nonterminal]
+                )?;
+                writeln!(w, "}}")?;
+            }
         }
         writeln!(
             w,
-            "    fn generic_parse(&mut self, starting_state: usize) {{"
+            "pub fn expected_terminals(stack: &[usize]) -> Vec<usize> {{"
         )?;
-        writeln!(w, "    self.position = 0;")?;
-        writeln!(w, "    loop {{")?;
-        writeln!(w, "    ")?;
+        let eof = self.terminals.len();
+        match options.backend {
+            Backend::Table => writeln!(
+                w,
+                "runtime::expected_terminals(&TABLES, PRODUCTIONS, {}, stack)",
+                eof
+            )?,
+            Backend::Direct => writeln!(
+                w,
+                "(0..={}).filter(|terminal| EXPECTED[*stack.last().unwrap()](&mut stack.to_vec(), *terminal)).collect()",
+                eof
+            )?,
+        }
         writeln!(w, "}}")?;
+        writeln!(
+            w,
+            "pub fn expected_nonterminals(stack: &[usize]) -> Vec<usize> {{"
+        )?;
+        match options.backend {
+            Backend::Table => writeln!(
+                w,
+                "runtime::expected_nonterminals(&TABLES, PRODUCTIONS, {}, stack)",
+                eof
+            )?,
+            Backend::Direct => {
+                // the states that would shift each terminal, after reducing
+                writeln!(w, "let shifting = (0..{}).filter_map(|terminal| {{", eof)?;
+                writeln!(w, "let mut stack = stack.to_vec();")?;
+                writeln!(w, "let state = *stack.last().unwrap();")?;
+                writeln!(
+                    w,
+                    "if EXPECTED[state](&mut stack, terminal) {{ stack.last().cloned() }} else {{ None }}"
+                )?;
+                writeln!(w, "}}).collect::<Vec<_>>();")?;
+                writeln!(
+                    w,
+                    "(0..{}).filter(|nonterminal| shifting.iter().any(|state| has_goto(*state, *nonterminal))).collect()",
+                    self.nonterminals.len()
+                )?;
+            }
+        }
+        writeln!(w, "}}")?;
+        writeln!(
+            w,
+            "    fn generic_parse(&mut self, starting_state: usize) -> Recovered {{"
+        )?;
+        match options.backend {
+            Backend::Table => writeln!(
+                w,
+                "    runtime::parse_recovering(&TABLES, PRODUCTIONS, &self.lexer, starting_state, self.input)"
+            )?,
+            Backend::Direct => {
+                writeln!(
+                    w,
+                    "    let stack = match runtime::start(&self.lexer, self.input, starting_state) {{"
+                )?;
+                writeln!(w, "        Ok(stack) => stack,")?;
+                writeln!(w, "        Err(recovered) => return recovered,")?;
+                writeln!(w, "    }};")?;
+                writeln!(
+                    w,
+                    "    stack.run(|stack| STATES[stack.state()](stack), |stack, error| stack.recover(error, shift_error, accepts))"
+                )?;
+            }
+        }
         writeln!(w, "}}")?;
         writeln!(w, "}}")?;
         Ok(())
     }
 
-    fn table_iter(
-        &self,
-    ) -> impl Iterator<Item = (usize, &(HashMap<Symbol, Action>, HashMap<Symbol, usize>))> + '_
-    {
+    /// The (nonterminal number, length) of every production, as the runtime
+    /// wants them.
+    pub(crate) fn production_shapes(&self) -> Vec<(usize, usize)> {
+        self.productions
+            .iter()
+            .map(|(lhs, symbols)| {
+                let lhs = self.nonterminals.iter().position(|nt| nt == lhs).unwrap();
+                (lhs, symbols.len())
+            })
+            .collect()
+    }
+
+    /// Lays out the ACTION and GOTO tables as a table with one row per state,
+    /// with each state's default reduction in the last column. The `error`
+    /// column is only there if some production uses it.
+    pub(crate) fn pretty_table(&self) -> Table {
+        let mut terminals = self.terminal_columns();
+        let uses_error = self
+            .productions
+            .iter()
+            .any(|(_, symbols)| symbols.contains(&Symbol::error()));
+        if uses_error {
+            terminals.push(Symbol::error());
+        }
+
+        let mut table = Table::new();
+        let mut row = row!["table"];
+        row.extend(terminals.iter().map(|s| s.name()));
+        row.extend(self.nonterminals.iter());
+        row.add_cell(cell!("default"));
+        table.add_row(row);
+        for (i, (action, goto)) in self.table_iter() {
+            let mut row = row![i];
+            row.extend(terminals.iter().map(|term| {
+                if let Some(action) = action.get(term) {
+                    action.to_string()
+                } else {
+                    "".to_owned()
+                }
+            }));
+            row.extend(self.nonterminals.iter().map(|nonterm| {
+                if let Some(goto) = goto.get(&Symbol::NT(nonterm.to_owned())) {
+                    goto.to_string()
+                } else {
+                    "".to_owned()
+                }
+            }));
+            let default = self.default_reductions[i].map(Action::Reduce);
+            row.add_cell(cell!(default.map_or_else(String::new, |d| d.to_string())));
+            table.add_row(row);
+        }
+        table
+    }
+
+    pub(crate) fn table_iter(&self) -> impl Iterator<Item = (usize, &TableRow)> + '_ {
         self.table.0.iter().enumerate()
     }
 
-    pub fn interpret(&self, _: impl AsRef<str>, input: impl AsRef<str>) {
-        use regex::*;
+    /// The action to take in a state on a lookahead, falling back on the
+    /// state's default reduction.
+    pub(crate) fn action(&self, state: usize, lookahead: &Symbol) -> Option<Action> {
+        let (actions, _) = &self.table.0[state];
+        actions
+            .get(lookahead)
+            .cloned()
+            .or_else(|| self.default_reductions[state].map(Action::Reduce))
+    }
 
-        let input = input.as_ref();
-        println!("INTERPRETING {:?}", input);
-        println!("{:?}", self.start_symbols);
-        let regexes = self
-            .terminals
+    /// The terminals whose ACTION a parse with this LR stack could take next,
+    /// after whatever reductions get to it, the end of input included.
+    pub fn expected_terminals(&self, stack: &[usize]) -> Vec<Symbol> {
+        self.terminal_columns()
+            .into_iter()
+            .filter(|terminal| self.shifting_state(stack, terminal).is_some())
+            .collect()
+    }
+
+    /// The nonterminals that could start next after this LR stack: the ones
+    /// the states that would shift an expected terminal have a GOTO on.
+    pub fn expected_nonterminals(&self, stack: &[usize]) -> Vec<Id> {
+        let mut terminals = self.expected_terminals(stack);
+        terminals.retain(|terminal| *terminal != Symbol::EOF);
+        let shifting = terminals
             .iter()
-            .map(|(k, v)| (*k, Regex::new(&format!("^{}", v)).unwrap()))
+            .filter_map(|terminal| self.shifting_state(stack, terminal))
             .collect::<Vec<_>>();
+        self.nonterminals
+            .iter()
+            .filter(|nonterminal| {
+                let symbol = Symbol::NT(**nonterminal);
+                shifting
+                    .iter()
+                    .any(|state| self.table.0[*state].1.contains_key(&symbol))
+            })
+            .cloned()
+            .collect()
+    }
 
-        let mut state = 0;
-        let mut consumed = 0;
-        let mut stack = Vec::new();
-
-        // take 10 steps
-        for _ in 0..10 {
-            let mut longest_match = "";
-            let mut matched_id = Id::from("");
-            for (id, regex) in regexes.iter() {
-                // println!(" {} {:?}", regex.as_str(), regex.find(&input[consumed..]).map(|s| s.as_str()));
-                if let Some(m) = regex.find(&input[consumed..]) {
-                    let matched_str = m.as_str();
-                    if matched_str.len() > longest_match.len() {
-                        longest_match = matched_str;
-                        matched_id = *id;
-                    }
+    /// Makes the reductions a lookahead calls for on a copy of the stack,
+    /// returning the state that then shifts or accepts it, or None if it's
+    /// an error.
+    fn shifting_state(&self, stack: &[usize], lookahead: &Symbol) -> Option<usize> {
+        let mut stack = stack.to_vec();
+        loop {
+            let state = *stack.last().unwrap();
+            match self.action(state, lookahead)? {
+                Action::Shift(_) | Action::Accept => return Some(state),
+                Action::Reduce(n) => {
+                    let (lhs, symbols) = &self.productions[n];
+                    stack.truncate(stack.len() - symbols.len());
+                    let (_, gotos) = &self.table.0[*stack.last().unwrap()];
+                    stack.push(gotos[&Symbol::NT(*lhs)]);
                 }
             }
-            if longest_match.len() > 0 {
-                consumed += longest_match.len();
-                println!("Longest match: {}, id => {}", longest_match, matched_id);
-                let (actions, gotos) = &self.table.0[state];
-                println!("Row [{}]: {:?}", state, actions);
-                let action = &actions[&Symbol::T(matched_id)];
-                println!("action: {:?}", action);
-                match action {
-                    Action::Shift(new_state) => {
-                        stack.push(longest_match);
-                        state = *new_state;
-                    }
-                    _ => (),
+        }
+    }
+
+    /// The state the parser starts in when parsing the given start symbol, or
+    /// None if it isn't one.
+    pub fn start_state(&self, start_symbol: &str) -> Option<usize> {
+        self.entry_states.get(&Id::from(start_symbol)).cloned()
+    }
+
+    /// Parses the input from the start symbol straight off the parse table,
+    /// stopping at the first syntax error.
+    pub fn interpret(
+        &self,
+        start_symbol: impl AsRef<str>,
+        input: impl AsRef<str>,
+    ) -> Result<Tree, ParseError> {
+        self.interpret_recovering(start_symbol, input).into_result()
+    }
+
+    /// Interprets the input like `interpret`, recovering from syntax errors in
+    /// states that can shift the `error` terminal.
+    pub fn interpret_recovering(
+        &self,
+        start_symbol: impl AsRef<str>,
+        input: impl AsRef<str>,
+    ) -> Recovered {
+        let input = input.as_ref();
+        let lexer = self.lexer();
+        let columns = self.table_columns();
+
+        let start_state = self
+            .start_state(start_symbol.as_ref())
+            .expect("not a start symbol");
+        let stack = match runtime::start(&lexer, input, start_state) {
+            Ok(stack) => stack,
+            Err(recovered) => return recovered,
+        };
+
+        let step = |stack: &mut Stack| {
+            let state = stack.state();
+            let (actions, _) = &self.table.0[state];
+            // a consistent state reduces without lexing the lookahead
+            let action = match self.default_reductions[state] {
+                Some(n) if actions.is_empty() => Some(Action::Reduce(n)),
+                _ => self.action(state, &columns[stack.lookahead()?]),
+            };
+            let action = match action {
+                Some(action) => action,
+                None => {
+                    let expected = (0..=lexer.eof())
+                        .filter(|column| actions.contains_key(&columns[*column]))
+                        .collect::<Vec<_>>();
+                    return Err(stack.error(&expected));
                 }
+            };
+            match action {
+                Action::Shift(new_state) => stack.shift(new_state)?,
+                Action::Reduce(n) => {
+                    let (lhs, symbols) = &self.productions[n];
+                    let uncovered = stack.reduce(n, symbols.len());
+                    let (_, gotos) = &self.table.0[uncovered];
+                    stack.goto(gotos[&Symbol::NT(*lhs)]);
+                }
+                Action::Accept => return Ok(true),
             }
-        }
+            Ok(false)
+        };
+        let shift_error = |state: usize| match self.table.0[state].0.get(&Symbol::error()) {
+            Some(Action::Shift(next)) => Some(*next),
+            _ => None,
+        };
+        let accepts =
+            |state: usize, terminal: usize| self.action(state, &columns[terminal]).is_some();
+        stack.run(step, |stack, error| {
+            stack.recover(error, shift_error, accepts)
+        })
+    }
+
+    /// Parses the input, repairing syntax errors by inserting and deleting
+    /// tokens at the least cost.
+    pub fn parse_repairing(
+        &self,
+        start_symbol: impl AsRef<str>,
+        input: impl AsRef<str>,
+    ) -> Recovered {
+        let lexer = self.lexer();
+        let start_state = self
+            .start_state(start_symbol.as_ref())
+            .expect("not a start symbol");
+        let tables = self
+            .dense_tables
+            .get_or_init(|| self.pack_tables(TableLayout::Dense));
+        runtime::parse_repairing(
+            tables,
+            &self.production_shapes(),
+            &self.repair_costs,
+            &lexer,
+            start_state,
+            input.as_ref(),
+        )
+    }
+
+    /// Starts a parse that's fed its input a piece at a time.
+    pub fn push_parser(
+        &self,
+        start_symbol: impl AsRef<str>,
+    ) -> PushParser<'static, impl runtime::Tables> {
+        let lexer = self.lexer();
+        let start_state = self
+            .start_state(start_symbol.as_ref())
+            .expect("not a start symbol");
+        PushParser::new(
+            self.pack_tables(TableLayout::Dense),
+            self.production_shapes(),
+            lexer,
+            start_state,
+        )
+    }
+
+    /// Describes a syntax error from this parser, with a snippet of the input.
+    pub fn render_error(&self, input: &str, error: &ParseError) -> String {
+        self.render_error_with(input, error, &ErrorMessages::default())
+    }
+
+    /// Describes a syntax error like `render_error`, using the custom message
+    /// for its state if there is one.
+    pub fn render_error_with(
+        &self,
+        input: &str,
+        error: &ParseError,
+        messages: &ErrorMessages,
+    ) -> String {
+        let names = self
+            .terminal_columns()
+            .iter()
+            .map(Symbol::name)
+            .collect::<Vec<_>>();
+        let names = names.iter().map(|name| name.as_str()).collect::<Vec<_>>();
+        error.render_with_messages(input, &names, &messages.as_slice())
     }
 }
 
-#[derive(Debug)]
-pub struct ParseTable(pub(crate) Vec<(HashMap<Symbol, Action>, HashMap<Symbol, usize>)>);
+/// A single state's row: the ACTION entries keyed by terminal and the GOTO
+/// entries keyed by nonterminal.
+pub(crate) type TableRow = (HashMap<Symbol, Action>, HashMap<Symbol, usize>);
 
 #[derive(Debug)]
+pub struct ParseTable(pub(crate) Vec<TableRow>);
+
+/// A table cell that more than one action competed for.
+///
+/// The first action in `actions` is the one that ended up in the table.
+#[derive(Clone, Debug)]
+pub struct Conflict {
+    pub state: usize,
+    pub symbol: Symbol,
+    pub actions: Vec<Action>,
+}
+
+impl Conflict {
+    /// Whether this is a shift/reduce conflict (as opposed to reduce/reduce).
+    pub fn is_shift_reduce(&self) -> bool {
+        self.actions
+            .iter()
+            .any(|action| matches!(action, Action::Shift(_)))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Action {
     Shift(usize),
     Reduce(usize),
@@ -196,19 +561,117 @@ pub enum Action {
 }
 
 impl Action {
-    pub fn shortname(&self) -> String {
+    /// Encodes the action as a single integer for generated tables: shifts
+    /// are the state number, reductions come after the states, and -1 accepts.
+    pub fn code(&self, n_states: usize) -> i64 {
         match self {
-            Action::Shift(n) => format!("s{}", n),
-            Action::Reduce(n) => format!("r{}", n),
-            Action::Accept => "acc".to_owned(),
+            Action::Shift(n) => *n as i64,
+            Action::Reduce(n) => (n_states + *n) as i64,
+            Action::Accept => -1,
         }
     }
 
-    pub fn code(&self, n_states: usize) -> i8 {
+    /// Decodes an action encoded by `code`, or None for an empty cell (any
+    /// other negative number).
+    pub fn decode(code: i64, n_states: usize) -> Option<Action> {
+        match code {
+            -1 => Some(Action::Accept),
+            code if code < 0 => None,
+            code if (code as usize) < n_states => Some(Action::Shift(code as usize)),
+            code => Some(Action::Reduce(code as usize - n_states)),
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Action::Shift(n) => *n as i8,
-            Action::Reduce(n) => (n_states as i8) + (*n as i8),
-            Action::Accept => -1,
+            Action::Shift(n) => write!(f, "s{}", n),
+            Action::Reduce(n) => write!(f, "r{}", n),
+            Action::Accept => write!(f, "acc"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::Lexer;
+    use crate::{BuildOptions, DefaultReductions, Grammar, Symbol};
+
+    fn make_parens() -> Grammar {
+        make_grammar! {
+            start_symbols: [E],
+            terminals: {
+                Add: r"\+",
+                N: r"\d+",
+                LP: r"\(",
+                RP: r"\)",
+            },
+            productions: {
+                E: [ [E, Add, T], [T] ],
+                T: [ [LP, E, RP], [N] ],
+            }
+        }
+    }
+
+    #[test]
+    fn test_expected() {
+        let names = |symbols: Vec<Symbol>| {
+            symbols
+                .iter()
+                .map(|symbol| symbol.name().as_str().to_owned())
+                .collect::<Vec<_>>()
+        };
+        for mode in &[DefaultReductions::None, DefaultReductions::All] {
+            let options = BuildOptions {
+                default_reductions: *mode,
+                ..BuildOptions::default()
+            };
+            let parser = make_parens().build_with(&options).unwrap();
+            let lexer = Lexer::new(&[r"\+", r"\d+", r"\(", r"\)"]).unwrap();
+            for (input, terminals, nonterminals) in &[
+                ("", vec!["N", "LP"], vec!["E", "T"]),
+                ("(", vec!["N", "LP"], vec!["E", "T"]),
+                ("(1", vec!["Add", "RP"], vec![]),
+                ("(1+", vec!["N", "LP"], vec!["T"]),
+                ("(1+2)", vec!["Add", "$"], vec![]),
+                ("(1x", vec![], vec![]),
+            ] {
+                // what can follow the text, the token held back included
+                let mut push = parser.push_parser("E");
+                push.push_str(input).unwrap();
+                let expected = push
+                    .expected_terminals()
+                    .iter()
+                    .map(|terminal| parser.terminal_columns()[*terminal].clone())
+                    .collect();
+                assert_eq!(names(expected), *terminals, "{:?}", input);
+                let expected = push
+                    .expected_nonterminals()
+                    .iter()
+                    .map(|nonterminal| parser.nonterminals[*nonterminal].as_str())
+                    .collect::<Vec<_>>();
+                assert_eq!(expected, *nonterminals, "{:?}", input);
+
+                // the parser agrees, given a stack with every token shifted
+                let mut push = parser.push_parser("E");
+                let mut position = 0;
+                while position < input.len() {
+                    let (terminal, end) = match lexer.next(input, position) {
+                        Some(token) => token,
+                        None => break,
+                    };
+                    push.push_token(terminal, &input[position..end]).unwrap();
+                    position = end;
+                }
+                if position == input.len() {
+                    let stack = push.states();
+                    assert_eq!(names(parser.expected_terminals(stack)), *terminals);
+                    let expected = parser.expected_nonterminals(stack);
+                    let expected = expected.iter().map(|nonterminal| nonterminal.as_str());
+                    assert_eq!(expected.collect::<Vec<_>>(), *nonterminals);
+                }
+            }
         }
     }
 }
@@ -0,0 +1,460 @@
+use std::io::{self, Write};
+
+use symbol::Symbol as Id;
+
+use crate::grammar::Symbol;
+use crate::parser::Action;
+use crate::Parser;
+
+impl Parser {
+    /// Writes a self-contained HTML page describing the grammar and its automaton.
+    ///
+    /// The page lists the numbered productions, the nullable/FIRST/FOLLOW
+    /// table, conflicts (linking to the states they occur in), the parse
+    /// table, and every state with its items and actions. It also embeds the
+    /// tables as JSON together with a small script that steps through a parse
+    /// of some input, one action at a time.
+    pub fn write_report(&self, mut w: impl Write) -> io::Result<()> {
+        let mut terminals: Vec<_> = self.terminals.keys().map(|t| Symbol::T(*t)).collect();
+        terminals.push(Symbol::EOF);
+        let nonterminals = &self.nonterminals;
+
+        writeln!(w, "<!DOCTYPE html>")?;
+        writeln!(w, "<html>")?;
+        writeln!(w, "<head>")?;
+        writeln!(w, "<meta charset=\"utf-8\">")?;
+        writeln!(w, "<title>pgen report</title>")?;
+        writeln!(w, "<style>{}</style>", STYLE)?;
+        writeln!(w, "</head>")?;
+        writeln!(w, "<body>")?;
+        writeln!(w, "<h1>pgen report</h1>")?;
+        writeln!(
+            w,
+            "<p>{} terminals, {} nonterminals, {} productions, {} states, {} conflicts</p>",
+            self.terminals.len(),
+            nonterminals.len(),
+            self.productions.len(),
+            self.states.len(),
+            self.conflicts.len()
+        )?;
+
+        // terminals
+        writeln!(w, "<h2 id=\"terminals\">Terminals</h2>")?;
+        writeln!(w, "<table>")?;
+        writeln!(w, "<tr><th>Name</th><th>Pattern</th></tr>")?;
+        for (name, regex) in self.terminals.iter() {
+            writeln!(
+                w,
+                "<tr><td>{}</td><td><code>{}</code></td></tr>",
+                escape(name),
+                escape(regex)
+            )?;
+        }
+        writeln!(w, "</table>")?;
+
+        // productions
+        writeln!(w, "<h2 id=\"productions\">Productions</h2>")?;
+        writeln!(w, "<ol start=\"0\">")?;
+        for (lhs, symbols) in self.productions.iter() {
+            let rhs = symbols
+                .iter()
+                .map(|symbol| symbol.name().as_str())
+                .collect::<Vec<_>>()
+                .join(" ");
+            writeln!(
+                w,
+                "<li><code>{} -&gt; {}</code></li>",
+                escape(lhs),
+                escape(&rhs)
+            )?;
+        }
+        writeln!(w, "</ol>")?;
+
+        // analysis
+        writeln!(w, "<h2 id=\"analysis\">Nullable, FIRST and FOLLOW</h2>")?;
+        writeln!(w, "<table>")?;
+        writeln!(
+            w,
+            "<tr><th>Nonterminal</th><th>Nullable</th><th>FIRST</th><th>FOLLOW</th></tr>"
+        )?;
+        for nonterminal in nonterminals.iter() {
+            let analysis = &self.analysis;
+            let nullable = analysis.is_nullable(*nonterminal);
+            let first = analysis
+                .first_set(&Symbol::NT(*nonterminal))
+                .into_iter()
+                .flatten()
+                .filter(|symbol| **symbol != Symbol::Epsilon);
+            let follow = analysis.follow_set(*nonterminal).into_iter().flatten();
+            writeln!(
+                w,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape(nonterminal),
+                if nullable { "yes" } else { "no" },
+                symbol_list(first),
+                symbol_list(follow)
+            )?;
+        }
+        writeln!(w, "</table>")?;
+
+        // conflicts
+        writeln!(w, "<h2 id=\"conflicts\">Conflicts</h2>")?;
+        if self.conflicts.is_empty() {
+            writeln!(w, "<p>None.</p>")?;
+        } else {
+            writeln!(w, "<ul>")?;
+            for conflict in self.conflicts.iter() {
+                let actions = conflict
+                    .actions
+                    .iter()
+                    .map(|action| action.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                writeln!(
+                    w,
+                    "<li class=\"conflict\"><a href=\"#state-{0}\">State {0}</a> on <code>{1}</code>: {2} ({3})</li>",
+                    conflict.state,
+                    escape(&conflict.symbol.name()),
+                    actions,
+                    if conflict.is_shift_reduce() {
+                        "shift/reduce"
+                    } else {
+                        "reduce/reduce"
+                    }
+                )?;
+            }
+            writeln!(w, "</ul>")?;
+        }
+
+        // parse table
+        writeln!(w, "<h2 id=\"table\">Parse table</h2>")?;
+        self.pretty_table().print_html(&mut w)?;
+
+        // states
+        writeln!(w, "<h2 id=\"states\">States</h2>")?;
+        for (i, (action, goto)) in self.table_iter() {
+            let conflicted = self.conflicts.iter().any(|conflict| conflict.state == i);
+            writeln!(
+                w,
+                "<section id=\"state-{}\" class=\"state{}\">",
+                i,
+                if conflicted { " conflicted" } else { "" }
+            )?;
+            writeln!(w, "<h3>State {}</h3>", i)?;
+            writeln!(w, "<ul class=\"items\">")?;
+            for item in self.states[i].iter() {
+                writeln!(w, "<li><code>{}</code></li>", escape(&item.to_string()))?;
+            }
+            writeln!(w, "</ul>")?;
+            writeln!(w, "<table>")?;
+            for terminal in terminals.iter() {
+                if let Some(action) = action.get(terminal) {
+                    writeln!(
+                        w,
+                        "<tr><td>{}</td><td>{}</td></tr>",
+                        escape(&terminal.name()),
+                        action_link(&action.to_string())
+                    )?;
+                }
+            }
+            if let Some(n) = self.default_reductions[i] {
+                writeln!(
+                    w,
+                    "<tr><td><em>default</em></td><td>{}</td></tr>",
+                    action_link(&Action::Reduce(n).to_string())
+                )?;
+            }
+            for nonterminal in nonterminals.iter() {
+                if let Some(n) = goto.get(&Symbol::NT(*nonterminal)) {
+                    writeln!(
+                        w,
+                        "<tr><td>{}</td><td><a href=\"#state-{1}\">goto {1}</a></td></tr>",
+                        escape(nonterminal),
+                        n
+                    )?;
+                }
+            }
+            writeln!(w, "</table>")?;
+            writeln!(w, "</section>")?;
+        }
+
+        // interactive stepper
+        writeln!(w, "<h2 id=\"stepper\">Try it</h2>")?;
+        writeln!(w, "<p>")?;
+        writeln!(w, "<select id=\"start\">")?;
+        for start_symbol in self.start_symbols.iter() {
+            writeln!(w, "<option>{}</option>", escape(start_symbol))?;
+        }
+        writeln!(w, "</select>")?;
+        writeln!(w, "<input id=\"input\" type=\"text\" size=\"40\">")?;
+        writeln!(w, "<button onclick=\"pgenReset()\">Reset</button>")?;
+        writeln!(w, "<button onclick=\"pgenStep()\">Step</button>")?;
+        writeln!(w, "<button onclick=\"pgenRun()\">Run</button>")?;
+        writeln!(w, "</p>")?;
+        writeln!(w, "<p id=\"status\"></p>")?;
+        writeln!(w, "<table id=\"trace\"></table>")?;
+
+        writeln!(w, "<script>")?;
+        write!(w, "const PGEN = ")?;
+        self.write_report_data(&mut w, &terminals, nonterminals)?;
+        writeln!(w, ";")?;
+        writeln!(w, "{}", SCRIPT)?;
+        writeln!(w, "</script>")?;
+
+        writeln!(w, "</body>")?;
+        writeln!(w, "</html>")?;
+        Ok(())
+    }
+
+    /// Writes the tables needed by the stepper script as a JSON object.
+    fn write_report_data(
+        &self,
+        mut w: impl Write,
+        terminals: &[Symbol],
+        nonterminals: &[Id],
+    ) -> io::Result<()> {
+        writeln!(w, "{{")?;
+
+        let patterns = self
+            .terminals
+            .iter()
+            .map(|(name, regex)| format!("[{}, {}]", json(name), json(regex)))
+            .collect::<Vec<_>>();
+        writeln!(w, "  \"terminals\": [{}],", patterns.join(", "))?;
+
+        let productions = self
+            .productions
+            .iter()
+            .map(|(lhs, symbols)| format!("[{}, {}]", json(lhs), symbols.len()))
+            .collect::<Vec<_>>();
+        writeln!(w, "  \"productions\": [{}],", productions.join(", "))?;
+
+        let starts = self
+            .entry_states
+            .iter()
+            .map(|(start, state)| format!("{}: {}", json(start), state))
+            .collect::<Vec<_>>();
+        writeln!(w, "  \"starts\": {{{}}},", starts.join(", "))?;
+
+        writeln!(w, "  \"actions\": [")?;
+        for (_, (action, _)) in self.table_iter() {
+            let cells = terminals
+                .iter()
+                .filter_map(|terminal| {
+                    let action = action.get(terminal)?;
+                    Some(format!(
+                        "{}: {}",
+                        json(&terminal.name()),
+                        json(&action.to_string())
+                    ))
+                })
+                .collect::<Vec<_>>();
+            writeln!(w, "    {{{}}},", cells.join(", "))?;
+        }
+        writeln!(w, "  ],")?;
+
+        let defaults = self
+            .default_reductions
+            .iter()
+            .map(|default| match default {
+                Some(n) => json(&Action::Reduce(*n).to_string()),
+                None => "null".to_owned(),
+            })
+            .collect::<Vec<_>>();
+        writeln!(w, "  \"defaults\": [{}],", defaults.join(", "))?;
+
+        writeln!(w, "  \"gotos\": [")?;
+        for (_, (_, goto)) in self.table_iter() {
+            let cells = nonterminals
+                .iter()
+                .filter_map(|nonterminal| {
+                    let n = goto.get(&Symbol::NT(*nonterminal))?;
+                    Some(format!("{}: {}", json(nonterminal), n))
+                })
+                .collect::<Vec<_>>();
+            writeln!(w, "    {{{}}},", cells.join(", "))?;
+        }
+        writeln!(w, "  ]")?;
+
+        write!(w, "}}")?;
+        Ok(())
+    }
+}
+
+fn symbol_list<'a>(symbols: impl Iterator<Item = &'a Symbol>) -> String {
+    symbols
+        .map(|symbol| format!("<code>{}</code>", escape(&symbol.name())))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn action_link(action: &str) -> String {
+    match action.strip_prefix('s') {
+        Some(n) => format!("<a href=\"#state-{}\">{}</a>", n, action),
+        None => action.to_owned(),
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Quotes a string as a JSON string literal that is also safe inside a
+/// `<script>` element.
+fn json(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out += "\\\"",
+            '\\' => out += "\\\\",
+            '\n' => out += "\\n",
+            '<' => out += "\\u003c",
+            c if (c as u32) < 0x20 => out += &format!("\\u{:04x}", c as u32),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+td, th { border: 1px solid #ccc; padding: 2px 6px; }
+.state { border-left: 4px solid #ccc; padding-left: 1em; margin-bottom: 1em; }
+.state.conflicted { border-left-color: #d33; }
+.state:target { background: #ffc; }
+.conflict { color: #d33; }
+#status.error { color: #d33; }
+";
+
+const SCRIPT: &str = r#"
+let pgenParse = null;
+const pgenRegexes = PGEN.terminals.map(([name, pattern]) => {
+  try { return [name, new RegExp(pattern, "y")]; } catch (e) { return [name, null]; }
+});
+
+function pgenStatus(message, error) {
+  const status = document.getElementById("status");
+  status.textContent = message;
+  status.className = error ? "error" : "";
+}
+
+function pgenReset() {
+  const start = document.getElementById("start").value;
+  pgenParse = {
+    input: document.getElementById("input").value,
+    pos: 0,
+    states: [PGEN.starts[start]],
+    stack: [],
+    token: null,
+    done: false,
+    steps: 0,
+  };
+  document.getElementById("trace").innerHTML =
+    "<tr><th>#</th><th>States</th><th>Symbols</th><th>Input</th><th>Action</th></tr>";
+  pgenStatus("", false);
+}
+
+function pgenLex(p) {
+  if (p.pos >= p.input.length) return { name: "$", text: "" };
+  let best = null;
+  for (const [name, re] of pgenRegexes) {
+    if (re === null) continue;
+    re.lastIndex = p.pos;
+    const m = re.exec(p.input);
+    if (m && m[0].length > 0 && (best === null || m[0].length > best.text.length)) {
+      best = { name: name, text: m[0] };
+    }
+  }
+  return best;
+}
+
+function pgenLog(p, action) {
+  const row = document.createElement("tr");
+  const cells = [p.steps, p.states.join(" "), p.stack.join(" "), p.input.slice(p.pos), action];
+  for (const cell of cells) {
+    const td = document.createElement("td");
+    td.textContent = cell;
+    row.appendChild(td);
+  }
+  document.getElementById("trace").appendChild(row);
+}
+
+function pgenStep() {
+  if (pgenParse === null) pgenReset();
+  const p = pgenParse;
+  if (p.done) return;
+  p.steps += 1;
+  if (p.token === null) p.token = pgenLex(p);
+  if (p.token === null) {
+    p.done = true;
+    pgenStatus("Error: no terminal matches at offset " + p.pos, true);
+    return;
+  }
+  const state = p.states[p.states.length - 1];
+  const action = PGEN.actions[state][p.token.name] ?? PGEN.defaults[state];
+  if (action === undefined || action === null) {
+    p.done = true;
+    pgenStatus("Error: unexpected " + p.token.name + " in state " + state, true);
+    return;
+  }
+  pgenLog(p, action);
+  if (action === "acc") {
+    p.done = true;
+    pgenStatus("Accepted", false);
+  } else if (action[0] === "s") {
+    p.stack.push(p.token.name);
+    p.states.push(Number(action.slice(1)));
+    p.pos += p.token.text.length;
+    p.token = null;
+  } else {
+    const [lhs, len] = PGEN.productions[Number(action.slice(1))];
+    p.stack.splice(p.stack.length - len, len);
+    p.states.splice(p.states.length - len, len);
+    p.stack.push(lhs);
+    p.states.push(PGEN.gotos[p.states[p.states.length - 1]][lhs]);
+  }
+}
+
+function pgenRun() {
+  pgenReset();
+  while (!pgenParse.done && pgenParse.steps < 10000) pgenStep();
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use crate::Grammar;
+
+    #[test]
+    fn test_arith_report() {
+        let grammar = make_grammar! {
+            start_symbols: [E],
+            terminals: {
+                Add: r"\+",
+                N: r"1",
+            },
+            productions: {
+                E: [ [E, Add, E], [N] ],
+            }
+        };
+        let parser = grammar.build().unwrap();
+        let mut buf = Vec::new();
+        parser.write_report(&mut buf).unwrap();
+        let output = String::from_utf8(buf).unwrap();
+
+        assert!(output.starts_with("<!DOCTYPE html>"));
+        assert!(output.contains("<li><code>E -&gt; E Add E</code></li>"));
+        assert!(output.contains("<td>E</td><td>no</td><td><code>N</code></td>"));
+        assert!(output.contains("(shift/reduce)"));
+        assert!(output.contains("\"terminals\": [[\"Add\", \"\\\\+\"], [\"N\", \"1\"]]"));
+        assert!(output.contains("\"productions\": [[\"E\", 3], [\"E\", 1]]"));
+        for i in 0..parser.states.len() {
+            assert!(output.contains(&format!("<section id=\"state-{}\"", i)));
+        }
+    }
+}
@@ -0,0 +1,1740 @@
+//! The grammar-independent half of a parser: the generated code only
+//! contains tables, and calls into this module to actually parse.
+
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+use std::ops::Range;
+
+use indexmap::IndexMap;
+use regex::Regex;
+use symbol::Symbol as Id;
+
+use crate::parser::Action;
+
+/// An integer type the tables can be stored as.
+pub trait Cell: Copy {
+    fn get(self) -> i64;
+}
+
+macro_rules! impl_cell {
+    ($($ty:ty),*) => {
+        $(impl Cell for $ty {
+            fn get(self) -> i64 {
+                self as i64
+            }
+        })*
+    };
+}
+
+impl_cell!(i8, i16, i32, i64);
+
+/// Lookups into the ACTION and GOTO tables, whatever their layout.
+///
+/// Terminals are numbered in declaration order, with the end of input coming
+/// right after the last one and the `error` terminal after that. Nonterminals
+/// are numbered in declaration order.
+pub trait Tables {
+    fn action(&self, state: usize, terminal: usize) -> Option<Action>;
+
+    fn goto(&self, state: usize, nonterminal: usize) -> Option<usize>;
+
+    /// The production a state reduces when its lookahead has no action.
+    fn default_reduction(&self, state: usize) -> Option<usize>;
+}
+
+impl<T: Tables + ?Sized> Tables for &T {
+    fn action(&self, state: usize, terminal: usize) -> Option<Action> {
+        (**self).action(state, terminal)
+    }
+
+    fn goto(&self, state: usize, nonterminal: usize) -> Option<usize> {
+        (**self).goto(state, nonterminal)
+    }
+
+    fn default_reduction(&self, state: usize) -> Option<usize> {
+        (**self).default_reduction(state)
+    }
+}
+
+/// Tables stored as one row per state, with a cell for every symbol.
+///
+/// Actions are encoded by `Action::code`, with -2 for an empty cell. Gotos
+/// are the state number, or -1 for an empty cell. Default reductions are the
+/// production number, or -1 for a state without one.
+#[derive(Clone, Copy, Debug)]
+pub struct DenseTables<'a, A, G> {
+    pub n_states: usize,
+    pub n_terminals: usize,
+    pub n_nonterminals: usize,
+    pub actions: &'a [A],
+    pub gotos: &'a [G],
+    pub defaults: &'a [A],
+}
+
+impl<'a, A: Cell, G: Cell> Tables for DenseTables<'a, A, G> {
+    fn action(&self, state: usize, terminal: usize) -> Option<Action> {
+        let code = self.actions[state * self.n_terminals + terminal].get();
+        Action::decode(code, self.n_states)
+    }
+
+    fn goto(&self, state: usize, nonterminal: usize) -> Option<usize> {
+        let goto = self.gotos[state * self.n_nonterminals + nonterminal].get();
+        if goto < 0 {
+            None
+        } else {
+            Some(goto as usize)
+        }
+    }
+
+    fn default_reduction(&self, state: usize) -> Option<usize> {
+        default_reduction(self.defaults, state)
+    }
+}
+
+/// One table packed by row displacement (a "comb vector", like Bison's
+/// `yypact`/`yytable`/`yycheck`).
+///
+/// Row `state` is stored starting at `base[state]`; the cell for column `c`
+/// is at `value[base[state] + c]`, but only if `check` at the same index says
+/// it belongs to `state`. Rows are overlapped wherever their cells don't
+/// collide, so sparse tables shrink a lot.
+#[derive(Clone, Copy, Debug)]
+pub struct Comb<'a, T> {
+    pub base: &'a [T],
+    pub check: &'a [T],
+    pub value: &'a [T],
+}
+
+impl<'a, T: Cell> Comb<'a, T> {
+    pub fn get(&self, row: usize, column: usize) -> Option<i64> {
+        let index = self.base[row].get() as usize + column;
+        match self.check.get(index) {
+            Some(check) if check.get() == row as i64 => Some(self.value[index].get()),
+            _ => None,
+        }
+    }
+}
+
+/// Tables stored as a pair of comb vectors.
+///
+/// Actions are encoded by `Action::code` and gotos are the state number;
+/// empty cells are simply absent. Default reductions are stored like
+/// `DenseTables` stores them.
+#[derive(Clone, Copy, Debug)]
+pub struct CombTables<'a, A, G> {
+    pub n_states: usize,
+    pub actions: Comb<'a, A>,
+    pub gotos: Comb<'a, G>,
+    pub defaults: &'a [A],
+}
+
+impl<'a, A: Cell, G: Cell> Tables for CombTables<'a, A, G> {
+    fn action(&self, state: usize, terminal: usize) -> Option<Action> {
+        let code = self.actions.get(state, terminal)?;
+        Action::decode(code, self.n_states)
+    }
+
+    fn goto(&self, state: usize, nonterminal: usize) -> Option<usize> {
+        self.gotos.get(state, nonterminal).map(|goto| goto as usize)
+    }
+
+    fn default_reduction(&self, state: usize) -> Option<usize> {
+        default_reduction(self.defaults, state)
+    }
+}
+
+fn default_reduction<T: Cell>(defaults: &[T], state: usize) -> Option<usize> {
+    let production = defaults[state].get();
+    if production < 0 {
+        None
+    } else {
+        Some(production as usize)
+    }
+}
+
+/// Splits input into terminals, taking the longest match at each position
+/// (and the terminal declared first among equally long ones).
+#[derive(Clone, Debug)]
+pub struct Lexer {
+    regexes: Vec<Regex>,
+}
+
+impl Lexer {
+    pub fn new(patterns: &[&str]) -> Result<Self, regex::Error> {
+        let regexes = patterns
+            .iter()
+            .map(|pattern| Regex::new(&format!("^(?:{})", pattern)))
+            .collect::<Result<_, _>>()?;
+        Ok(Lexer { regexes })
+    }
+
+    /// A lexer for a grammar's terminals and their patterns, which were
+    /// checked when the grammar was built.
+    pub(crate) fn for_terminals(terminals: &IndexMap<Id, String>) -> Self {
+        let patterns = terminals.values().map(String::as_str).collect::<Vec<_>>();
+        Lexer::new(&patterns).expect("patterns are checked by Grammar::check_patterns")
+    }
+
+    /// The terminal that the end of input is lexed as.
+    pub fn eof(&self) -> usize {
+        self.regexes.len()
+    }
+
+    /// The `error` terminal, which is never lexed.
+    pub fn error(&self) -> usize {
+        self.regexes.len() + 1
+    }
+
+    /// Lexes the token starting at `position`, returning its terminal and
+    /// where it ends, or None if no terminal matches there.
+    pub fn next(&self, input: &str, position: usize) -> Option<(usize, usize)> {
+        if position == input.len() {
+            return Some((self.eof(), position));
+        }
+        let mut longest = None;
+        for (terminal, regex) in self.regexes.iter().enumerate() {
+            if let Some(m) = regex.find(&input[position..]) {
+                if m.end() > longest.map_or(0, |(_, len)| len) {
+                    longest = Some((terminal, m.end()));
+                }
+            }
+        }
+        longest.map(|(terminal, len)| (terminal, position + len))
+    }
+}
+
+/// A parse tree, with the bytes of the input every part of it covers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Tree {
+    /// A token: its terminal, the text it matched and its span
+    Leaf(usize, String, Range<usize>),
+
+    /// A reduced production: its number, the trees of its right hand side,
+    /// and the span from the first of them to the last
+    Node(usize, Vec<Tree>, Range<usize>),
+}
+
+impl Tree {
+    /// A node for a production, spanning its children, or nothing at
+    /// `position` (where the next token starts) if it has none.
+    pub fn node(production: usize, children: Vec<Tree>, position: usize) -> Tree {
+        let span = match (children.first(), children.last()) {
+            (Some(first), Some(last)) => first.span().start..last.span().end,
+            _ => position..position,
+        };
+        Tree::Node(production, children, span)
+    }
+
+    /// The bytes of the input the tree covers.
+    pub fn span(&self) -> Range<usize> {
+        match self {
+            Tree::Leaf(_, _, span) | Tree::Node(_, _, span) => span.clone(),
+        }
+    }
+
+    /// The line and column the tree starts at.
+    pub fn line_column(&self, input: &str) -> (usize, usize) {
+        line_column(input, self.span().start)
+    }
+}
+
+/// The line and column of a byte offset into the input, counting from 1, with
+/// columns in characters.
+pub fn line_column(input: &str, offset: usize) -> (usize, usize) {
+    let before = &input[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    let column = input[line_start..offset].chars().count() + 1;
+    (line, column)
+}
+
+/// A syntax error: the token the parser couldn't go on from, and the
+/// terminals it could have gone on with.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+#[error("Syntax error at {line}:{column}")]
+pub struct ParseError {
+    /// The bytes of the offending token, empty at the end of input
+    pub span: Range<usize>,
+
+    /// The line of the start of the span, counting from 1
+    pub line: usize,
+
+    /// The column of the start of the span in characters, counting from 1
+    pub column: usize,
+
+    /// The terminal of the offending token, or None if no terminal matches
+    pub found: Option<usize>,
+
+    /// The terminals that would have been accepted instead, in order
+    pub expected: Vec<usize>,
+
+    /// The edits error repair made to get past the error, if any
+    pub repairs: Vec<Repair>,
+
+    /// The LR state the error was found in, which picks its custom message
+    pub state: Option<usize>,
+}
+
+/// An edit made to the input by error repair.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Repair {
+    /// A token of the terminal was made up, with no text
+    Insert(usize),
+
+    /// The token of the terminal at the span was skipped
+    Delete(usize, Range<usize>),
+}
+
+impl ParseError {
+    pub fn new(
+        input: &str,
+        span: Range<usize>,
+        found: Option<usize>,
+        expected: Vec<usize>,
+    ) -> Self {
+        let (line, column) = line_column(input, span.start);
+        ParseError {
+            span,
+            line,
+            column,
+            found,
+            expected,
+            repairs: Vec::new(),
+            state: None,
+        }
+    }
+
+    /// An error where no terminal matches, spanning the character there.
+    pub fn unlexable(input: &str, position: usize) -> Self {
+        let len = input[position..].chars().next().map_or(0, char::len_utf8);
+        ParseError::new(input, position..position + len, None, Vec::new())
+    }
+
+    /// Where the error is, as a byte offset.
+    pub fn position(&self) -> usize {
+        self.span.start
+    }
+
+    /// Describes the error, followed by the line it's on with a caret under
+    /// the offending token, and how it was repaired if it was.
+    ///
+    /// `terminals` has the name of every terminal in the order they're
+    /// numbered, ending with the end of input.
+    pub fn render(&self, input: &str, terminals: &[&str]) -> String {
+        self.render_with_messages(input, terminals, &[])
+    }
+
+    /// Renders the error like `render`, but describes it with the message for
+    /// its state instead, if `messages` (sorted by state) has one.
+    pub fn render_with_messages(
+        &self,
+        input: &str,
+        terminals: &[&str],
+        messages: &[(usize, &str)],
+    ) -> String {
+        let eof = terminals.len() - 1;
+        let name = |terminal: usize| match terminal == eof {
+            true => "end of input".to_owned(),
+            false => terminals[terminal].to_owned(),
+        };
+        let message = self.state.and_then(|state| {
+            let i = messages.binary_search_by_key(&state, |(state, _)| *state);
+            i.ok().map(|i| messages[i].1)
+        });
+        let headline = match (message, self.found) {
+            (Some(message), _) => message.to_owned(),
+            (None, Some(terminal)) => format!("unexpected {}", name(terminal)),
+            (None, None) => format!("unrecognized token {:?}", &input[self.span.clone()]),
+        };
+        let expected = self.expected.iter().cloned().map(name).collect::<Vec<_>>();
+        let expected = match (message, expected.as_slice()) {
+            (Some(_), _) | (None, []) => String::new(),
+            (None, [one]) => format!(", expected {}", one),
+            (None, [first, second]) => format!(", expected {} or {}", first, second),
+            (None, all) => format!(", expected one of {}", all.join(", ")),
+        };
+
+        let line_start = input[..self.span.start]
+            .rfind('\n')
+            .map_or(0, |newline| newline + 1);
+        let line_end = input[self.span.start..]
+            .find('\n')
+            .map_or(input.len(), |newline| self.span.start + newline);
+        let text = &input[line_start..line_end];
+        // a span going past the end of the line is only underlined up to it
+        let underlined = input[self.span.start..self.span.end.min(line_end)]
+            .chars()
+            .count()
+            .max(1);
+        let number = self.line.to_string();
+        let gutter = " ".repeat(number.len());
+        let repairs = self
+            .repairs
+            .iter()
+            .map(|repair| match repair {
+                Repair::Insert(terminal) => format!("inserted {}", name(*terminal)),
+                Repair::Delete(_, span) => format!("deleted `{}`", &input[span.clone()]),
+            })
+            .collect::<Vec<_>>();
+
+        let mut rendered = format!("error: {}{}\n", headline, expected);
+        rendered += &format!("{}--> {}:{}\n", gutter, self.line, self.column);
+        rendered += &format!("{} |\n", gutter);
+        rendered += &format!("{} | {}\n", number, text);
+        rendered += &format!(
+            "{} | {}{}\n",
+            gutter,
+            " ".repeat(self.column - 1),
+            "^".repeat(underlined)
+        );
+        if !repairs.is_empty() {
+            rendered += &format!("{} = repaired: {}\n", gutter, repairs.join(", "));
+        }
+        rendered
+    }
+}
+
+/// The input as a stream of tokens, with one token of lookahead.
+#[derive(Debug)]
+pub struct Tokens<'a> {
+    lexer: &'a Lexer,
+    input: &'a str,
+    position: usize,
+
+    /// The lookahead token's terminal and end, or None if it hasn't been
+    /// lexed yet
+    lookahead: Option<(usize, usize)>,
+
+    /// Tokens made up by error repair, which come before the lookahead, the
+    /// next one last
+    inserted: Vec<usize>,
+}
+
+impl<'a> Tokens<'a> {
+    /// Lexes the first token of the input.
+    pub fn new(lexer: &'a Lexer, input: &'a str) -> Result<Self, ParseError> {
+        let lookahead = lexer
+            .next(input, 0)
+            .ok_or_else(|| ParseError::unlexable(input, 0))?;
+        Ok(Tokens {
+            lexer,
+            input,
+            position: 0,
+            lookahead: Some(lookahead),
+            inserted: Vec::new(),
+        })
+    }
+
+    /// The terminal of the lookahead token, which must have been lexed.
+    pub fn peek(&self) -> usize {
+        match self.inserted.last() {
+            Some(terminal) => *terminal,
+            None => self.lexed().0,
+        }
+    }
+
+    /// Consumes the lookahead token as a leaf, and lexes the one after.
+    pub fn bump(&mut self) -> Result<Tree, ParseError> {
+        let leaf = self.take();
+        self.fill()?;
+        Ok(leaf)
+    }
+
+    /// Consumes the lookahead token as a leaf, leaving the one after unlexed
+    /// until `fill` is called.
+    pub fn take(&mut self) -> Tree {
+        if let Some(terminal) = self.inserted.pop() {
+            let span = self.position..self.position;
+            return Tree::Leaf(terminal, String::new(), span);
+        }
+        let (terminal, end) = self.lexed();
+        let span = self.position..end;
+        let text = self.input[span.clone()].to_owned();
+        self.position = end;
+        self.lookahead = None;
+        Tree::Leaf(terminal, text, span)
+    }
+
+    /// Lexes the lookahead token, unless it already has been.
+    pub fn fill(&mut self) -> Result<(), ParseError> {
+        if self.lookahead.is_none() {
+            let lookahead = self
+                .lexer
+                .next(self.input, self.position)
+                .ok_or_else(|| ParseError::unlexable(self.input, self.position))?;
+            self.lookahead = Some(lookahead);
+        }
+        Ok(())
+    }
+
+    fn lexed(&self) -> (usize, usize) {
+        self.lookahead.expect("the lookahead hasn't been lexed")
+    }
+
+    /// Consumes the lookahead token if it's the given terminal, or fails.
+    pub fn expect(&mut self, terminal: usize) -> Result<Tree, ParseError> {
+        if self.peek() == terminal {
+            self.bump()
+        } else {
+            Err(self.error(&[terminal]))
+        }
+    }
+
+    /// Where the lookahead token starts, as a byte offset.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Fails unless the whole input has been consumed.
+    pub fn end(&self) -> Result<(), ParseError> {
+        if self.peek() == self.lexer.eof() {
+            Ok(())
+        } else {
+            Err(self.error(&[self.lexer.eof()]))
+        }
+    }
+
+    /// A syntax error at the lookahead token, which isn't one of `expected`.
+    pub fn error(&self, expected: &[usize]) -> ParseError {
+        let span = match self.inserted.last() {
+            Some(_) => self.position..self.position,
+            None => self.position..self.lexed().1,
+        };
+        ParseError::new(self.input, span, Some(self.peek()), expected.to_vec())
+    }
+
+    /// Makes up a token of the terminal, with no text, to come before the
+    /// lookahead.
+    pub fn insert(&mut self, terminal: usize) {
+        self.inserted.push(terminal);
+    }
+
+    /// The terminal and span of the `i`th token from the lookahead on, or
+    /// None if the input ends or stops lexing before it.
+    fn upcoming(&self, i: usize) -> Option<(usize, Range<usize>)> {
+        let n_inserted = self.inserted.len();
+        if i < n_inserted {
+            let terminal = self.inserted[n_inserted - 1 - i];
+            return Some((terminal, self.position..self.position));
+        }
+        let (mut terminal, mut end) = self.lexed();
+        let mut start = self.position;
+        for _ in n_inserted..i {
+            if terminal == self.lexer.eof() {
+                return None;
+            }
+            start = end;
+            let next = self.lexer.next(self.input, end)?;
+            terminal = next.0;
+            end = next.1;
+        }
+        Some((terminal, start..end))
+    }
+}
+
+/// The state of an LR parse in progress: the stacks of states and trees, and
+/// the lookahead token.
+///
+/// Both LR backends parse with this; the table backend looks up what to do
+/// next in its tables, while the direct-coded backend has a function per
+/// state.
+#[derive(Debug)]
+pub struct Stack<'a> {
+    tokens: Tokens<'a>,
+    states: Vec<usize>,
+    trees: Vec<Tree>,
+    errors: Vec<ParseError>,
+
+    /// How many tokens have been shifted since recovering from an error
+    shifted: Option<usize>,
+}
+
+/// A parse that went on after syntax errors: the tree, with an `error` leaf
+/// wherever the parser recovered, unless it couldn't, and every error.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Recovered {
+    pub tree: Option<Tree>,
+    pub errors: Vec<ParseError>,
+}
+
+impl Recovered {
+    /// The tree if there were no errors at all, and otherwise the first one.
+    pub fn into_result(mut self) -> Result<Tree, ParseError> {
+        match self.tree {
+            Some(tree) if self.errors.is_empty() => Ok(tree),
+            _ => Err(self.errors.remove(0)),
+        }
+    }
+}
+
+impl<'a> Stack<'a> {
+    /// Starts a parse in `start_state`, lexing the first token.
+    pub fn new(lexer: &'a Lexer, input: &'a str, start_state: usize) -> Result<Self, ParseError> {
+        Ok(Stack {
+            tokens: Tokens::new(lexer, input)?,
+            states: vec![start_state],
+            trees: Vec::new(),
+            errors: Vec::new(),
+            shifted: None,
+        })
+    }
+
+    /// The state on top of the stack.
+    pub fn state(&self) -> usize {
+        *self.states.last().unwrap()
+    }
+
+    /// The terminal of the lookahead token, lexing it if that hasn't been
+    /// done yet.
+    ///
+    /// Tokens are only lexed once a state needs to look at them, so a state
+    /// that only reduces doesn't fail on text that doesn't lex, or wait for
+    /// text that hasn't been typed yet.
+    pub fn lookahead(&mut self) -> Result<usize, ParseError> {
+        self.tokens.fill()?;
+        Ok(self.tokens.peek())
+    }
+
+    /// Shifts the lookahead token, going to `next`.
+    pub fn shift(&mut self, next: usize) -> Result<(), ParseError> {
+        self.tokens.fill()?;
+        let leaf = self.tokens.take();
+        self.trees.push(leaf);
+        self.states.push(next);
+        self.shifted = self.shifted.map(|shifted| shifted + 1);
+        Ok(())
+    }
+
+    /// Pops the `len` symbols of a production's right hand side into a node,
+    /// returning the state uncovered, which the caller takes the goto from.
+    pub fn reduce(&mut self, production: usize, len: usize) -> usize {
+        let children = self.trees.split_off(self.trees.len() - len);
+        self.states.truncate(self.states.len() - len);
+        let position = self.tokens.position();
+        self.trees.push(Tree::node(production, children, position));
+        self.state()
+    }
+
+    /// Pushes the state a goto went to after a reduction.
+    pub fn goto(&mut self, next: usize) {
+        self.states.push(next);
+    }
+
+    /// The parse tree, once the input has been accepted.
+    pub fn accept(mut self) -> Tree {
+        self.trees.pop().unwrap()
+    }
+
+    /// A syntax error at the lookahead token, which isn't one of `expected`.
+    pub fn error(&self, expected: &[usize]) -> ParseError {
+        ParseError {
+            state: Some(self.state()),
+            ..self.tokens.error(expected)
+        }
+    }
+
+    /// Recovers from a syntax error like Yacc does, returning false if it
+    /// can't.
+    ///
+    /// States are popped until one can shift `error` (`shift_error` gives
+    /// where to), `error` is shifted, and then tokens are skipped until one
+    /// `accepts` says the new state has an action for, counting its default
+    /// reduction. Errors within three tokens of the last recovery aren't
+    /// reported, and skip a token before recovering again so that it can't
+    /// loop. Errors where the input doesn't lex can't be recovered from.
+    pub fn recover(
+        &mut self,
+        error: ParseError,
+        shift_error: impl Fn(usize) -> Option<usize>,
+        accepts: impl Fn(usize, usize) -> bool,
+    ) -> bool {
+        let unlexable = error.found.is_none();
+        let mut start = error.span.start;
+        match self.shifted {
+            Some(shifted) if shifted < 3 && !unlexable => {
+                if shifted == 0 && !self.skip() {
+                    return false;
+                }
+            }
+            _ => self.errors.push(error),
+        }
+        if unlexable {
+            return false;
+        }
+
+        let next = loop {
+            if let Some(next) = shift_error(self.state()) {
+                break next;
+            }
+            if self.states.len() == 1 {
+                return false;
+            }
+            self.states.pop();
+            if let Some(tree) = self.trees.pop() {
+                start = start.min(tree.span().start);
+            }
+        };
+        self.states.push(next);
+
+        loop {
+            let lookahead = match self.lookahead() {
+                Ok(lookahead) => lookahead,
+                Err(unlexable) => {
+                    self.errors.push(unlexable);
+                    return false;
+                }
+            };
+            if accepts(self.state(), lookahead) {
+                break;
+            }
+            if !self.skip() {
+                return false;
+            }
+        }
+        // the `error` leaf covers the trees popped and the tokens skipped
+        let span = start..self.tokens.position().max(start);
+        let leaf = Tree::Leaf(self.tokens.lexer.error(), String::new(), span);
+        self.trees.push(leaf);
+        self.shifted = Some(0);
+        true
+    }
+
+    /// Skips the lookahead token, failing at the end of input or if it
+    /// doesn't lex.
+    fn skip(&mut self) -> bool {
+        match self.lookahead() {
+            Ok(lookahead) if lookahead == self.tokens.lexer.eof() => false,
+            Ok(_) => {
+                self.tokens.take();
+                true
+            }
+            Err(error) => {
+                self.errors.push(error);
+                false
+            }
+        }
+    }
+
+    /// Repairs a syntax error by inserting and deleting tokens, like CPCT+
+    /// does, returning false if it can't.
+    ///
+    /// The search finds the cheapest edits, each costing its terminal's entry
+    /// in `costs` (or 1), after which the parse can go on to shift three
+    /// tokens of the input in a row, or accept. The edits up to the first of
+    /// those shifts are made and recorded in the error; later ones are left
+    /// for the next error.
+    pub fn repair(
+        &mut self,
+        mut error: ParseError,
+        tables: impl Tables,
+        productions: &[(usize, usize)],
+        costs: &[usize],
+    ) -> bool {
+        if error.found.is_none() {
+            self.errors.push(error);
+            return false;
+        }
+        let edits = match find_repair(&tables, productions, costs, &self.states, &self.tokens) {
+            Some(edits) => edits,
+            None => {
+                self.errors.push(error);
+                return false;
+            }
+        };
+
+        // insertions always come before deletions, so deleting first and
+        // then putting the made up tokens in front of what's left is the same
+        for edit in edits.iter() {
+            if let Edit::Delete = edit {
+                let (terminal, span) = self.tokens.upcoming(0).unwrap();
+                if let Err(unlexable) = self.tokens.bump() {
+                    self.errors.push(error);
+                    self.errors.push(unlexable);
+                    return false;
+                }
+                error.repairs.push(Repair::Delete(terminal, span));
+            }
+        }
+        for edit in edits.iter().rev() {
+            if let Edit::Insert(terminal) = edit {
+                self.tokens.insert(*terminal);
+            }
+        }
+        let inserted = edits.iter().filter_map(|edit| match edit {
+            Edit::Insert(terminal) => Some(Repair::Insert(*terminal)),
+            _ => None,
+        });
+        let deleted = std::mem::take(&mut error.repairs);
+        error.repairs = inserted.chain(deleted).collect();
+        self.errors.push(error);
+        true
+    }
+
+    /// Runs a parse to the end, taking each move with `step` (which returns
+    /// whether the input was accepted), and calling `recover` on its errors
+    /// to get going again.
+    pub fn run(
+        mut self,
+        mut step: impl FnMut(&mut Stack<'a>) -> Result<bool, ParseError>,
+        mut recover: impl FnMut(&mut Stack<'a>, ParseError) -> bool,
+    ) -> Recovered {
+        loop {
+            match step(&mut self) {
+                Ok(true) => {
+                    let errors = std::mem::take(&mut self.errors);
+                    return Recovered {
+                        tree: Some(self.accept()),
+                        errors,
+                    };
+                }
+                Ok(false) => (),
+                Err(error) => {
+                    if !recover(&mut self, error) {
+                        return Recovered {
+                            tree: None,
+                            errors: self.errors,
+                        };
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// An edit tried by error repair.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Edit {
+    Insert(usize),
+    Delete,
+    Shift,
+}
+
+/// A sequence of edits being tried by error repair, and where it gets to.
+struct Candidate {
+    cost: usize,
+    states: Vec<usize>,
+
+    /// The upcoming token the candidate is at
+    token: usize,
+
+    /// How many tokens the candidate has shifted since its last edit
+    shifted: usize,
+    accepted: bool,
+    edits: Vec<Edit>,
+}
+
+/// How many tokens have to be shifted after a repair for it to count.
+const REPAIR_SHIFTS: usize = 3;
+
+/// How many candidates error repair keeps before giving up.
+const REPAIR_CANDIDATES: usize = 10_000;
+
+/// Searches for the cheapest edits that get the parse going again, returning
+/// the ones up to the first shift.
+fn find_repair(
+    tables: &impl Tables,
+    productions: &[(usize, usize)],
+    costs: &[usize],
+    states: &[usize],
+    tokens: &Tokens,
+) -> Option<Vec<Edit>> {
+    let eof = tokens.lexer.eof();
+    let cost = |terminal: usize| costs.get(terminal).cloned().unwrap_or(1);
+    let mut candidates = vec![Candidate {
+        cost: 0,
+        states: states.to_vec(),
+        token: 0,
+        shifted: 0,
+        accepted: false,
+        edits: Vec::new(),
+    }];
+    let mut queue = BinaryHeap::new();
+    queue.push(Reverse((0, 0)));
+    let mut seen = HashSet::new();
+
+    while let Some(Reverse((_, index))) = queue.pop() {
+        // every candidate is kept for its edits, so bound how many are made
+        if candidates.len() >= REPAIR_CANDIDATES {
+            return None;
+        }
+        let candidate = &candidates[index];
+        if candidate.accepted || candidate.shifted == REPAIR_SHIFTS {
+            let first_shift = candidate.edits.iter().position(|edit| *edit == Edit::Shift);
+            let edits = &candidate.edits[..first_shift.unwrap_or(candidate.edits.len())];
+            return Some(edits.to_vec());
+        }
+        let last = candidate.edits.last().cloned();
+        let key = (
+            candidate.states.clone(),
+            candidate.token,
+            candidate.shifted,
+            last == Some(Edit::Delete),
+        );
+        if !seen.insert(key) {
+            continue;
+        }
+
+        let mut next = Vec::new();
+        if let Some((terminal, _)) = tokens.upcoming(candidate.token) {
+            let mut states = candidate.states.clone();
+            if let Some(accepted) = feed(tables, productions, &mut states, terminal) {
+                next.push((0, states, 1, candidate.shifted + 1, accepted, Edit::Shift));
+            }
+            if terminal != eof {
+                let states = candidate.states.clone();
+                next.push((cost(terminal), states, 1, 0, false, Edit::Delete));
+            }
+        }
+        // inserting right after deleting would just be another order for the
+        // same edits
+        if last != Some(Edit::Delete) {
+            for terminal in 0..eof {
+                let mut states = candidate.states.clone();
+                if feed(tables, productions, &mut states, terminal) == Some(false) {
+                    next.push((cost(terminal), states, 0, 0, false, Edit::Insert(terminal)));
+                }
+            }
+        }
+
+        for (cost, states, tokens, shifted, accepted, edit) in next {
+            let candidate = &candidates[index];
+            let mut edits = candidate.edits.clone();
+            edits.push(edit);
+            let next = Candidate {
+                cost: candidate.cost + cost,
+                states,
+                token: candidate.token + tokens,
+                shifted,
+                accepted,
+                edits,
+            };
+            queue.push(Reverse((next.cost, candidates.len())));
+            candidates.push(next);
+        }
+    }
+    None
+}
+
+/// Runs the LR automaton on the states until it shifts or accepts the
+/// terminal, returning whether it accepted, or None if it errors.
+fn feed(
+    tables: &impl Tables,
+    productions: &[(usize, usize)],
+    states: &mut Vec<usize>,
+    terminal: usize,
+) -> Option<bool> {
+    loop {
+        let state = *states.last().unwrap();
+        let action = tables
+            .action(state, terminal)
+            .or_else(|| tables.default_reduction(state).map(Action::Reduce))?;
+        match action {
+            Action::Shift(next) => {
+                states.push(next);
+                return Some(false);
+            }
+            Action::Reduce(n) => {
+                let (lhs, len) = productions[n];
+                states.truncate(states.len() - len);
+                let next = tables.goto(*states.last().unwrap(), lhs)?;
+                states.push(next);
+            }
+            Action::Accept => return Some(true),
+        }
+    }
+}
+
+/// An LR parse that's fed its input a piece at a time, for REPLs and the
+/// like: text (or tokens lexed elsewhere) is pushed in, and the parse can be
+/// asked whether what it has so far is a whole sentence.
+///
+/// A push that hits a syntax error, in the text it holds back too, changes
+/// nothing, so the parser can go on as if it never happened.
+#[derive(Debug)]
+pub struct PushParser<'a, T> {
+    tables: T,
+    productions: Cow<'a, [(usize, usize)]>,
+    lexer: Lexer,
+    input: String,
+
+    /// Where the text that hasn't been shifted yet starts
+    lexed: usize,
+    states: Vec<usize>,
+    trees: Vec<Tree>,
+}
+
+/// Whether the input pushed so far is a whole sentence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Completion {
+    /// It parses if the input ends here, though more could follow
+    Complete,
+
+    /// It needs more input to parse, or can't parse at all
+    Incomplete,
+}
+
+/// A token a push parser shifts, and the reductions it makes first.
+struct Move {
+    terminal: usize,
+    span: Range<usize>,
+    reductions: Vec<usize>,
+    accepted: bool,
+}
+
+/// A push parser's state at some point, to go back to later.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    input: String,
+    lexed: usize,
+    states: Vec<usize>,
+    trees: Vec<Tree>,
+}
+
+impl<'a, T: Tables> PushParser<'a, T> {
+    /// Starts a parse in `start_state` with no input yet.
+    pub fn new(
+        tables: T,
+        productions: impl Into<Cow<'a, [(usize, usize)]>>,
+        lexer: Lexer,
+        start_state: usize,
+    ) -> Self {
+        PushParser {
+            tables,
+            productions: productions.into(),
+            lexer,
+            input: String::new(),
+            lexed: 0,
+            states: vec![start_state],
+            trees: Vec::new(),
+        }
+    }
+
+    /// All the text pushed so far.
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// Adds text to the input, and shifts every token in it but the last,
+    /// which more text could make longer. That one still has to be able to
+    /// come next. Text that doesn't lex waits for more, in case that makes it
+    /// lex.
+    ///
+    /// Chunks can split a token, so long as its first part doesn't lex as
+    /// more than one token by itself.
+    pub fn push_str(&mut self, text: &str) -> Result<(), ParseError> {
+        let start = self.input.len();
+        self.input += text;
+        let mut states = self.states.clone();
+        match self.lex(&mut states, false) {
+            Ok(moves) => {
+                self.make(states, moves);
+                Ok(())
+            }
+            Err(error) => {
+                self.input.truncate(start);
+                Err(error)
+            }
+        }
+    }
+
+    /// Shifts a token lexed by the caller, its text added to the input after
+    /// any text pushed before it, which is lexed first.
+    pub fn push_token(&mut self, terminal: usize, text: &str) -> Result<(), ParseError> {
+        let start = self.input.len();
+        let mut states = self.states.clone();
+        let result = self.lex(&mut states, true).and_then(|mut moves| {
+            self.input += text;
+            let span = start..self.input.len();
+            moves.push(self.advance(&mut states, terminal, span)?);
+            Ok(moves)
+        });
+        match result {
+            Ok(moves) => {
+                self.make(states, moves);
+                Ok(())
+            }
+            Err(error) => {
+                self.input.truncate(start);
+                Err(error)
+            }
+        }
+    }
+
+    /// Whether the input would parse if it ended here.
+    pub fn completion(&self) -> Completion {
+        let mut states = match self.held_back() {
+            Some(states) => states,
+            None => return Completion::Incomplete,
+        };
+        match feed(
+            &self.tables,
+            &self.productions,
+            &mut states,
+            self.lexer.eof(),
+        ) {
+            Some(true) => Completion::Complete,
+            _ => Completion::Incomplete,
+        }
+    }
+
+    /// The LR stack: the start state, and then the state each of `trees`
+    /// went to. Text held back isn't on it yet.
+    pub fn states(&self) -> &[usize] {
+        &self.states
+    }
+
+    /// The trees shifted and reduced so far, from the left.
+    pub fn trees(&self) -> &[Tree] {
+        &self.trees
+    }
+
+    /// The terminals that could come next, the end of input included, after
+    /// all the text pushed so far. There are none if that doesn't lex.
+    pub fn expected_terminals(&self) -> Vec<usize> {
+        let states = match self.held_back() {
+            Some(states) => states,
+            None => return Vec::new(),
+        };
+        expected_terminals(&self.tables, &self.productions, self.lexer.eof(), &states)
+    }
+
+    /// The nonterminals that could start next, after all the text pushed so
+    /// far: the ones the states that would shift an expected terminal have a
+    /// GOTO on.
+    pub fn expected_nonterminals(&self) -> Vec<usize> {
+        let states = match self.held_back() {
+            Some(states) => states,
+            None => return Vec::new(),
+        };
+        expected_nonterminals(&self.tables, &self.productions, self.lexer.eof(), &states)
+    }
+
+    /// Ends the input, returning its tree if it parses.
+    pub fn finish(mut self) -> Result<Tree, ParseError> {
+        let mut states = self.states.clone();
+        let mut moves = self.lex(&mut states, true)?;
+        let end = self.input.len();
+        moves.push(self.advance(&mut states, self.lexer.eof(), end..end)?);
+        self.make(states, moves);
+        Ok(self.trees.pop().unwrap())
+    }
+
+    /// Saves the state of the parse, input included.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            input: self.input.clone(),
+            lexed: self.lexed,
+            states: self.states.clone(),
+            trees: self.trees.clone(),
+        }
+    }
+
+    /// Goes back to a state saved by `checkpoint`.
+    pub fn restore(&mut self, checkpoint: Checkpoint) {
+        self.input = checkpoint.input;
+        self.lexed = checkpoint.lexed;
+        self.states = checkpoint.states;
+        self.trees = checkpoint.trees;
+    }
+
+    /// The LR stack after the text held back, lexed as if the input ended
+    /// after it, or None if it doesn't lex or parse.
+    fn held_back(&self) -> Option<Vec<usize>> {
+        let mut states = self.states.clone();
+        let mut position = self.lexed;
+        while position < self.input.len() {
+            let (terminal, end) = self.lexer.next(&self.input, position)?;
+            if feed(&self.tables, &self.productions, &mut states, terminal) != Some(false) {
+                return None;
+            }
+            position = end;
+        }
+        Some(states)
+    }
+
+    /// Lexes the text not lexed yet, and runs `states` over its tokens,
+    /// holding back the last one unless the input is `ending`. A token held
+    /// back is checked, but doesn't move `states`.
+    fn lex(&self, states: &mut Vec<usize>, ending: bool) -> Result<Vec<Move>, ParseError> {
+        let mut moves = Vec::new();
+        let mut position = self.lexed;
+        while position < self.input.len() {
+            let (terminal, end) = match self.lexer.next(&self.input, position) {
+                Some(token) => token,
+                None if ending => return Err(ParseError::unlexable(&self.input, position)),
+                None => break,
+            };
+            if end == self.input.len() && !ending {
+                self.advance(&mut states.clone(), terminal, position..end)?;
+                break;
+            }
+            moves.push(self.advance(states, terminal, position..end)?);
+            position = end;
+        }
+        Ok(moves)
+    }
+
+    /// Runs `states` over the reductions the token calls for and then its
+    /// shift, or up to accepting it.
+    fn advance(
+        &self,
+        states: &mut Vec<usize>,
+        terminal: usize,
+        span: Range<usize>,
+    ) -> Result<Move, ParseError> {
+        let mut reductions = Vec::new();
+        let accepted = loop {
+            let state = *states.last().unwrap();
+            let action = self
+                .tables
+                .action(state, terminal)
+                .or_else(|| self.tables.default_reduction(state).map(Action::Reduce));
+            match action {
+                Some(Action::Shift(next)) => {
+                    states.push(next);
+                    break false;
+                }
+                Some(Action::Reduce(n)) => {
+                    let (lhs, len) = self.productions[n];
+                    states.truncate(states.len() - len);
+                    let next = self
+                        .tables
+                        .goto(*states.last().unwrap(), lhs)
+                        .expect("tables are missing a goto");
+                    states.push(next);
+                    reductions.push(n);
+                }
+                Some(Action::Accept) => break true,
+                None => {
+                    let expected = (0..=self.lexer.eof())
+                        .filter(|terminal| self.tables.action(state, *terminal).is_some())
+                        .collect();
+                    return Err(ParseError {
+                        state: Some(state),
+                        ..ParseError::new(&self.input, span, Some(terminal), expected)
+                    });
+                }
+            }
+        };
+        Ok(Move {
+            terminal,
+            span,
+            reductions,
+            accepted,
+        })
+    }
+
+    /// Builds the trees for moves that `states` was run over, and takes
+    /// `states` as the LR stack.
+    fn make(&mut self, states: Vec<usize>, moves: Vec<Move>) {
+        for token in moves {
+            for n in token.reductions {
+                let (_, len) = self.productions[n];
+                let children = self.trees.split_off(self.trees.len() - len);
+                self.trees.push(Tree::node(n, children, token.span.start));
+            }
+            self.lexed = token.span.end;
+            if !token.accepted {
+                let text = self.input[token.span.clone()].to_owned();
+                self.trees
+                    .push(Tree::Leaf(token.terminal, text, token.span));
+            }
+        }
+        self.states = states;
+    }
+}
+
+/// Starts a parse, or fails right away if the first token doesn't lex.
+pub fn start<'a>(
+    lexer: &'a Lexer,
+    input: &'a str,
+    start_state: usize,
+) -> Result<Stack<'a>, Recovered> {
+    Stack::new(lexer, input, start_state).map_err(|error| Recovered {
+        tree: None,
+        errors: vec![error],
+    })
+}
+
+/// Parses `input` from `start_state` with the usual LR loop, making a
+/// state's default reduction whenever the lookahead has no action, and
+/// stopping at the first syntax error.
+///
+/// `productions` has the (nonterminal, length) of every production's left
+/// and right hand sides, by production number.
+pub fn parse(
+    tables: impl Tables,
+    productions: &[(usize, usize)],
+    lexer: &Lexer,
+    start_state: usize,
+    input: &str,
+) -> Result<Tree, ParseError> {
+    parse_recovering(tables, productions, lexer, start_state, input).into_result()
+}
+
+/// Parses like `parse`, but recovers from syntax errors in states that can
+/// shift the `error` terminal.
+pub fn parse_recovering(
+    tables: impl Tables,
+    productions: &[(usize, usize)],
+    lexer: &Lexer,
+    start_state: usize,
+    input: &str,
+) -> Recovered {
+    let stack = match start(lexer, input, start_state) {
+        Ok(stack) => stack,
+        Err(recovered) => return recovered,
+    };
+    let shift_error = |state| match tables.action(state, lexer.error()) {
+        Some(Action::Shift(next)) => Some(next),
+        _ => None,
+    };
+    let accepts = |state, terminal| {
+        tables.action(state, terminal).is_some() || tables.default_reduction(state).is_some()
+    };
+    stack.run(
+        |stack| step(&tables, productions, stack),
+        |stack, error| stack.recover(error, shift_error, accepts),
+    )
+}
+
+/// Parses like `parse`, but repairs syntax errors by inserting and deleting
+/// tokens, with `costs` giving the cost of each terminal.
+pub fn parse_repairing(
+    tables: impl Tables,
+    productions: &[(usize, usize)],
+    costs: &[usize],
+    lexer: &Lexer,
+    start_state: usize,
+    input: &str,
+) -> Recovered {
+    let stack = match start(lexer, input, start_state) {
+        Ok(stack) => stack,
+        Err(recovered) => return recovered,
+    };
+    stack.run(
+        |stack| step(&tables, productions, stack),
+        |stack, error| stack.repair(error, &tables, productions, costs),
+    )
+}
+
+/// The terminals that could come next after an LR stack, the end of input
+/// included, with `eof` the terminal number of the end of input.
+pub fn expected_terminals(
+    tables: impl Tables,
+    productions: &[(usize, usize)],
+    eof: usize,
+    stack: &[usize],
+) -> Vec<usize> {
+    (0..=eof)
+        .filter(|terminal| feed(&tables, productions, &mut stack.to_vec(), *terminal).is_some())
+        .collect()
+}
+
+/// The nonterminals that could start next after an LR stack: the ones the
+/// states that would shift an expected terminal have a GOTO on.
+pub fn expected_nonterminals(
+    tables: impl Tables,
+    productions: &[(usize, usize)],
+    eof: usize,
+    stack: &[usize],
+) -> Vec<usize> {
+    let n_nonterminals = productions.iter().map(|(lhs, _)| lhs + 1).max();
+    let mut expected = vec![false; n_nonterminals.unwrap_or(0)];
+    for terminal in 0..eof {
+        let mut states = stack.to_vec();
+        if feed(&tables, productions, &mut states, terminal) == Some(false) {
+            let shifting = states[states.len() - 2];
+            for (nonterminal, expected) in expected.iter_mut().enumerate() {
+                *expected |= tables.goto(shifting, nonterminal).is_some();
+            }
+        }
+    }
+    (0..expected.len()).filter(|n| expected[*n]).collect()
+}
+
+/// The default reduction of a state with no action keyed by a terminal,
+/// which it makes without lexing the lookahead.
+fn consistent_reduction(tables: &impl Tables, lexer: &Lexer, state: usize) -> Option<usize> {
+    let n = tables.default_reduction(state)?;
+    let keyed = (0..=lexer.error()).any(|terminal| tables.action(state, terminal).is_some());
+    if keyed {
+        None
+    } else {
+        Some(n)
+    }
+}
+
+/// Makes one move of the usual LR loop, returning whether it accepted.
+fn step(
+    tables: &impl Tables,
+    productions: &[(usize, usize)],
+    stack: &mut Stack,
+) -> Result<bool, ParseError> {
+    let state = stack.state();
+    let action = match consistent_reduction(tables, stack.tokens.lexer, state) {
+        Some(n) => Some(Action::Reduce(n)),
+        None => tables
+            .action(state, stack.lookahead()?)
+            .or_else(|| tables.default_reduction(state).map(Action::Reduce)),
+    };
+    match action {
+        Some(Action::Shift(next)) => stack.shift(next)?,
+        Some(Action::Reduce(n)) => {
+            let (lhs, len) = productions[n];
+            let uncovered = stack.reduce(n, len);
+            let next = tables
+                .goto(uncovered, lhs)
+                .expect("tables are missing a goto");
+            stack.goto(next);
+        }
+        Some(Action::Accept) => return Ok(true),
+        None => {
+            let expected = (0..=stack.tokens.lexer.eof())
+                .filter(|terminal| tables.action(state, *terminal).is_some())
+                .collect::<Vec<_>>();
+            return Err(stack.error(&expected));
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use super::{Completion, ParseError, Repair, Tree};
+    use crate::runtime;
+    use crate::{BuildOptions, DefaultReductions, Grammar, GrammarError, TableLayout};
+
+    #[test]
+    fn test_error_messages() {
+        let parser = crate::this::pgen_grammar().build().unwrap();
+        let error = parser.interpret("E", "1+").unwrap_err();
+        let expected = ParseError {
+            state: Some(5),
+            ..ParseError::new("1+", 2..2, Some(4), vec![2, 3])
+        };
+        assert_eq!(error, expected);
+        assert_eq!(
+            parser.render_error("1+", &error),
+            "error: unexpected end of input, expected N0 or N1\n \
+             --> 1:3\n  \
+             |\n\
+             1 | 1+\n  \
+             |   ^\n"
+        );
+
+        let error = parser.interpret("E", "1*+0").unwrap_err();
+        assert_eq!((error.line, error.column, error.found), (1, 3, Some(0)));
+
+        let error = parser.interpret("E", "0+x").unwrap_err();
+        assert_eq!((error.span.clone(), error.found), (2..3, None));
+        assert!(parser
+            .render_error("0+x", &error)
+            .starts_with("error: unrecognized token \"x\"\n"));
+
+        // lines and columns count characters, from 1
+        let input = "first\nsécond + \nthird";
+        let error = ParseError::new(input, 14..15, Some(0), vec![0, 1, 2]);
+        assert_eq!((error.line, error.column), (2, 8));
+        assert_eq!(
+            error.render(input, &["Add", "Mul", "N", "$"]),
+            "error: unexpected Add, expected one of Add, Mul, N\n \
+             --> 2:8\n  \
+             |\n\
+             2 | sécond + \n  \
+             |        ^\n"
+        );
+    }
+
+    #[test]
+    fn test_error_recovery() {
+        let grammar = crate::this::stmts_grammar();
+        for mode in &[DefaultReductions::None, DefaultReductions::All] {
+            let options = BuildOptions {
+                default_reductions: *mode,
+                ..BuildOptions::default()
+            };
+            let parser = grammar.clone().build_with(&options).unwrap();
+            let lexer = parser.lexer();
+            let productions = parser.production_shapes();
+            let dense = parser.pack_tables(TableLayout::Dense);
+            let comb = parser.pack_tables(TableLayout::Comb);
+            let start = parser.start_state("Stmts").unwrap();
+
+            let input = "1+1;1++1;1;+1";
+            let recovered = parser.interpret_recovering("Stmts", input);
+            assert_eq!(recovered.errors[0].position(), 6);
+            assert_eq!(recovered.errors[0].expected, vec![2]);
+            assert!(recovered.tree.is_some());
+
+            // default reductions don't look at the lookahead, so they make
+            // recovery coarser, like in Bison
+            if *mode == DefaultReductions::None {
+                let positions = recovered
+                    .errors
+                    .iter()
+                    .map(ParseError::position)
+                    .collect::<Vec<_>>();
+                assert_eq!(positions, vec![6, 11]);
+
+                // the statements with errors become `error` leaves, covering
+                // the tokens recovery threw away
+                let error = |span: Range<usize>| {
+                    let leaf = Tree::Leaf(lexer.error(), String::new(), span.clone());
+                    Tree::Node(3, vec![leaf], span)
+                };
+                let mut statements = Vec::new();
+                let mut tree = recovered.tree.clone().unwrap();
+                while let Tree::Node(1, mut children, _) = tree {
+                    statements.push(children.pop().unwrap());
+                    tree = children.remove(0);
+                }
+                match tree {
+                    Tree::Node(0, mut children, _) => statements.push(children.pop().unwrap()),
+                    other => panic!("expected Stmts -> Stmt, got {:?}", other),
+                }
+                statements.reverse();
+                assert_eq!(statements.len(), 4);
+                assert_eq!(statements[1], error(4..8));
+                assert_eq!(statements[3], error(11..13));
+                assert_eq!(statements[2].span(), 9..10);
+            }
+
+            for tables in &[&dense, &comb] {
+                let from_tables =
+                    runtime::parse_recovering(*tables, &productions, &lexer, start, input);
+                assert_eq!(from_tables, recovered);
+            }
+
+            // errors right after recovering skip a token instead of looping
+            for input in &["+", ";;;", "1;+;+;+", "++++;1"] {
+                let recovered = parser.interpret_recovering("Stmts", input);
+                assert!(recovered.tree.is_some(), "{:?}", input);
+                assert!(!recovered.errors.is_empty(), "{:?}", input);
+                assert_eq!(
+                    parser.interpret("Stmts", input).unwrap_err(),
+                    recovered.errors[0]
+                );
+            }
+            // which doesn't help if the input doesn't lex
+            let recovered = parser.interpret_recovering("Stmts", "1+x;1");
+            assert_eq!(recovered.tree, None);
+            assert_eq!(recovered.errors[0].found, None);
+        }
+    }
+
+    fn make_parens(costs: &[(&str, usize)]) -> Grammar {
+        let mut grammar = make_grammar! {
+            start_symbols: [E],
+            terminals: {
+                Add: r"\+",
+                N: r"\d+",
+                LP: r"\(",
+                RP: r"\)",
+            },
+            productions: {
+                E: [ [E, Add, T], [T] ],
+                T: [ [LP, E, RP], [N] ],
+            }
+        };
+        for (terminal, cost) in costs {
+            grammar
+                .repair_costs
+                .insert(symbol::Symbol::from(*terminal), *cost);
+        }
+        grammar
+    }
+
+    #[test]
+    fn test_error_repair() {
+        let parser = make_parens(&[]).build().unwrap();
+        let (add, n, rp) = (0, 1, 3);
+
+        // inputs without errors parse as usual
+        let recovered = parser.parse_repairing("E", "(1+2)+3");
+        assert_eq!(
+            recovered.tree,
+            Some(parser.interpret("E", "(1+2)+3").unwrap())
+        );
+        assert!(recovered.errors.is_empty());
+
+        // a missing parenthesis is made up, with no text
+        let input = "(1+2";
+        let recovered = parser.parse_repairing("E", input);
+        assert_eq!(recovered.errors.len(), 1);
+        assert_eq!(recovered.errors[0].repairs, vec![Repair::Insert(rp)]);
+        assert!(recovered.tree.is_some());
+        assert!(parser
+            .render_error(input, &recovered.errors[0])
+            .ends_with("  = repaired: inserted RP\n"));
+
+        // errors further on are repaired too
+        let recovered = parser.parse_repairing("E", "((1+2)+3");
+        assert_eq!(recovered.errors[0].repairs, vec![Repair::Insert(rp)]);
+        let recovered = parser.parse_repairing("E", "1+2)+(3+4");
+        assert_eq!(recovered.errors.len(), 2);
+        assert_eq!(recovered.errors[1].repairs, vec![Repair::Insert(rp)]);
+
+        // the costs decide between deleting the extra `+` and making up a number
+        for (costs, repair) in &[
+            (&[("N", 2)], Repair::Delete(add, 2..3)),
+            (&[("Add", 2)], Repair::Insert(n)),
+        ] {
+            let parser = make_parens(*costs).build().unwrap();
+            let recovered = parser.parse_repairing("E", "1++2");
+            let mut error = recovered.errors[0].clone();
+            assert_eq!(error.repairs, vec![repair.clone()]);
+            error.repairs.clear();
+            assert_eq!(error, parser.interpret("E", "1++2").unwrap_err());
+        }
+
+        // input that doesn't lex can't be repaired
+        let recovered = parser.parse_repairing("E", "1+x");
+        assert_eq!(recovered.tree, None);
+        assert_eq!(recovered.errors[0].found, None);
+
+        // costs have to be for terminals
+        assert!(matches!(
+            make_parens(&[("E", 2)]).build(),
+            Err(GrammarError::InvalidSymbol(_))
+        ));
+    }
+
+    #[test]
+    fn test_spans() {
+        // nodes span their children, and empty ones sit where the next token
+        // starts
+        fn check(tree: &Tree, input: &str) {
+            match tree {
+                Tree::Leaf(_, text, span) => assert_eq!(&input[span.clone()], text),
+                Tree::Node(_, children, span) => {
+                    for child in children {
+                        check(child, input);
+                    }
+                    match (children.first(), children.last()) {
+                        (Some(first), Some(last)) => {
+                            assert_eq!(*span, first.span().start..last.span().end)
+                        }
+                        _ => assert!(span.is_empty()),
+                    }
+                }
+            }
+        }
+        let mut grammar = crate::this::ll_grammar();
+        // let `*` start a new line
+        grammar
+            .terminals
+            .insert(symbol::Symbol::from("Mul"), r"\n?\*".to_owned());
+        let parser = grammar.clone().build().unwrap();
+        let ll = grammar.clone().build_ll().unwrap();
+        let earley = grammar.build_earley().unwrap();
+        let input = "(1+2)\n*3";
+        let tree = parser.interpret("E", input).unwrap();
+        check(&tree, input);
+        assert_eq!(tree.span(), 0..input.len());
+
+        // every parser agrees on them
+        assert_eq!(ll.parse("E", input).unwrap(), tree);
+        let trees = parser.parse_glr("E", input).unwrap().trees();
+        assert_eq!(trees, vec![tree.clone()]);
+        assert_eq!(
+            earley.parse("E", input).unwrap().trees(),
+            vec![tree.clone()]
+        );
+
+        // E -> T E_, with E_ empty at the end of the input
+        match &tree {
+            Tree::Node(0, children, _) => {
+                assert_eq!(children[1], Tree::Node(2, vec![], 8..8));
+                assert_eq!(children[0].span(), 0..8);
+                assert_eq!(children[0].line_column(input), (1, 1));
+            }
+            other => panic!("expected E -> T E_, got {:?}", other),
+        }
+        assert_eq!(runtime::line_column(input, 7), (2, 2));
+    }
+
+    #[test]
+    fn test_push_parser() {
+        let parser = make_parens(&[]).build().unwrap();
+        let (add, n) = (0, 1);
+
+        // it can tell when the input needs more, and chunks can split tokens
+        let mut push = parser.push_parser("E");
+        for (chunk, completion) in &[
+            ("(1", Completion::Incomplete),
+            ("2+", Completion::Incomplete),
+            ("3)", Completion::Complete),
+            ("+4", Completion::Complete),
+            ("5", Completion::Complete),
+        ] {
+            push.push_str(chunk).unwrap();
+            assert_eq!(push.completion(), *completion, "{:?}", push.input());
+        }
+        assert_eq!(
+            push.finish().unwrap(),
+            parser.interpret("E", "(12+3)+45").unwrap()
+        );
+
+        // tokens lexed elsewhere can be pushed too
+        let mut push = parser.push_parser("E");
+        push.push_str("1").unwrap();
+        push.push_token(add, "+").unwrap();
+        push.push_token(n, "23").unwrap();
+        assert_eq!(
+            push.finish().unwrap(),
+            parser.interpret("E", "1+23").unwrap()
+        );
+
+        // a push that hits an error is undone, with the same error as parsing
+        // the whole input
+        let mut push = parser.push_parser("E");
+        push.push_str("1+").unwrap();
+        let error = push.push_token(add, "+").unwrap_err();
+        assert_eq!(error, parser.interpret("E", "1++").unwrap_err());
+        assert_eq!(push.input(), "1+");
+
+        // even when the token is held back, which the push checks right away
+        let error = push.push_str("+").unwrap_err();
+        assert_eq!(error, parser.interpret("E", "1++").unwrap_err());
+        assert_eq!(push.input(), "1+");
+        push.push_str("2").unwrap();
+        assert_eq!(
+            push.finish().unwrap(),
+            parser.interpret("E", "1+2").unwrap()
+        );
+
+        // text that doesn't lex waits for more, in case that makes it lex
+        let mut push = parser.push_parser("E");
+        push.push_str("1x+2").unwrap();
+        assert_eq!(push.completion(), Completion::Incomplete);
+        assert_eq!(
+            push.finish().unwrap_err(),
+            parser.interpret("E", "1x+2").unwrap_err()
+        );
+
+        // and checkpoints go back further
+        let mut push = parser.push_parser("E");
+        push.push_str("(1+").unwrap();
+        let checkpoint = push.checkpoint();
+        push.push_str("2)+3").unwrap();
+        push.restore(checkpoint);
+        push.push_str("4)").unwrap();
+        assert_eq!(push.input(), "(1+4)");
+        assert_eq!(
+            push.finish().unwrap(),
+            parser.interpret("E", "(1+4)").unwrap()
+        );
+
+        // finishing early fails like parsing does
+        let mut push = parser.push_parser("E");
+        push.push_str("(1+2").unwrap();
+        assert_eq!(
+            push.finish().unwrap_err(),
+            parser.interpret("E", "(1+2").unwrap_err()
+        );
+    }
+}
@@ -0,0 +1,496 @@
+use std::io::{self, Write};
+
+use crate::grammar::Symbol;
+use crate::messages::ErrorMessages;
+use crate::runtime::{self, Comb, CombTables, DenseTables, Lexer};
+use crate::{Action, Parser};
+
+/// How the generated code stores the parse tables.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TableLayout {
+    /// One row per state with a cell for every symbol
+    #[default]
+    Dense,
+
+    /// Rows overlapped into comb vectors by row displacement
+    Comb,
+}
+
+/// What kind of parser the generated code is.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// Tables that `pgen::runtime::parse` is driven by
+    #[default]
+    Table,
+
+    /// A function per state that matches on the lookahead, with no tables
+    Direct,
+}
+
+/// Options for `Parser::codegen_with`.
+#[derive(Clone, Debug, Default)]
+pub struct CodegenOptions {
+    pub backend: Backend,
+
+    /// How the tables are laid out, for the table backend
+    pub layout: TableLayout,
+
+    /// Custom error messages, from `Parser::compile_messages`, for the
+    /// generated `render_error` to use
+    pub messages: ErrorMessages,
+}
+
+/// The ACTION and GOTO tables encoded as integers and laid out for codegen.
+///
+/// Default reductions are never packed, since there's exactly one per state.
+#[derive(Debug)]
+pub(crate) enum PackedTables {
+    Dense {
+        n_states: usize,
+        n_terminals: usize,
+        n_nonterminals: usize,
+        actions: Vec<i64>,
+        gotos: Vec<i64>,
+        defaults: Vec<i64>,
+    },
+    Comb {
+        n_states: usize,
+        actions: PackedComb,
+        gotos: PackedComb,
+        defaults: Vec<i64>,
+    },
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct PackedComb {
+    base: Vec<i64>,
+    check: Vec<i64>,
+    value: Vec<i64>,
+}
+
+impl Parser {
+    /// The terminals in column order, ending with EOF.
+    pub(crate) fn terminal_columns(&self) -> Vec<Symbol> {
+        let mut terminals: Vec<_> = self.terminals.keys().map(|term| Symbol::T(*term)).collect();
+        terminals.push(Symbol::EOF);
+        terminals
+    }
+
+    /// A lexer for the terminals, numbering them in column order.
+    pub(crate) fn lexer(&self) -> Lexer {
+        Lexer::for_terminals(&self.terminals)
+    }
+
+    /// The columns of the ACTION table: the terminals, EOF, and last the
+    /// `error` terminal, which only the runtime ever looks up.
+    pub(crate) fn table_columns(&self) -> Vec<Symbol> {
+        let mut columns = self.terminal_columns();
+        columns.push(Symbol::error());
+        columns
+    }
+
+    /// Encodes every cell of the tables, with None for the empty ones.
+    #[allow(clippy::type_complexity)]
+    fn encoded_rows(&self) -> (Vec<Vec<Option<i64>>>, Vec<Vec<Option<i64>>>) {
+        let terminals = self.table_columns();
+        let n_states = self.table.0.len();
+        self.table_iter()
+            .map(|(_, (action, goto))| {
+                let actions = terminals
+                    .iter()
+                    .map(|terminal| action.get(terminal).map(|action| action.code(n_states)))
+                    .collect();
+                let gotos = self
+                    .nonterminals
+                    .iter()
+                    .map(|nonterminal| goto.get(&Symbol::NT(*nonterminal)).map(|n| *n as i64))
+                    .collect();
+                (actions, gotos)
+            })
+            .unzip()
+    }
+
+    pub(crate) fn pack_tables(&self, layout: TableLayout) -> PackedTables {
+        let n_states = self.table.0.len();
+        let (actions, gotos) = self.encoded_rows();
+        let defaults = self
+            .default_reductions
+            .iter()
+            .map(|default| default.map_or(-1, |n| n as i64))
+            .collect();
+        match layout {
+            TableLayout::Dense => PackedTables::Dense {
+                n_states,
+                n_terminals: self.terminals.len() + 2,
+                n_nonterminals: self.nonterminals.len(),
+                actions: actions
+                    .iter()
+                    .flatten()
+                    .map(|cell| cell.unwrap_or(-2))
+                    .collect(),
+                gotos: gotos
+                    .iter()
+                    .flatten()
+                    .map(|cell| cell.unwrap_or(-1))
+                    .collect(),
+                defaults,
+            },
+            TableLayout::Comb => PackedTables::Comb {
+                n_states,
+                actions: PackedComb::pack(&actions),
+                gotos: PackedComb::pack(&gotos),
+                defaults,
+            },
+        }
+    }
+}
+
+impl PackedComb {
+    /// Packs the rows first-fit, densest rows first.
+    fn pack(rows: &[Vec<Option<i64>>]) -> Self {
+        let mut order = (0..rows.len()).collect::<Vec<_>>();
+        order.sort_by_key(|row| std::cmp::Reverse(rows[*row].iter().flatten().count()));
+
+        let mut comb = PackedComb {
+            base: vec![0; rows.len()],
+            ..PackedComb::default()
+        };
+        for row in order {
+            let cells = rows[row]
+                .iter()
+                .enumerate()
+                .filter_map(|(column, cell)| Some((column, (*cell)?)))
+                .collect::<Vec<_>>();
+            if cells.is_empty() {
+                continue;
+            }
+
+            let fits = |base: usize| {
+                cells
+                    .iter()
+                    .all(|(column, _)| comb.check.get(base + column).is_none_or(|check| *check < 0))
+            };
+            let base = (0..).find(|base| fits(*base)).unwrap();
+            let end = base + cells.last().unwrap().0 + 1;
+            if comb.check.len() < end {
+                comb.check.resize(end, -1);
+                comb.value.resize(end, 0);
+            }
+            for (column, value) in cells {
+                comb.check[base + column] = row as i64;
+                comb.value[base + column] = value;
+            }
+            comb.base[row] = base as i64;
+        }
+        comb
+    }
+
+    fn as_comb(&self) -> Comb<'_, i64> {
+        Comb {
+            base: &self.base,
+            check: &self.check,
+            value: &self.value,
+        }
+    }
+
+    fn write(&self, mut w: impl Write, name: &str, ty: &str) -> io::Result<()> {
+        for (suffix, array) in &[
+            ("BASE", &self.base),
+            ("CHECK", &self.check),
+            ("VALUE", &self.value),
+        ] {
+            writeln!(w, "const {}_{}: &[{}] = &[", name, suffix, ty)?;
+            for line in array.chunks(20) {
+                writeln!(w, "    {}", join(line))?;
+            }
+            writeln!(w, "];")?;
+        }
+        Ok(())
+    }
+
+    fn cells(&self) -> impl Iterator<Item = &i64> {
+        self.base.iter().chain(&self.check).chain(&self.value)
+    }
+}
+
+impl PackedTables {
+    /// Runs `f` on a runtime view of these tables, made on the stack.
+    fn with_view<T>(&self, f: impl FnOnce(&dyn runtime::Tables) -> T) -> T {
+        match self {
+            PackedTables::Dense {
+                n_states,
+                n_terminals,
+                n_nonterminals,
+                actions,
+                gotos,
+                defaults,
+            } => f(&DenseTables {
+                n_states: *n_states,
+                n_terminals: *n_terminals,
+                n_nonterminals: *n_nonterminals,
+                actions,
+                gotos,
+                defaults,
+            }),
+            PackedTables::Comb {
+                n_states,
+                actions,
+                gotos,
+                defaults,
+            } => f(&CombTables {
+                n_states: *n_states,
+                actions: actions.as_comb(),
+                gotos: gotos.as_comb(),
+                defaults,
+            }),
+        }
+    }
+
+    /// Writes the tables as constants, ending with a `TABLES` constant that
+    /// implements `pgen::runtime::Tables`.
+    pub(crate) fn write(&self, mut w: impl Write) -> io::Result<()> {
+        match self {
+            PackedTables::Dense {
+                n_states,
+                n_terminals,
+                n_nonterminals,
+                actions,
+                gotos,
+                defaults,
+            } => {
+                let action_type = int_type(actions.iter().chain(defaults));
+                writeln!(w, "const ACTIONS: &[{}] = &[", action_type)?;
+                for row in actions.chunks(*n_terminals) {
+                    writeln!(w, "    {}", join(row))?;
+                }
+                writeln!(w, "];")?;
+
+                let goto_type = int_type(gotos);
+                writeln!(w, "const GOTOS: &[{}] = &[", goto_type)?;
+                for row in gotos.chunks((*n_nonterminals).max(1)) {
+                    writeln!(w, "    {}", join(row))?;
+                }
+                writeln!(w, "];")?;
+                write_defaults(&mut w, action_type, defaults)?;
+
+                writeln!(
+                    w,
+                    "const TABLES: DenseTables<'static, {}, {}> = DenseTables {{",
+                    action_type, goto_type
+                )?;
+                writeln!(w, "    n_states: {},", n_states)?;
+                writeln!(w, "    n_terminals: {},", n_terminals)?;
+                writeln!(w, "    n_nonterminals: {},", n_nonterminals)?;
+                writeln!(w, "    actions: ACTIONS,")?;
+                writeln!(w, "    gotos: GOTOS,")?;
+                writeln!(w, "    defaults: DEFAULTS,")?;
+                writeln!(w, "}};")?;
+            }
+            PackedTables::Comb {
+                n_states,
+                actions,
+                gotos,
+                defaults,
+            } => {
+                // the defaults share the action type, so it has to fit them too
+                let action_type = int_type(actions.cells().chain(defaults));
+                let goto_type = int_type(gotos.cells());
+                actions.write(&mut w, "ACTION", action_type)?;
+                gotos.write(&mut w, "GOTO", goto_type)?;
+                write_defaults(&mut w, action_type, defaults)?;
+                writeln!(
+                    w,
+                    "const TABLES: CombTables<'static, {}, {}> = CombTables {{",
+                    action_type, goto_type
+                )?;
+                writeln!(w, "    n_states: {},", n_states)?;
+                for name in &["actions", "gotos"] {
+                    let prefix = name[..name.len() - 1].to_uppercase();
+                    writeln!(
+                        w,
+                        "    {}: Comb {{ base: {1}_BASE, check: {1}_CHECK, value: {1}_VALUE }},",
+                        name, prefix
+                    )?;
+                }
+                writeln!(w, "    defaults: DEFAULTS,")?;
+                writeln!(w, "}};")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// for driving a parse without codegen, and for parsers that own their
+// tables, like the push parser `Parser` makes
+impl runtime::Tables for PackedTables {
+    fn action(&self, state: usize, terminal: usize) -> Option<Action> {
+        self.with_view(|tables| tables.action(state, terminal))
+    }
+
+    fn goto(&self, state: usize, nonterminal: usize) -> Option<usize> {
+        self.with_view(|tables| tables.goto(state, nonterminal))
+    }
+
+    fn default_reduction(&self, state: usize) -> Option<usize> {
+        self.with_view(|tables| tables.default_reduction(state))
+    }
+}
+
+/// Writes the default reduction of every state as a `DEFAULTS` constant.
+fn write_defaults(mut w: impl Write, ty: &str, defaults: &[i64]) -> io::Result<()> {
+    writeln!(w, "const DEFAULTS: &[{}] = &[", ty)?;
+    for line in defaults.chunks(20) {
+        writeln!(w, "    {}", join(line))?;
+    }
+    writeln!(w, "];")
+}
+
+/// The narrowest integer type that can hold every value.
+fn int_type<'a>(values: impl IntoIterator<Item = &'a i64>) -> &'static str {
+    let (min, max) = values.into_iter().fold((0, 0), |(min, max), value| {
+        (min.min(*value), max.max(*value))
+    });
+    if min >= i8::MIN as i64 && max <= i8::MAX as i64 {
+        "i8"
+    } else if min >= i16::MIN as i64 && max <= i16::MAX as i64 {
+        "i16"
+    } else if min >= i32::MIN as i64 && max <= i32::MAX as i64 {
+        "i32"
+    } else {
+        "i64"
+    }
+}
+
+fn join(values: &[i64]) -> String {
+    let values = values
+        .iter()
+        .map(|value| format!("{},", value))
+        .collect::<Vec<_>>();
+    values.join("")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TableLayout;
+    use crate::parser::Action;
+    use crate::runtime::{self, Tables};
+    use crate::{BuildOptions, CodegenOptions, DefaultReductions, Parser};
+
+    fn codegen(parser: &Parser, layout: TableLayout) -> String {
+        let mut buf = Vec::new();
+        parser
+            .codegen_with(
+                &mut buf,
+                &CodegenOptions {
+                    layout,
+                    ..CodegenOptions::default()
+                },
+            )
+            .unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_layouts_parse_the_same() {
+        for mode in &[
+            DefaultReductions::None,
+            DefaultReductions::Consistent,
+            DefaultReductions::All,
+        ] {
+            let options = BuildOptions {
+                default_reductions: *mode,
+                ..BuildOptions::default()
+            };
+            let parser = crate::this::pgen_grammar().build_with(&options).unwrap();
+            let lexer = parser.lexer();
+            let productions = parser.production_shapes();
+            let dense = parser.pack_tables(TableLayout::Dense);
+            let comb = parser.pack_tables(TableLayout::Comb);
+            let start = parser.start_state("E").unwrap();
+
+            for input in &["1", "0+1", "1+1*0", "0*1*1+0+1"] {
+                let expected = parser.interpret("E", input).unwrap();
+                for tables in &[&dense, &comb] {
+                    let tree = runtime::parse(*tables, &productions, &lexer, start, input);
+                    assert_eq!(tree.unwrap(), expected);
+                }
+            }
+            for input in &["", "+", "1+", "11", "1+2"] {
+                let expected = parser.interpret("E", input).unwrap_err();
+                for tables in &[&dense, &comb] {
+                    let tree = runtime::parse(*tables, &productions, &lexer, start, input);
+                    assert_eq!(tree.unwrap_err(), expected, "{:?}", input);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_lazy_lookahead() {
+        let options = BuildOptions {
+            default_reductions: DefaultReductions::Consistent,
+            ..BuildOptions::default()
+        };
+        let parser = crate::this::pgen_grammar().build_with(&options).unwrap();
+        let lexer = parser.lexer();
+        let tables = parser.pack_tables(TableLayout::Dense);
+        let start = parser.start_state("E").unwrap();
+
+        // shifting doesn't lex the token after
+        let mut stack = runtime::start(&lexer, "1x", start).unwrap();
+        let next = match tables.action(start, stack.lookahead().unwrap()) {
+            Some(Action::Shift(next)) => next,
+            other => panic!("expected a shift, got {:?}", other),
+        };
+        stack.shift(next).unwrap();
+
+        // B -> N1 . is consistent, so it can reduce without the lookahead
+        let state = stack.state();
+        assert!(tables.default_reduction(state).is_some());
+        assert!((0..=lexer.error()).all(|terminal| tables.action(state, terminal).is_none()));
+        assert_eq!(stack.lookahead().unwrap_err().span, 1..2);
+
+        let productions = parser.production_shapes();
+        let error = runtime::parse(&tables, &productions, &lexer, start, "1x").unwrap_err();
+        assert_eq!((error.span, error.found), (1..2, None));
+    }
+
+    #[test]
+    fn test_layouts_agree() {
+        // every cell of a big table survives packing
+        let parser = crate::this::c_grammar().build().unwrap();
+        let dense = parser.pack_tables(TableLayout::Dense);
+        let comb = parser.pack_tables(TableLayout::Comb);
+        for state in 0..parser.table.0.len() {
+            for terminal in 0..parser.terminals.len() + 2 {
+                assert_eq!(dense.action(state, terminal), comb.action(state, terminal));
+            }
+            for nonterminal in 0..parser.nonterminals.len() {
+                assert_eq!(
+                    dense.goto(state, nonterminal),
+                    comb.goto(state, nonterminal)
+                );
+            }
+            assert_eq!(
+                dense.default_reduction(state),
+                comb.default_reduction(state)
+            );
+        }
+    }
+
+    #[test]
+    fn test_int_types() {
+        let small = crate::this::pgen_grammar().build().unwrap();
+        assert!(codegen(&small, TableLayout::Dense).contains("const ACTIONS: &[i8]"));
+        assert!(codegen(&small, TableLayout::Comb).contains("const ACTION_VALUE: &[i8]"));
+
+        // 349 states don't fit in an i8
+        let big = crate::this::c_grammar().build().unwrap();
+        let dense = codegen(&big, TableLayout::Dense);
+        assert!(dense.contains("const ACTIONS: &[i16]"));
+        assert!(dense.contains("const GOTOS: &[i16]"));
+        let comb = codegen(&big, TableLayout::Comb);
+        assert!(comb.contains("const ACTION_VALUE: &[i16]"));
+        assert!(comb.len() < dense.len());
+    }
+}
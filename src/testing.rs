@@ -0,0 +1,88 @@
+//! Property testing support, shared with the generated parsers' tests: random
+//! sentences of a grammar, some of them mutated into likely invalid inputs.
+//! Only built for pgen's own tests, or with the `testing` feature.
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::sample::{select, Index};
+
+use crate::{GenerateOptions, Grammar};
+
+/// A change to a sentence, which most likely makes it invalid.
+#[derive(Clone, Debug)]
+enum Mutation {
+    Delete(Index),
+    Insert(Index, char),
+    Swap(Index),
+    Repeat(Index, Index),
+}
+
+impl Mutation {
+    fn apply(&self, input: &mut Vec<char>) {
+        if input.is_empty() {
+            if let Mutation::Insert(_, c) = self {
+                input.push(*c);
+            }
+            return;
+        }
+        match self {
+            Mutation::Delete(i) => {
+                input.remove(i.index(input.len()));
+            }
+            Mutation::Insert(i, c) => input.insert(i.index(input.len() + 1), *c),
+            Mutation::Swap(i) => {
+                let i = i.index(input.len());
+                let j = (i + 1) % input.len();
+                input.swap(i, j);
+            }
+            Mutation::Repeat(i, j) => {
+                let (i, j) = (i.index(input.len()), j.index(input.len()));
+                let repeated = input[i.min(j)..=i.max(j)].to_vec();
+                let at = i.max(j) + 1;
+                input.splice(at..at, repeated);
+            }
+        }
+    }
+}
+
+/// Sentences of the start symbol, from the generator with a random seed, each
+/// with up to `max_mutations` mutations made to it. Inserted characters are
+/// ones the grammar's sentences use, or a space, which the grammars in
+/// `pgen::this` don't lex.
+pub fn inputs(
+    grammar: &Grammar,
+    start: &str,
+    max_mutations: usize,
+) -> impl Strategy<Value = String> {
+    let options = GenerateOptions {
+        max_depth: 8,
+        max_tokens: 16,
+        ..GenerateOptions::default()
+    };
+    let mut alphabet = grammar
+        .generator(start, &options)
+        .unwrap()
+        .take(20)
+        .flat_map(|sentence| sentence.chars().collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    alphabet.push(' ');
+    alphabet.sort_unstable();
+    alphabet.dedup();
+
+    let mutation = prop_oneof![
+        any::<Index>().prop_map(Mutation::Delete),
+        (any::<Index>(), select(alphabet)).prop_map(|(i, c)| Mutation::Insert(i, c)),
+        any::<Index>().prop_map(Mutation::Swap),
+        (any::<Index>(), any::<Index>()).prop_map(|(i, j)| Mutation::Repeat(i, j)),
+    ];
+    let (grammar, start) = (grammar.clone(), start.to_owned());
+    (any::<u64>(), vec(mutation, 0..=max_mutations)).prop_map(move |(seed, mutations)| {
+        let options = GenerateOptions { seed, ..options };
+        let sentence = grammar.generator(&start, &options).unwrap().next().unwrap();
+        let mut input = sentence.chars().collect::<Vec<_>>();
+        for mutation in mutations.iter() {
+            mutation.apply(&mut input);
+        }
+        input.into_iter().collect()
+    })
+}
@@ -1,5 +1,17 @@
 use crate::Grammar;
 
+/// Looks up one of the built-in grammars by name.
+pub fn by_name(name: &str) -> Option<Grammar> {
+    match name {
+        "pgen" => Some(pgen_grammar()),
+        "ll" => Some(ll_grammar()),
+        "stmts" => Some(stmts_grammar()),
+        "c" => Some(c_grammar()),
+        "sql" => Some(sql_grammar()),
+        _ => None,
+    }
+}
+
 pub fn pgen_grammar() -> Grammar {
     make_grammar! {
         start_symbols: [E],
@@ -14,25 +26,713 @@ pub fn pgen_grammar() -> Grammar {
             B: [ [N0], [N1] ],
         }
     }
+}
+
+/// Arithmetic with the left recursion taken out, which makes it LL(1).
+pub fn ll_grammar() -> Grammar {
+    make_grammar! {
+        start_symbols: [E],
+        terminals: {
+            N: r"\d+",
+            Add: r"\+",
+            Mul: r"\*",
+            LP: r"\(",
+            RP: r"\)",
+        },
+        productions: {
+            E: [ [T, E_] ],
+            E_: [ [Add, T, E_], [ɛ] ],
+            T: [ [F, T_] ],
+            T_: [ [Mul, F, T_], [ɛ] ],
+            F: [ [LP, E, RP], [N] ],
+        }
+    }
+}
+
+/// Statements separated by semicolons, with a Yacc-style error production so
+/// that a bad statement doesn't stop the parse.
+pub fn stmts_grammar() -> Grammar {
+    make_grammar! {
+        start_symbols: [Stmts],
+        terminals: {
+            Semi: r";",
+            Add: r"\+",
+            N: r"\d+",
+        },
+        productions: {
+            Stmts: [ [Stmt], [Stmts, Semi, Stmt] ],
+            Stmt: [ [E], [error] ],
+            E: [ [E, Add, N], [N] ],
+        }
+    }
+}
 
-    // make_grammar! {
-    //     start_symbols: [E],
-    //     terminals: {
-    //         N: r"\d+",
-    //         Add: r"\+",
-    //         Mul: r"\*",
-    //         LP: r"\(",
-    //         RP: r"\)",
-    //     },
-    //     productions: {
-    //         // E: [ [T, E_] ],
-    //         // E_: [ [Add, T, E_], [ɛ] ],
-    //         // T: [ [F, T_] ],
-    //         // T_: [ [Mul, F, T_], [ɛ] ],
-    //         // F: [ [LP, E, RP], [N] ],
-    //         E: [ [E, Add, T], [T], ],
-    //         T: [ [T, Mul, F], [F], ],
-    //         F: [ [LP, E, RP], [N], ],
-    //     },
-    // }
+/// The ANSI C grammar from the classic yacc distribution, minus the typedef
+/// hack: `TYPE_NAME` is just another token. It's mostly here to have a big,
+/// realistic grammar for the benchmarks.
+pub fn c_grammar() -> Grammar {
+    make_grammar! {
+        start_symbols: [translation_unit],
+        terminals: {
+            SIZEOF: r"sizeof",
+            PTR_OP: r"->",
+            INC_OP: r"\+\+",
+            DEC_OP: r"--",
+            LEFT_OP: r"<<",
+            RIGHT_OP: r">>",
+            LE_OP: r"<=",
+            GE_OP: r">=",
+            EQ_OP: r"==",
+            NE_OP: r"!=",
+            AND_OP: r"&&",
+            OR_OP: r"\|\|",
+            MUL_ASSIGN: r"\*=",
+            DIV_ASSIGN: r"/=",
+            MOD_ASSIGN: r"%=",
+            ADD_ASSIGN: r"\+=",
+            SUB_ASSIGN: r"-=",
+            LEFT_ASSIGN: r"<<=",
+            RIGHT_ASSIGN: r">>=",
+            AND_ASSIGN: r"&=",
+            XOR_ASSIGN: r"\^=",
+            OR_ASSIGN: r"\|=",
+            TYPEDEF: r"typedef",
+            EXTERN: r"extern",
+            STATIC: r"static",
+            AUTO: r"auto",
+            REGISTER: r"register",
+            CHAR: r"char",
+            SHORT: r"short",
+            INT: r"int",
+            LONG: r"long",
+            SIGNED: r"signed",
+            UNSIGNED: r"unsigned",
+            FLOAT: r"float",
+            DOUBLE: r"double",
+            CONST: r"const",
+            VOLATILE: r"volatile",
+            VOID: r"void",
+            STRUCT: r"struct",
+            UNION: r"union",
+            ENUM: r"enum",
+            ELLIPSIS: r"\.\.\.",
+            CASE: r"case",
+            DEFAULT: r"default",
+            IF: r"if",
+            ELSE: r"else",
+            SWITCH: r"switch",
+            WHILE: r"while",
+            DO: r"do",
+            FOR: r"for",
+            GOTO: r"goto",
+            CONTINUE: r"continue",
+            BREAK: r"break",
+            RETURN: r"return",
+            TYPE_NAME: r"[A-Z][A-Za-z0-9_]*_t",
+            IDENTIFIER: r"[A-Za-z_][A-Za-z0-9_]*",
+            CONSTANT: r"[0-9]+",
+            STRING_LITERAL: r#""[^"]*""#,
+            SEMI: r";",
+            LBRACE: r"\{",
+            RBRACE: r"\}",
+            COMMA: r",",
+            COLON: r":",
+            ASSIGN: r"=",
+            LPAREN: r"\(",
+            RPAREN: r"\)",
+            LBRACKET: r"\[",
+            RBRACKET: r"\]",
+            DOT: r"\.",
+            AMP: r"&",
+            BANG: r"!",
+            TILDE: r"~",
+            MINUS: r"-",
+            PLUS: r"\+",
+            STAR: r"\*",
+            SLASH: r"/",
+            PERCENT: r"%",
+            LT: r"<",
+            GT: r">",
+            CARET: r"\^",
+            PIPE: r"\|",
+            QUESTION: r"\?",
+        },
+        productions: {
+            primary_expression: [
+                [IDENTIFIER],
+                [CONSTANT],
+                [STRING_LITERAL],
+                [LPAREN, expression, RPAREN],
+            ],
+            postfix_expression: [
+                [primary_expression],
+                [postfix_expression, LBRACKET, expression, RBRACKET],
+                [postfix_expression, LPAREN, RPAREN],
+                [postfix_expression, LPAREN, argument_expression_list, RPAREN],
+                [postfix_expression, DOT, IDENTIFIER],
+                [postfix_expression, PTR_OP, IDENTIFIER],
+                [postfix_expression, INC_OP],
+                [postfix_expression, DEC_OP],
+            ],
+            argument_expression_list: [
+                [assignment_expression],
+                [argument_expression_list, COMMA, assignment_expression],
+            ],
+            unary_expression: [
+                [postfix_expression],
+                [INC_OP, unary_expression],
+                [DEC_OP, unary_expression],
+                [unary_operator, cast_expression],
+                [SIZEOF, unary_expression],
+                [SIZEOF, LPAREN, type_name, RPAREN],
+            ],
+            unary_operator: [ [AMP], [STAR], [PLUS], [MINUS], [TILDE], [BANG] ],
+            cast_expression: [
+                [unary_expression],
+                [LPAREN, type_name, RPAREN, cast_expression],
+            ],
+            multiplicative_expression: [
+                [cast_expression],
+                [multiplicative_expression, STAR, cast_expression],
+                [multiplicative_expression, SLASH, cast_expression],
+                [multiplicative_expression, PERCENT, cast_expression],
+            ],
+            additive_expression: [
+                [multiplicative_expression],
+                [additive_expression, PLUS, multiplicative_expression],
+                [additive_expression, MINUS, multiplicative_expression],
+            ],
+            shift_expression: [
+                [additive_expression],
+                [shift_expression, LEFT_OP, additive_expression],
+                [shift_expression, RIGHT_OP, additive_expression],
+            ],
+            relational_expression: [
+                [shift_expression],
+                [relational_expression, LT, shift_expression],
+                [relational_expression, GT, shift_expression],
+                [relational_expression, LE_OP, shift_expression],
+                [relational_expression, GE_OP, shift_expression],
+            ],
+            equality_expression: [
+                [relational_expression],
+                [equality_expression, EQ_OP, relational_expression],
+                [equality_expression, NE_OP, relational_expression],
+            ],
+            and_expression: [
+                [equality_expression],
+                [and_expression, AMP, equality_expression],
+            ],
+            exclusive_or_expression: [
+                [and_expression],
+                [exclusive_or_expression, CARET, and_expression],
+            ],
+            inclusive_or_expression: [
+                [exclusive_or_expression],
+                [inclusive_or_expression, PIPE, exclusive_or_expression],
+            ],
+            logical_and_expression: [
+                [inclusive_or_expression],
+                [logical_and_expression, AND_OP, inclusive_or_expression],
+            ],
+            logical_or_expression: [
+                [logical_and_expression],
+                [logical_or_expression, OR_OP, logical_and_expression],
+            ],
+            conditional_expression: [
+                [logical_or_expression],
+                [logical_or_expression, QUESTION, expression, COLON, conditional_expression],
+            ],
+            assignment_expression: [
+                [conditional_expression],
+                [unary_expression, assignment_operator, assignment_expression],
+            ],
+            assignment_operator: [
+                [ASSIGN], [MUL_ASSIGN], [DIV_ASSIGN], [MOD_ASSIGN], [ADD_ASSIGN],
+                [SUB_ASSIGN], [LEFT_ASSIGN], [RIGHT_ASSIGN], [AND_ASSIGN],
+                [XOR_ASSIGN], [OR_ASSIGN],
+            ],
+            expression: [
+                [assignment_expression],
+                [expression, COMMA, assignment_expression],
+            ],
+            constant_expression: [ [conditional_expression] ],
+            declaration: [
+                [declaration_specifiers, SEMI],
+                [declaration_specifiers, init_declarator_list, SEMI],
+            ],
+            declaration_specifiers: [
+                [storage_class_specifier],
+                [storage_class_specifier, declaration_specifiers],
+                [type_specifier],
+                [type_specifier, declaration_specifiers],
+                [type_qualifier],
+                [type_qualifier, declaration_specifiers],
+            ],
+            init_declarator_list: [
+                [init_declarator],
+                [init_declarator_list, COMMA, init_declarator],
+            ],
+            init_declarator: [
+                [declarator],
+                [declarator, ASSIGN, initializer],
+            ],
+            storage_class_specifier: [ [TYPEDEF], [EXTERN], [STATIC], [AUTO], [REGISTER] ],
+            type_specifier: [
+                [VOID], [CHAR], [SHORT], [INT], [LONG], [FLOAT], [DOUBLE], [SIGNED],
+                [UNSIGNED], [struct_or_union_specifier], [enum_specifier], [TYPE_NAME],
+            ],
+            struct_or_union_specifier: [
+                [struct_or_union, IDENTIFIER, LBRACE, struct_declaration_list, RBRACE],
+                [struct_or_union, LBRACE, struct_declaration_list, RBRACE],
+                [struct_or_union, IDENTIFIER],
+            ],
+            struct_or_union: [ [STRUCT], [UNION] ],
+            struct_declaration_list: [
+                [struct_declaration],
+                [struct_declaration_list, struct_declaration],
+            ],
+            struct_declaration: [
+                [specifier_qualifier_list, struct_declarator_list, SEMI],
+            ],
+            specifier_qualifier_list: [
+                [type_specifier, specifier_qualifier_list],
+                [type_specifier],
+                [type_qualifier, specifier_qualifier_list],
+                [type_qualifier],
+            ],
+            struct_declarator_list: [
+                [struct_declarator],
+                [struct_declarator_list, COMMA, struct_declarator],
+            ],
+            struct_declarator: [
+                [declarator],
+                [COLON, constant_expression],
+                [declarator, COLON, constant_expression],
+            ],
+            enum_specifier: [
+                [ENUM, LBRACE, enumerator_list, RBRACE],
+                [ENUM, IDENTIFIER, LBRACE, enumerator_list, RBRACE],
+                [ENUM, IDENTIFIER],
+            ],
+            enumerator_list: [
+                [enumerator],
+                [enumerator_list, COMMA, enumerator],
+            ],
+            enumerator: [
+                [IDENTIFIER],
+                [IDENTIFIER, ASSIGN, constant_expression],
+            ],
+            type_qualifier: [ [CONST], [VOLATILE] ],
+            declarator: [
+                [pointer, direct_declarator],
+                [direct_declarator],
+            ],
+            direct_declarator: [
+                [IDENTIFIER],
+                [LPAREN, declarator, RPAREN],
+                [direct_declarator, LBRACKET, constant_expression, RBRACKET],
+                [direct_declarator, LBRACKET, RBRACKET],
+                [direct_declarator, LPAREN, parameter_type_list, RPAREN],
+                [direct_declarator, LPAREN, identifier_list, RPAREN],
+                [direct_declarator, LPAREN, RPAREN],
+            ],
+            pointer: [
+                [STAR],
+                [STAR, type_qualifier_list],
+                [STAR, pointer],
+                [STAR, type_qualifier_list, pointer],
+            ],
+            type_qualifier_list: [
+                [type_qualifier],
+                [type_qualifier_list, type_qualifier],
+            ],
+            parameter_type_list: [
+                [parameter_list],
+                [parameter_list, COMMA, ELLIPSIS],
+            ],
+            parameter_list: [
+                [parameter_declaration],
+                [parameter_list, COMMA, parameter_declaration],
+            ],
+            parameter_declaration: [
+                [declaration_specifiers, declarator],
+                [declaration_specifiers, abstract_declarator],
+                [declaration_specifiers],
+            ],
+            identifier_list: [
+                [IDENTIFIER],
+                [identifier_list, COMMA, IDENTIFIER],
+            ],
+            type_name: [
+                [specifier_qualifier_list],
+                [specifier_qualifier_list, abstract_declarator],
+            ],
+            abstract_declarator: [
+                [pointer],
+                [direct_abstract_declarator],
+                [pointer, direct_abstract_declarator],
+            ],
+            direct_abstract_declarator: [
+                [LPAREN, abstract_declarator, RPAREN],
+                [LBRACKET, RBRACKET],
+                [LBRACKET, constant_expression, RBRACKET],
+                [direct_abstract_declarator, LBRACKET, RBRACKET],
+                [direct_abstract_declarator, LBRACKET, constant_expression, RBRACKET],
+                [LPAREN, RPAREN],
+                [LPAREN, parameter_type_list, RPAREN],
+                [direct_abstract_declarator, LPAREN, RPAREN],
+                [direct_abstract_declarator, LPAREN, parameter_type_list, RPAREN],
+            ],
+            initializer: [
+                [assignment_expression],
+                [LBRACE, initializer_list, RBRACE],
+                [LBRACE, initializer_list, COMMA, RBRACE],
+            ],
+            initializer_list: [
+                [initializer],
+                [initializer_list, COMMA, initializer],
+            ],
+            statement: [
+                [labeled_statement],
+                [compound_statement],
+                [expression_statement],
+                [selection_statement],
+                [iteration_statement],
+                [jump_statement],
+            ],
+            labeled_statement: [
+                [IDENTIFIER, COLON, statement],
+                [CASE, constant_expression, COLON, statement],
+                [DEFAULT, COLON, statement],
+            ],
+            compound_statement: [
+                [LBRACE, RBRACE],
+                [LBRACE, statement_list, RBRACE],
+                [LBRACE, declaration_list, RBRACE],
+                [LBRACE, declaration_list, statement_list, RBRACE],
+            ],
+            declaration_list: [
+                [declaration],
+                [declaration_list, declaration],
+            ],
+            statement_list: [
+                [statement],
+                [statement_list, statement],
+            ],
+            expression_statement: [
+                [SEMI],
+                [expression, SEMI],
+            ],
+            selection_statement: [
+                [IF, LPAREN, expression, RPAREN, statement],
+                [IF, LPAREN, expression, RPAREN, statement, ELSE, statement],
+                [SWITCH, LPAREN, expression, RPAREN, statement],
+            ],
+            iteration_statement: [
+                [WHILE, LPAREN, expression, RPAREN, statement],
+                [DO, statement, WHILE, LPAREN, expression, RPAREN, SEMI],
+                [FOR, LPAREN, expression_statement, expression_statement, RPAREN, statement],
+                [FOR, LPAREN, expression_statement, expression_statement, expression, RPAREN, statement],
+            ],
+            jump_statement: [
+                [GOTO, IDENTIFIER, SEMI],
+                [CONTINUE, SEMI],
+                [BREAK, SEMI],
+                [RETURN, SEMI],
+                [RETURN, expression, SEMI],
+            ],
+            translation_unit: [
+                [external_declaration],
+                [translation_unit, external_declaration],
+            ],
+            external_declaration: [
+                [function_definition],
+                [declaration],
+            ],
+            function_definition: [
+                [declaration_specifiers, declarator, declaration_list, compound_statement],
+                [declaration_specifiers, declarator, compound_statement],
+                [declarator, declaration_list, compound_statement],
+                [declarator, compound_statement],
+            ],
+        }
+    }
+}
+
+/// A subset of SQL: queries with joins, grouping and subqueries, plus the
+/// basic data manipulation and definition statements.
+pub fn sql_grammar() -> Grammar {
+    make_grammar! {
+        start_symbols: [statement_list],
+        terminals: {
+            SELECT: r"(?i)select",
+            DISTINCT: r"(?i)distinct",
+            ALL: r"(?i)all",
+            FROM: r"(?i)from",
+            WHERE: r"(?i)where",
+            GROUP: r"(?i)group",
+            BY: r"(?i)by",
+            HAVING: r"(?i)having",
+            ORDER: r"(?i)order",
+            ASC: r"(?i)asc",
+            DESC: r"(?i)desc",
+            LIMIT: r"(?i)limit",
+            OFFSET: r"(?i)offset",
+            UNION: r"(?i)union",
+            EXCEPT: r"(?i)except",
+            INTERSECT: r"(?i)intersect",
+            JOIN: r"(?i)join",
+            INNER: r"(?i)inner",
+            LEFT: r"(?i)left",
+            RIGHT: r"(?i)right",
+            OUTER: r"(?i)outer",
+            CROSS: r"(?i)cross",
+            ON: r"(?i)on",
+            AS: r"(?i)as",
+            AND: r"(?i)and",
+            OR: r"(?i)or",
+            NOT: r"(?i)not",
+            IN: r"(?i)in",
+            IS: r"(?i)is",
+            NULL: r"(?i)null",
+            LIKE: r"(?i)like",
+            BETWEEN: r"(?i)between",
+            EXISTS: r"(?i)exists",
+            CASE: r"(?i)case",
+            WHEN: r"(?i)when",
+            THEN: r"(?i)then",
+            ELSE: r"(?i)else",
+            END: r"(?i)end",
+            INSERT: r"(?i)insert",
+            INTO: r"(?i)into",
+            VALUES: r"(?i)values",
+            UPDATE: r"(?i)update",
+            SET: r"(?i)set",
+            DELETE: r"(?i)delete",
+            CREATE: r"(?i)create",
+            TABLE: r"(?i)table",
+            PRIMARY: r"(?i)primary",
+            KEY: r"(?i)key",
+            DROP: r"(?i)drop",
+            IDENT: r"[A-Za-z_][A-Za-z0-9_]*",
+            NUMBER: r"[0-9]+(\.[0-9]+)?",
+            STRING: r"'[^']*'",
+            SEMI: r";",
+            COMMA: r",",
+            DOT: r"\.",
+            LPAREN: r"\(",
+            RPAREN: r"\)",
+            STAR: r"\*",
+            SLASH: r"/",
+            PLUS: r"\+",
+            MINUS: r"-",
+            CONCAT: r"\|\|",
+            EQ: r"=",
+            NE: r"<>|!=",
+            LT: r"<",
+            GT: r">",
+            LE: r"<=",
+            GE: r">=",
+        },
+        productions: {
+            statement_list: [
+                [statement, SEMI],
+                [statement_list, statement, SEMI],
+            ],
+            statement: [
+                [query],
+                [insert_statement],
+                [update_statement],
+                [delete_statement],
+                [create_statement],
+                [drop_statement],
+            ],
+            query: [
+                [query_term],
+                [query, UNION, query_term],
+                [query, UNION, ALL, query_term],
+                [query, EXCEPT, query_term],
+            ],
+            query_term: [
+                [query_primary],
+                [query_term, INTERSECT, query_primary],
+            ],
+            query_primary: [
+                [select_core, order_clause, limit_clause],
+                [LPAREN, query, RPAREN],
+            ],
+            select_core: [
+                [SELECT, set_quantifier, select_list, from_clause, where_clause, group_clause],
+            ],
+            set_quantifier: [ [ɛ], [DISTINCT], [ALL] ],
+            select_list: [
+                [select_item],
+                [select_list, COMMA, select_item],
+            ],
+            select_item: [
+                [STAR],
+                [IDENT, DOT, STAR],
+                [expr],
+                [expr, AS, IDENT],
+                [expr, IDENT],
+            ],
+            from_clause: [ [ɛ], [FROM, table_list] ],
+            table_list: [
+                [table_ref],
+                [table_list, COMMA, table_ref],
+            ],
+            table_ref: [
+                [table_primary],
+                [table_ref, join_type, JOIN, table_primary, ON, expr],
+                [table_ref, CROSS, JOIN, table_primary],
+            ],
+            join_type: [
+                [ɛ],
+                [INNER],
+                [LEFT],
+                [LEFT, OUTER],
+                [RIGHT],
+                [RIGHT, OUTER],
+            ],
+            table_primary: [
+                [qualified_name],
+                [qualified_name, AS, IDENT],
+                [qualified_name, IDENT],
+                [LPAREN, query, RPAREN, AS, IDENT],
+            ],
+            qualified_name: [
+                [IDENT],
+                [IDENT, DOT, IDENT],
+            ],
+            where_clause: [ [ɛ], [WHERE, expr] ],
+            group_clause: [
+                [ɛ],
+                [GROUP, BY, expr_list],
+                [GROUP, BY, expr_list, HAVING, expr],
+            ],
+            order_clause: [ [ɛ], [ORDER, BY, order_list] ],
+            order_list: [
+                [order_item],
+                [order_list, COMMA, order_item],
+            ],
+            order_item: [ [expr], [expr, ASC], [expr, DESC] ],
+            limit_clause: [
+                [ɛ],
+                [LIMIT, NUMBER],
+                [LIMIT, NUMBER, OFFSET, NUMBER],
+            ],
+            expr_list: [
+                [expr],
+                [expr_list, COMMA, expr],
+            ],
+            expr: [
+                [or_expr],
+            ],
+            or_expr: [
+                [and_expr],
+                [or_expr, OR, and_expr],
+            ],
+            and_expr: [
+                [not_expr],
+                [and_expr, AND, not_expr],
+            ],
+            not_expr: [
+                [predicate],
+                [NOT, not_expr],
+            ],
+            predicate: [
+                [additive],
+                [additive, comparison, additive],
+                [additive, IS, NULL],
+                [additive, IS, NOT, NULL],
+                [additive, LIKE, additive],
+                [additive, NOT, LIKE, additive],
+                [additive, BETWEEN, additive, AND, additive],
+                [additive, IN, LPAREN, expr_list, RPAREN],
+                [additive, IN, LPAREN, query, RPAREN],
+                [additive, NOT, IN, LPAREN, expr_list, RPAREN],
+                [EXISTS, LPAREN, query, RPAREN],
+            ],
+            comparison: [ [EQ], [NE], [LT], [GT], [LE], [GE] ],
+            additive: [
+                [multiplicative],
+                [additive, PLUS, multiplicative],
+                [additive, MINUS, multiplicative],
+                [additive, CONCAT, multiplicative],
+            ],
+            multiplicative: [
+                [unary],
+                [multiplicative, STAR, unary],
+                [multiplicative, SLASH, unary],
+            ],
+            unary: [
+                [primary],
+                [MINUS, unary],
+                [PLUS, unary],
+            ],
+            primary: [
+                [NUMBER],
+                [STRING],
+                [NULL],
+                [qualified_name],
+                [IDENT, LPAREN, RPAREN],
+                [IDENT, LPAREN, STAR, RPAREN],
+                [IDENT, LPAREN, expr_list, RPAREN],
+                [IDENT, LPAREN, DISTINCT, expr_list, RPAREN],
+                [LPAREN, expr, RPAREN],
+                [LPAREN, query, RPAREN],
+                [case_expr],
+            ],
+            case_expr: [
+                [CASE, when_list, END],
+                [CASE, when_list, ELSE, expr, END],
+                [CASE, expr, when_list, END],
+                [CASE, expr, when_list, ELSE, expr, END],
+            ],
+            when_list: [
+                [WHEN, expr, THEN, expr],
+                [when_list, WHEN, expr, THEN, expr],
+            ],
+            insert_statement: [
+                [INSERT, INTO, qualified_name, VALUES, row_list],
+                [INSERT, INTO, qualified_name, LPAREN, ident_list, RPAREN, VALUES, row_list],
+                [INSERT, INTO, qualified_name, query],
+            ],
+            row_list: [
+                [LPAREN, expr_list, RPAREN],
+                [row_list, COMMA, LPAREN, expr_list, RPAREN],
+            ],
+            ident_list: [
+                [IDENT],
+                [ident_list, COMMA, IDENT],
+            ],
+            update_statement: [
+                [UPDATE, qualified_name, SET, assignment_list, where_clause],
+            ],
+            assignment_list: [
+                [IDENT, EQ, expr],
+                [assignment_list, COMMA, IDENT, EQ, expr],
+            ],
+            delete_statement: [
+                [DELETE, FROM, qualified_name, where_clause],
+            ],
+            create_statement: [
+                [CREATE, TABLE, qualified_name, LPAREN, column_list, RPAREN],
+            ],
+            column_list: [
+                [column_def],
+                [column_list, COMMA, column_def],
+            ],
+            column_def: [
+                [IDENT, IDENT],
+                [IDENT, IDENT, NOT, NULL],
+                [IDENT, IDENT, PRIMARY, KEY],
+                [PRIMARY, KEY, LPAREN, ident_list, RPAREN],
+            ],
+            drop_statement: [
+                [DROP, TABLE, qualified_name],
+            ],
+        }
+    }
 }
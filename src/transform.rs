@@ -0,0 +1,854 @@
+//! Grammar-to-grammar transformations, each of which also produces a
+//! mapping that reshapes parse trees of the new grammar back into parse trees
+//! of the old one.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use indexmap::IndexMap;
+use symbol::Symbol as Id;
+
+use crate::grammar::{GrammarError, Production};
+use crate::runtime::Tree;
+use crate::Grammar;
+
+/// A transformed grammar, and how to get back to the original.
+#[derive(Debug)]
+pub struct Transformed {
+    pub grammar: Grammar,
+    pub mapping: TreeMapping,
+}
+
+/// Reshapes parse trees of a transformed grammar into parse trees of the
+/// grammar it was transformed from.
+///
+/// To undo a chain of transformations, reshape with each mapping in reverse.
+#[derive(Clone, Debug)]
+pub struct TreeMapping {
+    /// How to build the original tree for every new production
+    templates: Vec<Template>,
+}
+
+/// How the node of a new production turns into a tree of the old grammar.
+///
+/// Some new nonterminals stand for the tail of an old production whose start
+/// was parsed further up the tree (after removing left recursion or left
+/// factoring). Their nodes are handed the old trees built so far, and refer
+/// to them with `Acc`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Template {
+    /// The old tree of one of the node's children
+    Child(usize),
+
+    /// One of the old trees handed down to the node
+    Acc(usize),
+
+    /// A node of an old production
+    Node(usize, Vec<Template>),
+
+    /// The old tree of the child at the given position, handed the trees
+    Continue(Vec<Template>, usize),
+}
+
+impl Template {
+    /// Replaces every `Child` and `Acc`, keeping the structure around them.
+    ///
+    /// The tail of a `Continue` is remapped like a child, and has to stay a
+    /// child.
+    fn rewrite(&self, child: &dyn Fn(usize) -> Template, acc: &dyn Fn(usize) -> Template) -> Self {
+        match self {
+            Template::Child(k) => child(*k),
+            Template::Acc(i) => acc(*i),
+            Template::Node(n, templates) => Template::Node(
+                *n,
+                templates.iter().map(|t| t.rewrite(child, acc)).collect(),
+            ),
+            Template::Continue(templates, tail) => {
+                let tail = match child(*tail) {
+                    Template::Child(tail) => tail,
+                    _ => unreachable!("the tail of a continuation was substituted"),
+                };
+                Template::Continue(
+                    templates.iter().map(|t| t.rewrite(child, acc)).collect(),
+                    tail,
+                )
+            }
+        }
+    }
+
+    /// Substitutes `inner`, the template of a production with `len` symbols,
+    /// for the child at `position`.
+    fn substitute(&self, position: usize, inner: &Template, len: usize) -> Self {
+        let inner = inner.rewrite(&|k| Template::Child(position + k), &Template::Acc);
+        self.rewrite(
+            &|k| {
+                if k < position {
+                    Template::Child(k)
+                } else if k == position {
+                    inner.clone()
+                } else {
+                    Template::Child(k + len - 1)
+                }
+            },
+            &Template::Acc,
+        )
+    }
+}
+
+impl TreeMapping {
+    /// Turns a parse tree of the new grammar into one of the old grammar.
+    ///
+    /// The nodes keep the spans of their children, and empty ones go where
+    /// the tree before them ends.
+    pub fn reshape(&self, tree: &Tree) -> Tree {
+        let mut position = tree.span().start;
+        self.reshape_with(tree, &[], &mut position)
+    }
+
+    /// Reshapes a tree, with `position` at the end of the last tree built.
+    fn reshape_with(&self, tree: &Tree, acc: &[Tree], position: &mut usize) -> Tree {
+        match tree {
+            Tree::Leaf(..) => tree.clone(),
+            Tree::Node(n, children, _) => self.build(&self.templates[*n], children, acc, position),
+        }
+    }
+
+    fn build(
+        &self,
+        template: &Template,
+        children: &[Tree],
+        acc: &[Tree],
+        position: &mut usize,
+    ) -> Tree {
+        let tree = match template {
+            Template::Child(k) => {
+                *position = children[*k].span().start;
+                self.reshape_with(&children[*k], &[], position)
+            }
+            Template::Acc(i) => acc[*i].clone(),
+            Template::Node(n, templates) => {
+                let start = *position;
+                let children = templates
+                    .iter()
+                    .map(|t| self.build(t, children, acc, position))
+                    .collect();
+                Tree::node(*n, children, start)
+            }
+            Template::Continue(templates, tail) => {
+                let handed = templates
+                    .iter()
+                    .map(|t| self.build(t, children, acc, position))
+                    .collect::<Vec<_>>();
+                self.reshape_with(&children[*tail], &handed, position)
+            }
+        };
+        *position = tree.span().end;
+        tree
+    }
+}
+
+/// A production being transformed: its symbols, and how it maps back.
+#[derive(Clone, Debug)]
+struct Rule {
+    symbols: Vec<Id>,
+    template: Template,
+}
+
+/// The productions of a grammar being transformed, by nonterminal.
+struct Rules {
+    rules: IndexMap<Id, Vec<Rule>>,
+    taken: HashSet<Id>,
+
+    /// The nonterminal every new nonterminal was named after
+    bases: HashMap<Id, Id>,
+}
+
+impl Rules {
+    /// The grammar's productions, each mapping to itself.
+    fn new(grammar: &Grammar) -> Result<Self, GrammarError> {
+        let grammar_symbols = grammar.grammar_symbols()?;
+        grammar.check_symbols(&grammar_symbols)?;
+        let epsilon = Id::from("\u{025b}");
+        let mut rules = IndexMap::new();
+        let mut n = 0;
+        for (nonterminal, productions) in grammar.productions.iter() {
+            let mut alternatives = Vec::new();
+            for production in productions {
+                let symbols = production
+                    .0
+                    .iter()
+                    .filter(|symbol| **symbol != epsilon)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                let template = Template::Node(n, (0..symbols.len()).map(Template::Child).collect());
+                alternatives.push(Rule { symbols, template });
+                n += 1;
+            }
+            rules.insert(*nonterminal, alternatives);
+        }
+        Ok(Rules {
+            rules,
+            taken: grammar_symbols.keys().cloned().collect(),
+            bases: HashMap::new(),
+        })
+    }
+
+    fn is_nonterminal(&self, symbol: &Id) -> bool {
+        self.rules.contains_key(symbol)
+    }
+
+    /// A new nonterminal named after `base` (or what `base` was named after),
+    /// to be inserted right after it.
+    fn fresh(&mut self, base: Id) -> Id {
+        let base = self.bases.get(&base).cloned().unwrap_or(base);
+        let name = (1..)
+            .map(|i| Id::from(format!("{}_{}", base, i)))
+            .find(|name| !self.taken.contains(name))
+            .unwrap();
+        self.taken.insert(name);
+        self.bases.insert(name, base);
+        name
+    }
+
+    /// Puts `new` right after `after` in the nonterminal order.
+    fn insert_after(&mut self, after: Id, new: Id, rules: Vec<Rule>) {
+        let mut entries = self.rules.drain(..).collect::<Vec<_>>();
+        let index = entries.iter().position(|(name, _)| *name == after).unwrap();
+        entries.insert(index + 1, (new, rules));
+        self.rules = entries.into_iter().collect();
+    }
+
+    /// Builds the new grammar, dropping productions that repeat an earlier
+    /// one of the same nonterminal.
+    fn finish(self, grammar: &Grammar) -> Transformed {
+        let mut productions = IndexMap::new();
+        let mut templates = Vec::new();
+        for (nonterminal, rules) in self.rules {
+            let mut seen = HashSet::new();
+            let mut alternatives = Vec::new();
+            for rule in rules {
+                if seen.insert(rule.symbols.clone()) {
+                    alternatives.push(Production(rule.symbols));
+                    templates.push(rule.template);
+                }
+            }
+            productions.insert(nonterminal, alternatives);
+        }
+        Transformed {
+            grammar: Grammar {
+                start_symbols: grammar.start_symbols.clone(),
+                terminals: grammar.terminals.clone(),
+                productions,
+                repair_costs: grammar.repair_costs.clone(),
+            },
+            mapping: TreeMapping { templates },
+        }
+    }
+}
+
+impl Grammar {
+    /// Removes direct and indirect left recursion, with the algorithm from
+    /// the dragon book (4.19).
+    ///
+    /// Every left recursive nonterminal A gets a new nonterminal `A_1` for
+    /// the repeated part: A -> A a | b becomes A -> b A_1, A_1 -> a A_1 | ɛ.
+    /// Like the dragon book's, this only terminates in a grammar without
+    /// left recursion if there are no cycles or ɛ-productions to begin with.
+    pub fn remove_left_recursion(&self) -> Result<Transformed, GrammarError> {
+        let mut rules = Rules::new(self)?;
+        let order = rules.rules.keys().cloned().collect::<Vec<_>>();
+        for (i, ai) in order.iter().enumerate() {
+            // substitute the productions of earlier nonterminals for A_i -> A_j g
+            for aj in order[..i].iter() {
+                let mut substituted = Vec::new();
+                for rule in rules.rules[ai].iter() {
+                    if rule.symbols.first() != Some(aj) {
+                        substituted.push(rule.clone());
+                        continue;
+                    }
+                    for inner in rules.rules[aj].iter() {
+                        let mut symbols = inner.symbols.clone();
+                        symbols.extend(rule.symbols[1..].iter().cloned());
+                        let template =
+                            rule.template
+                                .substitute(0, &inner.template, inner.symbols.len());
+                        substituted.push(Rule { symbols, template });
+                    }
+                }
+                rules.rules[ai] = substituted;
+            }
+
+            // then remove the immediate left recursion; A -> A does nothing
+            let (recursive, others): (Vec<_>, Vec<_>) = rules.rules[ai]
+                .iter()
+                .filter(|rule| rule.symbols != [*ai])
+                .cloned()
+                .partition(|rule| rule.symbols.first() == Some(ai));
+            if recursive.is_empty() {
+                continue;
+            }
+            let tail = rules.fresh(*ai);
+            rules.rules[ai] = others
+                .into_iter()
+                .map(|rule| {
+                    let len = rule.symbols.len();
+                    let mut symbols = rule.symbols;
+                    symbols.push(tail);
+                    Rule {
+                        symbols,
+                        template: Template::Continue(vec![rule.template], len),
+                    }
+                })
+                .collect();
+            let mut tails = recursive
+                .into_iter()
+                .map(|rule| {
+                    // the tree so far stands in for the leading A
+                    let template = rule.template.rewrite(
+                        &|k| {
+                            if k == 0 {
+                                Template::Acc(0)
+                            } else {
+                                Template::Child(k - 1)
+                            }
+                        },
+                        &Template::Acc,
+                    );
+                    let mut symbols = rule.symbols[1..].to_vec();
+                    let len = symbols.len();
+                    symbols.push(tail);
+                    Rule {
+                        symbols,
+                        template: Template::Continue(vec![template], len),
+                    }
+                })
+                .collect::<Vec<_>>();
+            tails.push(Rule {
+                symbols: Vec::new(),
+                template: Template::Acc(0),
+            });
+            rules.insert_after(*ai, tail, tails);
+        }
+        Ok(rules.finish(self))
+    }
+
+    /// Left-factors productions of a nonterminal that start with the same
+    /// symbols: A -> a b | a c becomes A -> a A_1, A_1 -> b | c.
+    pub fn left_factor(&self) -> Result<Transformed, GrammarError> {
+        let mut rules = Rules::new(self)?;
+        // how many trees each nonterminal is handed by its parent
+        let mut handed = HashMap::new();
+        let mut worklist = rules.rules.keys().cloned().collect::<VecDeque<_>>();
+        while let Some(nonterminal) = worklist.pop_front() {
+            let a = handed.get(&nonterminal).cloned().unwrap_or(0);
+            loop {
+                let alternatives = &rules.rules[&nonterminal];
+                let first = alternatives.iter().enumerate().find_map(|(i, rule)| {
+                    let first = rule.symbols.first()?;
+                    let shared = alternatives[i + 1..]
+                        .iter()
+                        .any(|other| other.symbols.first() == Some(first));
+                    if shared {
+                        Some(*first)
+                    } else {
+                        None
+                    }
+                });
+                let first = match first {
+                    Some(first) => first,
+                    None => break,
+                };
+
+                let (group, rest): (Vec<_>, Vec<_>) = alternatives
+                    .iter()
+                    .cloned()
+                    .enumerate()
+                    .partition(|(_, rule)| rule.symbols.first() == Some(&first));
+                let prefix_len = (1..)
+                    .find(|len| {
+                        let prefix = group[0].1.symbols.get(..*len);
+                        group
+                            .iter()
+                            .any(|(_, rule)| prefix.is_none() || rule.symbols.get(..*len) != prefix)
+                    })
+                    .unwrap()
+                    - 1;
+                let prefix = group[0].1.symbols[..prefix_len].to_vec();
+
+                let suffix = rules.fresh(nonterminal);
+                handed.insert(suffix, a + prefix_len);
+                let suffixes = group
+                    .iter()
+                    .map(|(_, rule)| Rule {
+                        symbols: rule.symbols[prefix_len..].to_vec(),
+                        // the prefix is handed down after what this was handed
+                        template: rule.template.rewrite(
+                            &|k| {
+                                if k < prefix_len {
+                                    Template::Acc(a + k)
+                                } else {
+                                    Template::Child(k - prefix_len)
+                                }
+                            },
+                            &Template::Acc,
+                        ),
+                    })
+                    .collect();
+
+                let mut symbols = prefix;
+                symbols.push(suffix);
+                let handed_down = (0..a)
+                    .map(Template::Acc)
+                    .chain((0..prefix_len).map(Template::Child))
+                    .collect();
+                let factored = Rule {
+                    symbols,
+                    template: Template::Continue(handed_down, prefix_len),
+                };
+
+                // the factored production takes the place of the group's first
+                let mut alternatives = rest;
+                alternatives.push((group[0].0, factored));
+                alternatives.sort_by_key(|(i, _)| *i);
+                rules.rules[&nonterminal] =
+                    alternatives.into_iter().map(|(_, rule)| rule).collect();
+                rules.insert_after(nonterminal, suffix, suffixes);
+                worklist.push_back(suffix);
+            }
+        }
+        Ok(rules.finish(self))
+    }
+
+    /// Removes ɛ-productions, adding a copy of every production with each
+    /// combination of its nullable symbols left out.
+    ///
+    /// A nullable start symbol S keeps deriving ɛ: it gets S -> ɛ, and if it
+    /// appears on a right hand side, the rest of its productions move to a
+    /// new `S_1`.
+    pub fn remove_epsilon_productions(&self) -> Result<Transformed, GrammarError> {
+        let analysis = self.analyze()?;
+        let mut rules = Rules::new(self)?;
+
+        // a fixed tree for every nullable nonterminal to derive ɛ with
+        let mut empty_trees: HashMap<Id, Template> = HashMap::new();
+        loop {
+            let mut changes = false;
+            for (nonterminal, alternatives) in rules.rules.iter() {
+                if empty_trees.contains_key(nonterminal) {
+                    continue;
+                }
+                let tree = alternatives.iter().find_map(|rule| {
+                    let children = rule
+                        .symbols
+                        .iter()
+                        .map(|symbol| empty_trees.get(symbol).cloned())
+                        .collect::<Option<Vec<_>>>()?;
+                    Some(
+                        rule.template
+                            .rewrite(&|k| children[k].clone(), &Template::Acc),
+                    )
+                });
+                if let Some(tree) = tree {
+                    empty_trees.insert(*nonterminal, tree);
+                    changes = true;
+                }
+            }
+            if !changes {
+                break;
+            }
+        }
+
+        // nonterminals that only ever derive ɛ disappear altogether
+        let empty_only = rules
+            .rules
+            .iter()
+            .filter(|(_, alternatives)| alternatives.iter().all(|rule| rule.symbols.is_empty()))
+            .map(|(nonterminal, _)| *nonterminal)
+            .collect::<HashSet<_>>();
+
+        for nonterminal in rules.rules.keys().cloned().collect::<Vec<_>>() {
+            let mut expanded = Vec::new();
+            for rule in rules.rules[&nonterminal].iter() {
+                let nullable = rule
+                    .symbols
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, symbol)| analysis.is_nullable(**symbol))
+                    .map(|(k, _)| k)
+                    .collect::<Vec<_>>();
+                // every subset of the nullable symbols to leave out, keeping
+                // as many as possible first
+                for subset in 0..1usize << nullable.len() {
+                    let omitted = nullable
+                        .iter()
+                        .enumerate()
+                        .filter(|(bit, _)| subset & (1 << bit) != 0)
+                        .map(|(_, k)| *k)
+                        .collect::<HashSet<_>>();
+                    let keeps_empty_only =
+                        rule.symbols.iter().enumerate().any(|(k, symbol)| {
+                            !omitted.contains(&k) && empty_only.contains(symbol)
+                        });
+                    if keeps_empty_only || omitted.len() == rule.symbols.len() {
+                        continue;
+                    }
+
+                    let mut positions = HashMap::new();
+                    let mut symbols = Vec::new();
+                    for (k, symbol) in rule.symbols.iter().enumerate() {
+                        if !omitted.contains(&k) {
+                            positions.insert(k, symbols.len());
+                            symbols.push(*symbol);
+                        }
+                    }
+                    let template = rule.template.rewrite(
+                        &|k| match positions.get(&k) {
+                            Some(k) => Template::Child(*k),
+                            None => empty_trees[&rule.symbols[k]].clone(),
+                        },
+                        &Template::Acc,
+                    );
+                    expanded.push(Rule { symbols, template });
+                }
+            }
+            rules.rules[&nonterminal] = expanded;
+        }
+        rules.rules.retain(|nonterminal, _| {
+            !empty_only.contains(nonterminal) || self.start_symbols.contains(nonterminal)
+        });
+
+        for start_symbol in self.start_symbols.iter() {
+            if !analysis.is_nullable(*start_symbol) {
+                continue;
+            }
+            let empty = Rule {
+                symbols: Vec::new(),
+                template: empty_trees[start_symbol].clone(),
+            };
+            let used = rules
+                .rules
+                .values()
+                .flatten()
+                .any(|rule| rule.symbols.contains(start_symbol));
+            if !used {
+                rules.rules[start_symbol].push(empty);
+                continue;
+            }
+
+            let body = rules.fresh(*start_symbol);
+            for rule in rules.rules.values_mut().flatten() {
+                for symbol in rule.symbols.iter_mut() {
+                    if symbol == start_symbol {
+                        *symbol = body;
+                    }
+                }
+            }
+            let alternatives = std::mem::take(&mut rules.rules[start_symbol]);
+            rules.insert_after(*start_symbol, body, alternatives);
+            rules.rules[start_symbol] = vec![
+                Rule {
+                    symbols: vec![body],
+                    template: Template::Child(0),
+                },
+                empty,
+            ];
+        }
+        Ok(rules.finish(self))
+    }
+
+    /// Removes unit productions A -> B, giving A a copy of every other
+    /// production of each nonterminal it reaches through them.
+    pub fn remove_unit_productions(&self) -> Result<Transformed, GrammarError> {
+        let mut rules = Rules::new(self)?;
+        let is_unit = |rules: &Rules, rule: &Rule| {
+            rule.symbols.len() == 1 && rules.is_nonterminal(&rule.symbols[0])
+        };
+
+        let mut replaced = IndexMap::new();
+        for nonterminal in rules.rules.keys() {
+            // the nonterminals reachable through unit productions, nearest
+            // first, with the chain of nodes leading down to them
+            let mut reached = vec![(*nonterminal, Template::Child(0))];
+            let mut i = 0;
+            while i < reached.len() {
+                let (current, wrapper) = reached[i].clone();
+                for rule in rules.rules[&current].iter() {
+                    if !is_unit(&rules, rule) {
+                        continue;
+                    }
+                    let next = rule.symbols[0];
+                    if reached.iter().all(|(seen, _)| *seen != next) {
+                        reached.push((next, wrapper.substitute(0, &rule.template, 1)));
+                    }
+                }
+                i += 1;
+            }
+
+            let mut alternatives = Vec::new();
+            for (current, wrapper) in reached {
+                for rule in rules.rules[&current].iter() {
+                    if !is_unit(&rules, rule) {
+                        alternatives.push(Rule {
+                            symbols: rule.symbols.clone(),
+                            template: wrapper.substitute(0, &rule.template, rule.symbols.len()),
+                        });
+                    }
+                }
+            }
+            replaced.insert(*nonterminal, alternatives);
+        }
+        rules.rules = replaced;
+        Ok(rules.finish(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Transformed;
+    use crate::runtime::Tree;
+    use crate::Grammar;
+
+    /// Checks that parsing with the transformed grammar and reshaping gives
+    /// the same trees as parsing with the original.
+    fn check_reshapes(original: Grammar, transformed: &Transformed, inputs: &[&str]) {
+        let original = original.build().unwrap();
+        assert!(original.conflicts.is_empty());
+        let parser = transformed.grammar.clone().build().unwrap();
+        assert!(parser.conflicts.is_empty(), "{}", transformed.grammar);
+        for input in inputs {
+            let tree = parser.interpret("S", input).unwrap();
+            assert_eq!(
+                transformed.mapping.reshape(&tree),
+                original.interpret("S", input).unwrap(),
+                "{:?}",
+                input
+            );
+        }
+    }
+
+    fn make_arith() -> Grammar {
+        make_grammar! {
+            start_symbols: [S],
+            terminals: {
+                Add: r"\+",
+                Mul: r"\*",
+                LP: r"\(",
+                RP: r"\)",
+                N: r"[0-9]",
+            },
+            productions: {
+                S: [ [S, Add, T], [T] ],
+                T: [ [T, Mul, F], [F] ],
+                F: [ [LP, S, RP], [N] ],
+            }
+        }
+    }
+
+    const ARITH_INPUTS: &[&str] = &["1", "1+2", "1+2*3", "(1+2)*3", "1*(2+3*4)+5"];
+
+    #[test]
+    fn test_left_recursion() {
+        let transformed = make_arith().remove_left_recursion().unwrap();
+        assert_eq!(
+            transformed.grammar.to_string(),
+            r#"make_grammar! {
+    start_symbols: [S],
+    terminals: {
+        Add: r"\+",
+        Mul: r"\*",
+        LP: r"\(",
+        RP: r"\)",
+        N: r"[0-9]",
+    },
+    productions: {
+        S: [ [T, S_1] ],
+        S_1: [ [Add, T, S_1], [] ],
+        T: [ [F, T_1] ],
+        T_1: [ [Mul, F, T_1], [] ],
+        F: [ [LP, S, RP], [N] ],
+    }
+}"#
+        );
+        check_reshapes(make_arith(), &transformed, ARITH_INPUTS);
+
+        // it's LL(1) now, and the LL parser's trees reshape too
+        let original = make_arith().build().unwrap();
+        let ll = transformed.grammar.clone().build_ll().unwrap();
+        for input in ARITH_INPUTS {
+            let tree = ll.parse("S", input).unwrap();
+            assert_eq!(
+                transformed.mapping.reshape(&tree),
+                original.interpret("S", input).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_indirect_left_recursion() {
+        let make = || {
+            make_grammar! {
+                start_symbols: [S],
+                terminals: {
+                    A: r"a",
+                    B: r"b",
+                    C: r"c",
+                    D: r"d",
+                    E: r"e",
+                },
+                productions: {
+                    S: [ [X, A], [B] ],
+                    X: [ [X, C], [S, D], [E] ],
+                }
+            }
+        };
+        let transformed = make().remove_left_recursion().unwrap();
+        let productions = transformed.grammar.to_string();
+        assert!(productions.contains("X: [ [B, D, X_1], [E, X_1] ],"));
+        assert!(productions.contains("X_1: [ [C, X_1], [A, D, X_1], [] ],"));
+
+        // the new grammar needs more than FOLLOW sets to parse, so build the
+        // trees by hand
+        let original = make().build().unwrap();
+        let leaf = |terminal, text: &str, at: usize| {
+            Tree::Leaf(terminal, text.to_owned(), at..at + text.len())
+        };
+        let ea = Tree::node(
+            0,
+            vec![
+                Tree::node(3, vec![leaf(4, "e", 0), Tree::node(6, vec![], 1)], 0),
+                leaf(0, "a", 1),
+            ],
+            0,
+        );
+        assert_eq!(
+            transformed.mapping.reshape(&ea),
+            original.interpret("S", "ea").unwrap()
+        );
+        let bdca = Tree::node(
+            0,
+            vec![
+                Tree::node(
+                    2,
+                    vec![
+                        leaf(1, "b", 0),
+                        leaf(3, "d", 1),
+                        Tree::node(4, vec![leaf(2, "c", 2), Tree::node(6, vec![], 3)], 2),
+                    ],
+                    0,
+                ),
+                leaf(0, "a", 3),
+            ],
+            0,
+        );
+        assert_eq!(
+            transformed.mapping.reshape(&bdca),
+            original.interpret("S", "bdca").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_left_factor() {
+        let make = || {
+            make_grammar! {
+                start_symbols: [S],
+                terminals: {
+                    A: r"a",
+                    B: r"b",
+                    C: r"c",
+                    D: r"d",
+                },
+                productions: {
+                    S: [ [A, B, C], [D], [A, B, D], [A, C], [A, B] ],
+                }
+            }
+        };
+        let transformed = make().left_factor().unwrap();
+        let productions = transformed.grammar.to_string();
+        assert!(productions.contains("S: [ [A, S_1], [D] ],"));
+        assert!(productions.contains("S_1: [ [B, S_2], [C] ],"));
+        assert!(productions.contains("S_2: [ [C], [D], [] ],"));
+        check_reshapes(make(), &transformed, &["abc", "d", "abd", "ac", "ab"]);
+    }
+
+    #[test]
+    fn test_epsilon_productions() {
+        let make = || {
+            make_grammar! {
+                start_symbols: [S],
+                terminals: {
+                    A: r"a",
+                    B: r"b",
+                    C: r"c",
+                },
+                productions: {
+                    S: [ [X, Y, C] ],
+                    X: [ [A], [ɛ] ],
+                    Y: [ [B], [Z] ],
+                    Z: [ [ɛ] ],
+                }
+            }
+        };
+        let transformed = make().remove_epsilon_productions().unwrap();
+        let productions = transformed.grammar.to_string();
+        assert!(productions.contains("S: [ [X, Y, C], [Y, C], [X, C], [C] ],"));
+        assert!(productions.contains("X: [ [A] ],"));
+        assert!(productions.contains("Y: [ [B] ],"));
+        assert!(!productions.contains("Z:"));
+        check_reshapes(make(), &transformed, &["abc", "bc", "ac", "c"]);
+
+        // a nullable start symbol still derives ɛ, but nothing else does
+        let make = || {
+            make_grammar! {
+                start_symbols: [S],
+                terminals: {
+                    A: r"a",
+                },
+                productions: {
+                    S: [ [A, S], [ɛ] ],
+                }
+            }
+        };
+        let transformed = make().remove_epsilon_productions().unwrap();
+        let productions = transformed.grammar.to_string();
+        assert!(productions.contains("S: [ [S_1], [] ],"));
+        assert!(productions.contains("S_1: [ [A, S_1], [A] ],"));
+        check_reshapes(make(), &transformed, &["", "a", "aaa"]);
+    }
+
+    #[test]
+    fn test_unit_productions() {
+        let make = || {
+            make_grammar! {
+                start_symbols: [S],
+                terminals: {
+                    Add: r"\+",
+                    N: r"n",
+                    X: r"x",
+                    Y: r"y",
+                },
+                productions: {
+                    S: [ [S, Add, N], [T] ],
+                    T: [ [U], [N, X] ],
+                    U: [ [Y] ],
+                }
+            }
+        };
+        let inputs = &["nx", "y", "y+n", "nx+n+n"];
+        let transformed = make().remove_unit_productions().unwrap();
+        let productions = transformed.grammar.to_string();
+        assert!(productions.contains("S: [ [S, Add, N], [N, X], [Y] ],"));
+        assert!(productions.contains("T: [ [N, X], [Y] ],"));
+        check_reshapes(make(), &transformed, inputs);
+
+        // transformations chain, reshaping with each mapping in reverse
+        let recursion = transformed.grammar.remove_left_recursion().unwrap();
+        let parser = recursion.grammar.clone().build().unwrap();
+        let original = make().build().unwrap();
+        for input in inputs {
+            let tree = parser.interpret("S", input).unwrap();
+            let tree = transformed
+                .mapping
+                .reshape(&recursion.mapping.reshape(&tree));
+            assert_eq!(tree, original.interpret("S", input).unwrap());
+        }
+    }
+}
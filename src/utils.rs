@@ -10,16 +10,20 @@ macro_rules! make_grammar {
                 [$($symbol:ident),* $(,)?]
             ),* $(,)?]
         ),* $(,)?}
+        $(, repair_costs: {
+            $($cname:ident: $cost:expr),* $(,)?
+        })?
         $(,)?
     } => {
         Grammar {
-            start_symbols: vec![$(symbol::Symbol::from(stringify!($start_symbol)))*],
+            start_symbols: vec![$(symbol::Symbol::from(stringify!($start_symbol)),)*],
             terminals: vec![$((symbol::Symbol::from(stringify!($tname)), $regex.to_owned()),)*].into_iter().collect(),
             productions: vec![$(
                 (symbol::Symbol::from(stringify!($ntname)), vec![
                     $(vec![$(symbol::Symbol::from(stringify!($symbol)),)*].into_iter().into(),)*
                 ]),
             )*].into_iter().collect(),
+            repair_costs: vec![$($((symbol::Symbol::from(stringify!($cname)), $cost),)*)*].into_iter().collect(),
         }
     }
 }
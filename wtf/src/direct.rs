@@ -0,0 +1,378 @@
+// Code was generated by pgen
+#![allow(dead_code, non_snake_case, unused, clippy::all)]
+use pgen::runtime::{self, Lexer, ParseError, Recovered, Stack, Tree};
+const PATTERNS: &[&str] = &[
+    "\\+",
+    "\\*",
+    "0",
+    "1",
+];
+pub const TERMINALS: &[&str] = &[
+    "Add",
+    "Mul",
+    "N0",
+    "N1",
+    "$",
+];
+pub const NONTERMINALS: &[&str] = &[
+    "E",
+    "B",
+];
+const MESSAGES: &[(usize, &str)] = &[
+    (0, "An expression has to start with a number."),
+    (1, "Expected an operator between these numbers."),
+    (2, "Expected an operator between these numbers."),
+    (5, "Expected a number after this operator."),
+    (6, "Expected a number after this operator."),
+];
+// State 0
+//   B -> • N0
+//   B -> • N1
+//   E -> • B
+//   E -> • E Add B
+//   E -> • E Mul B
+//   E' -> • E
+fn state_0(stack: &mut Stack<'_>) -> Result<bool, ParseError> {
+    match stack.lookahead()? {
+        // N0
+        2 => {
+            stack.shift(1)?;
+        }
+        // N1
+        3 => {
+            stack.shift(2)?;
+        }
+        _ => {
+            return Err(stack.error(&[2, 3]));
+        }
+    }
+    Ok(false)
+}
+fn expected_0(stack: &mut Vec<usize>, terminal: usize) -> bool {
+    match terminal {
+        2 | 3 => true,
+        _ => false,
+    }
+}
+// State 1
+//   B -> N0 •
+fn state_1(stack: &mut Stack<'_>) -> Result<bool, ParseError> {
+    match stack.lookahead()? {
+        // Add | Mul | $
+        0 | 1 | 4 => {
+            // reduce B -> N0
+            let uncovered = stack.reduce(3, 1);
+            stack.goto(goto_B(uncovered));
+        }
+        _ => {
+            return Err(stack.error(&[0, 1, 4]));
+        }
+    }
+    Ok(false)
+}
+fn expected_1(stack: &mut Vec<usize>, terminal: usize) -> bool {
+    match terminal {
+        0 | 1 | 4 => {
+            // reduce B -> N0
+            stack.truncate(stack.len() - 1);
+            let uncovered = *stack.last().unwrap();
+            stack.push(goto_B(uncovered));
+            EXPECTED[*stack.last().unwrap()](stack, terminal)
+        }
+        _ => false,
+    }
+}
+// State 2
+//   B -> N1 •
+fn state_2(stack: &mut Stack<'_>) -> Result<bool, ParseError> {
+    match stack.lookahead()? {
+        // Add | Mul | $
+        0 | 1 | 4 => {
+            // reduce B -> N1
+            let uncovered = stack.reduce(4, 1);
+            stack.goto(goto_B(uncovered));
+        }
+        _ => {
+            return Err(stack.error(&[0, 1, 4]));
+        }
+    }
+    Ok(false)
+}
+fn expected_2(stack: &mut Vec<usize>, terminal: usize) -> bool {
+    match terminal {
+        0 | 1 | 4 => {
+            // reduce B -> N1
+            stack.truncate(stack.len() - 1);
+            let uncovered = *stack.last().unwrap();
+            stack.push(goto_B(uncovered));
+            EXPECTED[*stack.last().unwrap()](stack, terminal)
+        }
+        _ => false,
+    }
+}
+// State 3
+//   E -> E • Add B
+//   E -> E • Mul B
+//   E' -> E •
+fn state_3(stack: &mut Stack<'_>) -> Result<bool, ParseError> {
+    match stack.lookahead()? {
+        // Add
+        0 => {
+            stack.shift(5)?;
+        }
+        // Mul
+        1 => {
+            stack.shift(6)?;
+        }
+        // $
+        4 => {
+            return Ok(true);
+        }
+        _ => {
+            return Err(stack.error(&[0, 1, 4]));
+        }
+    }
+    Ok(false)
+}
+fn expected_3(stack: &mut Vec<usize>, terminal: usize) -> bool {
+    match terminal {
+        0 | 1 | 4 => true,
+        _ => false,
+    }
+}
+// State 4
+//   E -> B •
+fn state_4(stack: &mut Stack<'_>) -> Result<bool, ParseError> {
+    match stack.lookahead()? {
+        // Add | Mul | $
+        0 | 1 | 4 => {
+            // reduce E -> B
+            let uncovered = stack.reduce(2, 1);
+            stack.goto(goto_E(uncovered));
+        }
+        _ => {
+            return Err(stack.error(&[0, 1, 4]));
+        }
+    }
+    Ok(false)
+}
+fn expected_4(stack: &mut Vec<usize>, terminal: usize) -> bool {
+    match terminal {
+        0 | 1 | 4 => {
+            // reduce E -> B
+            stack.truncate(stack.len() - 1);
+            let uncovered = *stack.last().unwrap();
+            stack.push(goto_E(uncovered));
+            EXPECTED[*stack.last().unwrap()](stack, terminal)
+        }
+        _ => false,
+    }
+}
+// State 5
+//   B -> • N0
+//   B -> • N1
+//   E -> E Add • B
+fn state_5(stack: &mut Stack<'_>) -> Result<bool, ParseError> {
+    match stack.lookahead()? {
+        // N0
+        2 => {
+            stack.shift(1)?;
+        }
+        // N1
+        3 => {
+            stack.shift(2)?;
+        }
+        _ => {
+            return Err(stack.error(&[2, 3]));
+        }
+    }
+    Ok(false)
+}
+fn expected_5(stack: &mut Vec<usize>, terminal: usize) -> bool {
+    match terminal {
+        2 | 3 => true,
+        _ => false,
+    }
+}
+// State 6
+//   B -> • N0
+//   B -> • N1
+//   E -> E Mul • B
+fn state_6(stack: &mut Stack<'_>) -> Result<bool, ParseError> {
+    match stack.lookahead()? {
+        // N0
+        2 => {
+            stack.shift(1)?;
+        }
+        // N1
+        3 => {
+            stack.shift(2)?;
+        }
+        _ => {
+            return Err(stack.error(&[2, 3]));
+        }
+    }
+    Ok(false)
+}
+fn expected_6(stack: &mut Vec<usize>, terminal: usize) -> bool {
+    match terminal {
+        2 | 3 => true,
+        _ => false,
+    }
+}
+// State 7
+//   E -> E Add B •
+fn state_7(stack: &mut Stack<'_>) -> Result<bool, ParseError> {
+    match stack.lookahead()? {
+        // Add | Mul | $
+        0 | 1 | 4 => {
+            // reduce E -> E Add B
+            let uncovered = stack.reduce(1, 3);
+            stack.goto(goto_E(uncovered));
+        }
+        _ => {
+            return Err(stack.error(&[0, 1, 4]));
+        }
+    }
+    Ok(false)
+}
+fn expected_7(stack: &mut Vec<usize>, terminal: usize) -> bool {
+    match terminal {
+        0 | 1 | 4 => {
+            // reduce E -> E Add B
+            stack.truncate(stack.len() - 3);
+            let uncovered = *stack.last().unwrap();
+            stack.push(goto_E(uncovered));
+            EXPECTED[*stack.last().unwrap()](stack, terminal)
+        }
+        _ => false,
+    }
+}
+// State 8
+//   E -> E Mul B •
+fn state_8(stack: &mut Stack<'_>) -> Result<bool, ParseError> {
+    match stack.lookahead()? {
+        // Add | Mul | $
+        0 | 1 | 4 => {
+            // reduce E -> E Mul B
+            let uncovered = stack.reduce(0, 3);
+            stack.goto(goto_E(uncovered));
+        }
+        _ => {
+            return Err(stack.error(&[0, 1, 4]));
+        }
+    }
+    Ok(false)
+}
+fn expected_8(stack: &mut Vec<usize>, terminal: usize) -> bool {
+    match terminal {
+        0 | 1 | 4 => {
+            // reduce E -> E Mul B
+            stack.truncate(stack.len() - 3);
+            let uncovered = *stack.last().unwrap();
+            stack.push(goto_E(uncovered));
+            EXPECTED[*stack.last().unwrap()](stack, terminal)
+        }
+        _ => false,
+    }
+}
+fn goto_E(state: usize) -> usize {
+    match state {
+        0 => 3,
+        _ => unreachable!(),
+    }
+}
+fn goto_B(state: usize) -> usize {
+    match state {
+        0 => 4,
+        5 => 7,
+        6 => 8,
+        _ => unreachable!(),
+    }
+}
+fn has_goto(state: usize, nonterminal: usize) -> bool {
+    match state {
+        0 => matches!(nonterminal, 0 | 1),
+        5 => matches!(nonterminal, 1),
+        6 => matches!(nonterminal, 1),
+        _ => false,
+    }
+}
+fn shift_error(state: usize) -> Option<usize> {
+    match state {
+        _ => None,
+    }
+}
+fn accepts(state: usize, terminal: usize) -> bool {
+    match state {
+        0 => matches!(terminal, 2 | 3),
+        1 => matches!(terminal, 0 | 1 | 4),
+        2 => matches!(terminal, 0 | 1 | 4),
+        3 => matches!(terminal, 0 | 1 | 4),
+        4 => matches!(terminal, 0 | 1 | 4),
+        5 => matches!(terminal, 2 | 3),
+        6 => matches!(terminal, 2 | 3),
+        7 => matches!(terminal, 0 | 1 | 4),
+        8 => matches!(terminal, 0 | 1 | 4),
+        _ => false,
+    }
+}
+const STATES: &[fn(&mut Stack<'_>) -> Result<bool, ParseError>] = &[
+    state_0,
+    state_1,
+    state_2,
+    state_3,
+    state_4,
+    state_5,
+    state_6,
+    state_7,
+    state_8,
+];
+const EXPECTED: &[fn(&mut Vec<usize>, usize) -> bool] = &[
+    expected_0,
+    expected_1,
+    expected_2,
+    expected_3,
+    expected_4,
+    expected_5,
+    expected_6,
+    expected_7,
+    expected_8,
+];
+pub struct Parser<'a> {
+    lexer: Lexer,
+    input: &'a str,
+}
+impl<'a> Parser<'a> {
+    pub fn new(input: &'a str) -> Self {
+let lexer = Lexer::new(PATTERNS).unwrap();
+Parser { lexer, input }
+}
+pub fn render_error(&self, error: &ParseError) -> String {
+error.render_with_messages(self.input, TERMINALS, MESSAGES)
+}
+pub fn parse_E(&mut self) -> Result<Tree, ParseError> {
+self.generic_parse(0).into_result()
+}
+pub fn parse_E_recovering(&mut self) -> Recovered {
+self.generic_parse(0)
+}
+pub fn expected_terminals(stack: &[usize]) -> Vec<usize> {
+(0..=4).filter(|terminal| EXPECTED[*stack.last().unwrap()](&mut stack.to_vec(), *terminal)).collect()
+}
+pub fn expected_nonterminals(stack: &[usize]) -> Vec<usize> {
+let shifting = (0..4).filter_map(|terminal| {
+let mut stack = stack.to_vec();
+let state = *stack.last().unwrap();
+if EXPECTED[state](&mut stack, terminal) { stack.last().cloned() } else { None }
+}).collect::<Vec<_>>();
+(0..2).filter(|nonterminal| shifting.iter().any(|state| has_goto(*state, *nonterminal))).collect()
+}
+    fn generic_parse(&mut self, starting_state: usize) -> Recovered {
+    let stack = match runtime::start(&self.lexer, self.input, starting_state) {
+        Ok(stack) => stack,
+        Err(recovered) => return recovered,
+    };
+    stack.run(|stack| STATES[stack.state()](stack), |stack, error| stack.recover(error, shift_error, accepts))
+}
+}
@@ -1,51 +1,213 @@
-// Code was generated by pgen
-use std::io::Read;
-use pgen::regex::RegexSet;
-const PATTERNS: &[&'static str] = &[
-    "\\+",
-    "\\*",
-    "0",
-    "1",
-];
-const ACTIONS: &[[i8;5]] = &[
-    [-2,-2,7,8,-2,],
-    [2,4,-2,-2,-1,],
-    [-2,-2,7,8,-2,],
-    [-2,-2,-2,-2,-2,],
-    [-2,-2,7,8,-2,],
-    [-2,-2,-2,-2,-2,],
-    [-2,-2,-2,-2,-2,],
-    [-2,-2,12,-2,-2,],
-    [-2,-2,-2,13,-2,],
-];
-const GOTOS: &[[i8;2]] = &[
-    [1,6,],
-    [-1,-1,],
-    [-1,3,],
-    [-1,-1,],
-    [-1,5,],
-    [-1,-1,],
-    [-1,-1,],
-    [-1,-1,],
-    [-1,-1,],
-];
-pub struct Parser<'a> {
-    regex_set: RegexSet,
-    position: usize,
-    input: &'a str,
-}
-impl<'a> Parser<'a> {
-    pub fn new(input: &'a str) -> Self {
-let regex_set = RegexSet::new(PATTERNS.clone()).unwrap();
-Parser { input: input.as_ref(), regex_set, position: 0 }
-}
-pub fn parse_E(&mut self) {
-self.generic_parse(0)
-}
-    fn generic_parse(&mut self, starting_state: usize) {
-    self.position = 0;
-    loop {
-    
-}
-}
+//! The example grammars from `pgen::this`, generated with each backend by
+//! running pgen.
+
+pub mod direct;
+pub mod ll;
+pub mod stmts;
+pub mod table;
+
+pub use crate::table::Parser;
+
+#[cfg(test)]
+mod tests {
+    use pgen::runtime::{ParseError, Tree};
+    use pgen::testing::inputs;
+    use proptest::prelude::*;
+
+    fn parse_both(input: &str) -> (Result<Tree, ParseError>, Result<Tree, ParseError>) {
+        let table = crate::table::Parser::new(input).parse_E();
+        let direct = crate::direct::Parser::new(input).parse_E();
+        (table, direct)
+    }
+
+    #[test]
+    fn test_backends_accept_the_same() {
+        for input in &["1", "0+1", "1+1*0", "0*1*1+0+1"] {
+            let (table, direct) = parse_both(input);
+            assert_eq!(table.unwrap(), direct.unwrap(), "{:?}", input);
+        }
+
+        let (tree, _) = parse_both("1+0");
+        assert_eq!(
+            tree.unwrap(),
+            Tree::Node(
+                1,
+                vec![
+                    Tree::Node(
+                        2,
+                        vec![Tree::Node(4, vec![Tree::Leaf(3, "1".to_owned(), 0..1)], 0..1)],
+                        0..1
+                    ),
+                    Tree::Leaf(0, "+".to_owned(), 1..2),
+                    Tree::Node(3, vec![Tree::Leaf(2, "0".to_owned(), 2..3)], 2..3),
+                ],
+                0..3
+            )
+        );
+    }
+
+    #[test]
+    fn test_backends_reject_the_same() {
+        for input in &["", "+", "1+", "11", "1+2", "*0"] {
+            let (table, direct) = parse_both(input);
+            let (table, direct) = (table.unwrap_err(), direct.unwrap_err());
+            assert_eq!(table, direct, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_custom_messages() {
+        // both backends have wtf/pgen.messages compiled in
+        for (input, message) in &[
+            ("", "An expression has to start with a number."),
+            ("1+*", "Expected a number after this operator."),
+            ("1*", "Expected a number after this operator."),
+            ("1+10", "Expected an operator between these numbers."),
+        ] {
+            let mut table = crate::table::Parser::new(input);
+            let error = table.parse_E().unwrap_err();
+            let rendered = table.render_error(&error);
+            assert!(rendered.starts_with(&format!("error: {}\n", message)), "{}", rendered);
+
+            let mut direct = crate::direct::Parser::new(input);
+            let error = direct.parse_E().unwrap_err();
+            assert_eq!(direct.render_error(&error), rendered);
+        }
+    }
+
+    #[test]
+    fn test_push_parser() {
+        use pgen::runtime::Completion;
+
+        let parser = pgen::this::pgen_grammar().build().unwrap();
+        let mut push = crate::table::Parser::push_E();
+        let mut interpreted = parser.push_parser("E");
+        for chunk in &["1", "+", "0*", "1"] {
+            push.push_str(chunk).unwrap();
+            interpreted.push_str(chunk).unwrap();
+            assert_eq!(push.completion(), interpreted.completion());
+        }
+        assert_eq!(push.completion(), Completion::Complete);
+        assert_eq!(push.finish(), interpreted.finish());
+
+        // it can be stopped anywhere to see what comes next
+        let mut push = crate::table::Parser::push_E();
+        let mut interpreted = parser.push_parser("E");
+        push.push_str("1+").unwrap();
+        interpreted.push_str("1+").unwrap();
+        assert_eq!(push.expected_terminals(), interpreted.expected_terminals());
+        let nonterminals = push.expected_nonterminals();
+        let nonterminals = nonterminals.iter().map(|n| crate::table::NONTERMINALS[*n]);
+        assert_eq!(nonterminals.collect::<Vec<_>>(), vec!["B"]);
+
+        let mut push = crate::table::Parser::push_E();
+        let error = push.push_str("1+*").unwrap_err();
+        assert_eq!(
+            error,
+            crate::table::Parser::new("1+*").parse_E().unwrap_err()
+        );
+        assert_eq!(push.input(), "");
+    }
+
+    #[test]
+    fn test_expected_matches_interpreter() {
+        let parser = pgen::this::pgen_grammar().build().unwrap();
+        let named = |numbers: Vec<usize>, names: &[&'static str]| {
+            numbers.into_iter().map(|n| names[n]).collect::<Vec<_>>()
+        };
+        for input in &["", "1+", "1+0*", "0*1+", "1+1*0+"] {
+            // the stack a push parser has, short of the token it holds back
+            let mut push = parser.push_parser("E");
+            push.push_str(input).unwrap();
+            let stack = push.states();
+
+            let terminals = parser.expected_terminals(stack);
+            let terminals = terminals.iter().map(|t| t.name().as_str()).collect::<Vec<_>>();
+            let nonterminals = parser.expected_nonterminals(stack);
+            let nonterminals = nonterminals.iter().map(|n| n.as_str()).collect::<Vec<_>>();
+            for (generated_terminals, generated_nonterminals) in &[
+                (
+                    crate::table::Parser::expected_terminals(stack),
+                    crate::table::Parser::expected_nonterminals(stack),
+                ),
+                (
+                    crate::direct::Parser::expected_terminals(stack),
+                    crate::direct::Parser::expected_nonterminals(stack),
+                ),
+            ] {
+                let generated = named(generated_terminals.clone(), crate::table::TERMINALS);
+                assert_eq!(generated, terminals, "{:?}", input);
+                let generated = named(generated_nonterminals.clone(), crate::table::NONTERMINALS);
+                assert_eq!(generated, nonterminals, "{:?}", input);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ll_matches_interpreter() {
+        let parser = pgen::this::ll_grammar().build_ll().unwrap();
+        for input in &["1", "1+2", "(1+2)*3", "4*(5+6*7)+8"] {
+            let tree = crate::ll::Parser::new(input).parse_E();
+            assert_eq!(tree.unwrap(), parser.parse("E", input).unwrap());
+        }
+        for input in &["", "+", "1+", "(1", "1)", "1 2"] {
+            let generated = crate::ll::Parser::new(input).parse_E().unwrap_err();
+            let interpreted = parser.parse("E", input).unwrap_err();
+            assert_eq!(generated, interpreted, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_recovery_matches_interpreter() {
+        let parser = pgen::this::stmts_grammar().build().unwrap();
+        for input in &["1+2;3", "1++2;3;+4", "+", ";;;", "1;+;+;+5", "1+x;2"] {
+            let generated = crate::stmts::Parser::new(input).parse_Stmts_recovering();
+            assert_eq!(generated, parser.interpret_recovering("Stmts", input), "{:?}", input);
+        }
+        let recovered = crate::stmts::Parser::new("1++2;3;+4").parse_Stmts_recovering();
+        assert!(recovered.tree.is_some());
+        assert_eq!(recovered.errors.len(), 2);
+    }
+
+    #[test]
+    fn test_repair() {
+        let parser = pgen::this::pgen_grammar().build().unwrap();
+        for input in &["1+0", "1++0", "1+", "*0*1", "1+x"] {
+            let generated = crate::table::Parser::new(input).parse_E_repairing();
+            assert_eq!(generated, parser.parse_repairing("E", input), "{:?}", input);
+        }
+        let repaired = crate::table::Parser::new("1++0").parse_E_repairing();
+        assert!(repaired.tree.is_some());
+        assert_eq!(repaired.errors.len(), 1);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        // the generated parsers accept what the Earley parser does
+        #[test]
+        fn test_generated_agree(input in inputs(&pgen::this::pgen_grammar(), "E", 2)) {
+            let grammar = pgen::this::pgen_grammar();
+            let accepted = grammar.build_earley().unwrap().recognize("E", &input).is_ok();
+            let (table, direct) = parse_both(&input);
+            prop_assert_eq!(table.is_ok(), accepted, "{:?}", input);
+            prop_assert_eq!(table, direct);
+        }
+
+        #[test]
+        fn test_generated_ll_agrees(input in inputs(&pgen::this::ll_grammar(), "E", 2)) {
+            let grammar = pgen::this::ll_grammar();
+            let accepted = grammar.build_earley().unwrap().recognize("E", &input).is_ok();
+            let parsed = crate::ll::Parser::new(&input).parse_E();
+            prop_assert_eq!(parsed.is_ok(), accepted, "{:?}", input);
+        }
+
+        #[test]
+        fn test_generated_stmts_agrees(input in inputs(&pgen::this::stmts_grammar(), "Stmts", 2)) {
+            let grammar = pgen::this::stmts_grammar();
+            let accepted = grammar.build_earley().unwrap().recognize("Stmts", &input).is_ok();
+            let parsed = crate::stmts::Parser::new(&input).parse_Stmts();
+            prop_assert_eq!(parsed.is_ok(), accepted, "{:?}", input);
+        }
+    }
 }
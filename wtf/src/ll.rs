@@ -0,0 +1,110 @@
+// Code was generated by pgen
+#![allow(dead_code, non_snake_case, unused, clippy::all)]
+use pgen::runtime::{Lexer, ParseError, Tokens, Tree};
+const PATTERNS: &[&str] = &[
+    "\\d+",
+    "\\+",
+    "\\*",
+    "\\(",
+    "\\)",
+];
+pub const TERMINALS: &[&str] = &[
+    "N",
+    "Add",
+    "Mul",
+    "LP",
+    "RP",
+    "$",
+];
+fn rule_E(tokens: &mut Tokens<'_>) -> Result<Tree, ParseError> {
+    match tokens.peek() {
+        // N | LP
+        0 | 3 => {
+            // E -> T E_
+            let children = vec![rule_T(tokens)?, rule_E_(tokens)?];
+            Ok(Tree::node(0, children, tokens.position()))
+        }
+        _ => Err(tokens.error(&[0, 3])),
+    }
+}
+fn rule_E_(tokens: &mut Tokens<'_>) -> Result<Tree, ParseError> {
+    match tokens.peek() {
+        // Add
+        1 => {
+            // E_ -> Add T E_
+            let children = vec![tokens.expect(1)?, rule_T(tokens)?, rule_E_(tokens)?];
+            Ok(Tree::node(1, children, tokens.position()))
+        }
+        // RP | $
+        4 | 5 => {
+            // E_ -> ɛ
+            let children = vec![];
+            Ok(Tree::node(2, children, tokens.position()))
+        }
+        _ => Err(tokens.error(&[1, 4, 5])),
+    }
+}
+fn rule_T(tokens: &mut Tokens<'_>) -> Result<Tree, ParseError> {
+    match tokens.peek() {
+        // N | LP
+        0 | 3 => {
+            // T -> F T_
+            let children = vec![rule_F(tokens)?, rule_T_(tokens)?];
+            Ok(Tree::node(3, children, tokens.position()))
+        }
+        _ => Err(tokens.error(&[0, 3])),
+    }
+}
+fn rule_T_(tokens: &mut Tokens<'_>) -> Result<Tree, ParseError> {
+    match tokens.peek() {
+        // Mul
+        2 => {
+            // T_ -> Mul F T_
+            let children = vec![tokens.expect(2)?, rule_F(tokens)?, rule_T_(tokens)?];
+            Ok(Tree::node(4, children, tokens.position()))
+        }
+        // Add | RP | $
+        1 | 4 | 5 => {
+            // T_ -> ɛ
+            let children = vec![];
+            Ok(Tree::node(5, children, tokens.position()))
+        }
+        _ => Err(tokens.error(&[1, 2, 4, 5])),
+    }
+}
+fn rule_F(tokens: &mut Tokens<'_>) -> Result<Tree, ParseError> {
+    match tokens.peek() {
+        // LP
+        3 => {
+            // F -> LP E RP
+            let children = vec![tokens.expect(3)?, rule_E(tokens)?, tokens.expect(4)?];
+            Ok(Tree::node(6, children, tokens.position()))
+        }
+        // N
+        0 => {
+            // F -> N
+            let children = vec![tokens.expect(0)?];
+            Ok(Tree::node(7, children, tokens.position()))
+        }
+        _ => Err(tokens.error(&[0, 3])),
+    }
+}
+pub struct Parser<'a> {
+    lexer: Lexer,
+    input: &'a str,
+}
+impl<'a> Parser<'a> {
+    pub fn new(input: &'a str) -> Self {
+let lexer = Lexer::new(PATTERNS).unwrap();
+Parser { lexer, input }
+}
+pub fn render_error(&self, error: &ParseError) -> String {
+error.render(self.input, TERMINALS)
+}
+pub fn parse_E(&mut self) -> Result<Tree, ParseError> {
+let mut tokens = Tokens::new(&self.lexer, self.input)?;
+let tree = rule_E(&mut tokens)?;
+tokens.end()?;
+Ok(tree)
+}
+}
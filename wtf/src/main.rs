@@ -1,8 +1,6 @@
-use std::io::Cursor;
-
 use wtf::Parser;
 
 fn main() {
-    let mut parser = Parser::new("1+2*3");
+    let mut parser = Parser::new("1+0*1");
     println!("{:?}", parser.parse_E());
 }
@@ -0,0 +1,400 @@
+// Code was generated by pgen
+#![allow(dead_code, non_snake_case, unused, clippy::all)]
+use pgen::runtime::{self, Lexer, ParseError, Recovered, Stack, Tree};
+const PATTERNS: &[&str] = &[
+    ";",
+    "\\+",
+    "\\d+",
+];
+pub const TERMINALS: &[&str] = &[
+    "Semi",
+    "Add",
+    "N",
+    "$",
+];
+pub const NONTERMINALS: &[&str] = &[
+    "Stmts",
+    "Stmt",
+    "E",
+];
+const MESSAGES: &[(usize, &str)] = &[
+];
+// State 0
+//   E -> • N
+//   E -> • E Add N
+//   Stmt -> • error
+//   Stmt -> • E
+//   Stmts -> • Stmt
+//   Stmts -> • Stmts Semi Stmt
+//   Stmts' -> • Stmts
+fn state_0(stack: &mut Stack<'_>) -> Result<bool, ParseError> {
+    match stack.lookahead()? {
+        // N
+        2 => {
+            stack.shift(1)?;
+        }
+        _ => {
+            return Err(stack.error(&[2]));
+        }
+    }
+    Ok(false)
+}
+fn expected_0(stack: &mut Vec<usize>, terminal: usize) -> bool {
+    match terminal {
+        2 => true,
+        _ => false,
+    }
+}
+// State 1
+//   E -> N •
+fn state_1(stack: &mut Stack<'_>) -> Result<bool, ParseError> {
+    match stack.lookahead()? {
+        // Semi | Add | $
+        0 | 1 | 3 => {
+            // reduce E -> N
+            let uncovered = stack.reduce(5, 1);
+            stack.goto(goto_E(uncovered));
+        }
+        _ => {
+            return Err(stack.error(&[0, 1, 3]));
+        }
+    }
+    Ok(false)
+}
+fn expected_1(stack: &mut Vec<usize>, terminal: usize) -> bool {
+    match terminal {
+        0 | 1 | 3 => {
+            // reduce E -> N
+            stack.truncate(stack.len() - 1);
+            let uncovered = *stack.last().unwrap();
+            stack.push(goto_E(uncovered));
+            EXPECTED[*stack.last().unwrap()](stack, terminal)
+        }
+        _ => false,
+    }
+}
+// State 2
+//   Stmt -> error •
+fn state_2(stack: &mut Stack<'_>) -> Result<bool, ParseError> {
+    match stack.lookahead()? {
+        // Semi | $
+        0 | 3 => {
+            // reduce Stmt -> error
+            let uncovered = stack.reduce(3, 1);
+            stack.goto(goto_Stmt(uncovered));
+        }
+        _ => {
+            return Err(stack.error(&[0, 3]));
+        }
+    }
+    Ok(false)
+}
+fn expected_2(stack: &mut Vec<usize>, terminal: usize) -> bool {
+    match terminal {
+        0 | 3 => {
+            // reduce Stmt -> error
+            stack.truncate(stack.len() - 1);
+            let uncovered = *stack.last().unwrap();
+            stack.push(goto_Stmt(uncovered));
+            EXPECTED[*stack.last().unwrap()](stack, terminal)
+        }
+        _ => false,
+    }
+}
+// State 3
+//   Stmts -> Stmts • Semi Stmt
+//   Stmts' -> Stmts •
+fn state_3(stack: &mut Stack<'_>) -> Result<bool, ParseError> {
+    match stack.lookahead()? {
+        // Semi
+        0 => {
+            stack.shift(6)?;
+        }
+        // $
+        3 => {
+            return Ok(true);
+        }
+        _ => {
+            return Err(stack.error(&[0, 3]));
+        }
+    }
+    Ok(false)
+}
+fn expected_3(stack: &mut Vec<usize>, terminal: usize) -> bool {
+    match terminal {
+        0 | 3 => true,
+        _ => false,
+    }
+}
+// State 4
+//   Stmts -> Stmt •
+fn state_4(stack: &mut Stack<'_>) -> Result<bool, ParseError> {
+    match stack.lookahead()? {
+        // Semi | $
+        0 | 3 => {
+            // reduce Stmts -> Stmt
+            let uncovered = stack.reduce(0, 1);
+            stack.goto(goto_Stmts(uncovered));
+        }
+        _ => {
+            return Err(stack.error(&[0, 3]));
+        }
+    }
+    Ok(false)
+}
+fn expected_4(stack: &mut Vec<usize>, terminal: usize) -> bool {
+    match terminal {
+        0 | 3 => {
+            // reduce Stmts -> Stmt
+            stack.truncate(stack.len() - 1);
+            let uncovered = *stack.last().unwrap();
+            stack.push(goto_Stmts(uncovered));
+            EXPECTED[*stack.last().unwrap()](stack, terminal)
+        }
+        _ => false,
+    }
+}
+// State 5
+//   E -> E • Add N
+//   Stmt -> E •
+fn state_5(stack: &mut Stack<'_>) -> Result<bool, ParseError> {
+    match stack.lookahead()? {
+        // Semi | $
+        0 | 3 => {
+            // reduce Stmt -> E
+            let uncovered = stack.reduce(2, 1);
+            stack.goto(goto_Stmt(uncovered));
+        }
+        // Add
+        1 => {
+            stack.shift(7)?;
+        }
+        _ => {
+            return Err(stack.error(&[0, 1, 3]));
+        }
+    }
+    Ok(false)
+}
+fn expected_5(stack: &mut Vec<usize>, terminal: usize) -> bool {
+    match terminal {
+        1 => true,
+        0 | 3 => {
+            // reduce Stmt -> E
+            stack.truncate(stack.len() - 1);
+            let uncovered = *stack.last().unwrap();
+            stack.push(goto_Stmt(uncovered));
+            EXPECTED[*stack.last().unwrap()](stack, terminal)
+        }
+        _ => false,
+    }
+}
+// State 6
+//   E -> • N
+//   E -> • E Add N
+//   Stmt -> • error
+//   Stmt -> • E
+//   Stmts -> Stmts Semi • Stmt
+fn state_6(stack: &mut Stack<'_>) -> Result<bool, ParseError> {
+    match stack.lookahead()? {
+        // N
+        2 => {
+            stack.shift(1)?;
+        }
+        _ => {
+            return Err(stack.error(&[2]));
+        }
+    }
+    Ok(false)
+}
+fn expected_6(stack: &mut Vec<usize>, terminal: usize) -> bool {
+    match terminal {
+        2 => true,
+        _ => false,
+    }
+}
+// State 7
+//   E -> E Add • N
+fn state_7(stack: &mut Stack<'_>) -> Result<bool, ParseError> {
+    match stack.lookahead()? {
+        // N
+        2 => {
+            stack.shift(9)?;
+        }
+        _ => {
+            return Err(stack.error(&[2]));
+        }
+    }
+    Ok(false)
+}
+fn expected_7(stack: &mut Vec<usize>, terminal: usize) -> bool {
+    match terminal {
+        2 => true,
+        _ => false,
+    }
+}
+// State 8
+//   Stmts -> Stmts Semi Stmt •
+fn state_8(stack: &mut Stack<'_>) -> Result<bool, ParseError> {
+    match stack.lookahead()? {
+        // Semi | $
+        0 | 3 => {
+            // reduce Stmts -> Stmts Semi Stmt
+            let uncovered = stack.reduce(1, 3);
+            stack.goto(goto_Stmts(uncovered));
+        }
+        _ => {
+            return Err(stack.error(&[0, 3]));
+        }
+    }
+    Ok(false)
+}
+fn expected_8(stack: &mut Vec<usize>, terminal: usize) -> bool {
+    match terminal {
+        0 | 3 => {
+            // reduce Stmts -> Stmts Semi Stmt
+            stack.truncate(stack.len() - 3);
+            let uncovered = *stack.last().unwrap();
+            stack.push(goto_Stmts(uncovered));
+            EXPECTED[*stack.last().unwrap()](stack, terminal)
+        }
+        _ => false,
+    }
+}
+// State 9
+//   E -> E Add N •
+fn state_9(stack: &mut Stack<'_>) -> Result<bool, ParseError> {
+    match stack.lookahead()? {
+        // Semi | Add | $
+        0 | 1 | 3 => {
+            // reduce E -> E Add N
+            let uncovered = stack.reduce(4, 3);
+            stack.goto(goto_E(uncovered));
+        }
+        _ => {
+            return Err(stack.error(&[0, 1, 3]));
+        }
+    }
+    Ok(false)
+}
+fn expected_9(stack: &mut Vec<usize>, terminal: usize) -> bool {
+    match terminal {
+        0 | 1 | 3 => {
+            // reduce E -> E Add N
+            stack.truncate(stack.len() - 3);
+            let uncovered = *stack.last().unwrap();
+            stack.push(goto_E(uncovered));
+            EXPECTED[*stack.last().unwrap()](stack, terminal)
+        }
+        _ => false,
+    }
+}
+fn goto_Stmts(state: usize) -> usize {
+    match state {
+        0 => 3,
+        _ => unreachable!(),
+    }
+}
+fn goto_Stmt(state: usize) -> usize {
+    match state {
+        0 => 4,
+        6 => 8,
+        _ => unreachable!(),
+    }
+}
+fn goto_E(state: usize) -> usize {
+    match state {
+        0 => 5,
+        6 => 5,
+        _ => unreachable!(),
+    }
+}
+fn has_goto(state: usize, nonterminal: usize) -> bool {
+    match state {
+        0 => matches!(nonterminal, 0 | 1 | 2),
+        6 => matches!(nonterminal, 1 | 2),
+        _ => false,
+    }
+}
+fn shift_error(state: usize) -> Option<usize> {
+    match state {
+        0 => Some(2),
+        6 => Some(2),
+        _ => None,
+    }
+}
+fn accepts(state: usize, terminal: usize) -> bool {
+    match state {
+        0 => matches!(terminal, 2),
+        1 => matches!(terminal, 0 | 1 | 3),
+        2 => matches!(terminal, 0 | 3),
+        3 => matches!(terminal, 0 | 3),
+        4 => matches!(terminal, 0 | 3),
+        5 => matches!(terminal, 0 | 1 | 3),
+        6 => matches!(terminal, 2),
+        7 => matches!(terminal, 2),
+        8 => matches!(terminal, 0 | 3),
+        9 => matches!(terminal, 0 | 1 | 3),
+        _ => false,
+    }
+}
+const STATES: &[fn(&mut Stack<'_>) -> Result<bool, ParseError>] = &[
+    state_0,
+    state_1,
+    state_2,
+    state_3,
+    state_4,
+    state_5,
+    state_6,
+    state_7,
+    state_8,
+    state_9,
+];
+const EXPECTED: &[fn(&mut Vec<usize>, usize) -> bool] = &[
+    expected_0,
+    expected_1,
+    expected_2,
+    expected_3,
+    expected_4,
+    expected_5,
+    expected_6,
+    expected_7,
+    expected_8,
+    expected_9,
+];
+pub struct Parser<'a> {
+    lexer: Lexer,
+    input: &'a str,
+}
+impl<'a> Parser<'a> {
+    pub fn new(input: &'a str) -> Self {
+let lexer = Lexer::new(PATTERNS).unwrap();
+Parser { lexer, input }
+}
+pub fn render_error(&self, error: &ParseError) -> String {
+error.render_with_messages(self.input, TERMINALS, MESSAGES)
+}
+pub fn parse_Stmts(&mut self) -> Result<Tree, ParseError> {
+self.generic_parse(0).into_result()
+}
+pub fn parse_Stmts_recovering(&mut self) -> Recovered {
+self.generic_parse(0)
+}
+pub fn expected_terminals(stack: &[usize]) -> Vec<usize> {
+(0..=3).filter(|terminal| EXPECTED[*stack.last().unwrap()](&mut stack.to_vec(), *terminal)).collect()
+}
+pub fn expected_nonterminals(stack: &[usize]) -> Vec<usize> {
+let shifting = (0..3).filter_map(|terminal| {
+let mut stack = stack.to_vec();
+let state = *stack.last().unwrap();
+if EXPECTED[state](&mut stack, terminal) { stack.last().cloned() } else { None }
+}).collect::<Vec<_>>();
+(0..3).filter(|nonterminal| shifting.iter().any(|state| has_goto(*state, *nonterminal))).collect()
+}
+    fn generic_parse(&mut self, starting_state: usize) -> Recovered {
+    let stack = match runtime::start(&self.lexer, self.input, starting_state) {
+        Ok(stack) => stack,
+        Err(recovered) => return recovered,
+    };
+    stack.run(|stack| STATES[stack.state()](stack), |stack, error| stack.recover(error, shift_error, accepts))
+}
+}
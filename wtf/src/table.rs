@@ -0,0 +1,108 @@
+// Code was generated by pgen
+#![allow(dead_code, non_snake_case, unused, clippy::all)]
+use pgen::runtime::{self, Comb, CombTables, DenseTables, Lexer, ParseError, Recovered, Tree};
+const PATTERNS: &[&str] = &[
+    "\\+",
+    "\\*",
+    "0",
+    "1",
+];
+pub const TERMINALS: &[&str] = &[
+    "Add",
+    "Mul",
+    "N0",
+    "N1",
+    "$",
+];
+pub const NONTERMINALS: &[&str] = &[
+    "E",
+    "B",
+];
+const MESSAGES: &[(usize, &str)] = &[
+    (0, "An expression has to start with a number."),
+    (1, "Expected an operator between these numbers."),
+    (2, "Expected an operator between these numbers."),
+    (5, "Expected a number after this operator."),
+    (6, "Expected a number after this operator."),
+];
+const PRODUCTIONS: &[(usize, usize)] = &[
+    (0, 3),
+    (0, 3),
+    (0, 1),
+    (1, 1),
+    (1, 1),
+];
+const REPAIR_COSTS: &[usize] = &[
+    1,
+    1,
+    1,
+    1,
+    1,
+];
+const ACTIONS: &[i8] = &[
+    -2,-2,1,2,-2,-2,
+    12,12,-2,-2,12,-2,
+    13,13,-2,-2,13,-2,
+    5,6,-2,-2,-1,-2,
+    11,11,-2,-2,11,-2,
+    -2,-2,1,2,-2,-2,
+    -2,-2,1,2,-2,-2,
+    10,10,-2,-2,10,-2,
+    9,9,-2,-2,9,-2,
+];
+const GOTOS: &[i8] = &[
+    3,4,
+    -1,-1,
+    -1,-1,
+    -1,-1,
+    -1,-1,
+    -1,7,
+    -1,8,
+    -1,-1,
+    -1,-1,
+];
+const DEFAULTS: &[i8] = &[
+    -1,-1,-1,-1,-1,-1,-1,-1,-1,
+];
+const TABLES: DenseTables<'static, i8, i8> = DenseTables {
+    n_states: 9,
+    n_terminals: 6,
+    n_nonterminals: 2,
+    actions: ACTIONS,
+    gotos: GOTOS,
+    defaults: DEFAULTS,
+};
+pub struct Parser<'a> {
+    lexer: Lexer,
+    input: &'a str,
+}
+impl<'a> Parser<'a> {
+    pub fn new(input: &'a str) -> Self {
+let lexer = Lexer::new(PATTERNS).unwrap();
+Parser { lexer, input }
+}
+pub fn render_error(&self, error: &ParseError) -> String {
+error.render_with_messages(self.input, TERMINALS, MESSAGES)
+}
+pub fn parse_E(&mut self) -> Result<Tree, ParseError> {
+self.generic_parse(0).into_result()
+}
+pub fn parse_E_recovering(&mut self) -> Recovered {
+self.generic_parse(0)
+}
+pub fn parse_E_repairing(&mut self) -> Recovered {
+runtime::parse_repairing(&TABLES, PRODUCTIONS, REPAIR_COSTS, &self.lexer, 0, self.input)
+}
+pub fn push_E() -> runtime::PushParser<'static, impl runtime::Tables> {
+runtime::PushParser::new(TABLES, PRODUCTIONS, Lexer::new(PATTERNS).unwrap(), 0)
+}
+pub fn expected_terminals(stack: &[usize]) -> Vec<usize> {
+runtime::expected_terminals(&TABLES, PRODUCTIONS, 4, stack)
+}
+pub fn expected_nonterminals(stack: &[usize]) -> Vec<usize> {
+runtime::expected_nonterminals(&TABLES, PRODUCTIONS, 4, stack)
+}
+    fn generic_parse(&mut self, starting_state: usize) -> Recovered {
+    runtime::parse_recovering(&TABLES, PRODUCTIONS, &self.lexer, starting_state, self.input)
+}
+}